@@ -1,7 +1,7 @@
 use anyhow::{anyhow, Result};
-use crab::compile::compile;
-use crab::parse::parse;
-use crab::quill::ArtifactType;
+use crab::compile::{analyse, compile, WarningConfig, WarningLevel, WarningName};
+use crab::parse::parse_with_libraries;
+use crab::quill::{ArtifactType, OptLevel};
 use glob::glob;
 use log::{debug, error, info, warn, LevelFilter};
 use simple_logger::SimpleLogger;
@@ -17,6 +17,10 @@ struct Args {
     #[structopt(parse(from_os_str))]
     paths: Vec<PathBuf>,
 
+    /// Library files, or directories of library files, whose unused functions are not warned about
+    #[structopt(short = "L", long = "lib", parse(from_os_str), number_of_values = 1)]
+    libraries: Vec<PathBuf>,
+
     // The number of occurrences of the `v/verbose` flag
     /// Verbose mode (-v, -vv, -vvv, etc.)
     #[structopt(short, long, parse(from_occurrences))]
@@ -26,10 +30,15 @@ struct Args {
     #[structopt(short, long, default_value = "out.exe")]
     output: PathBuf,
 
-    /// Perform an optimized build
+    /// Perform an optimized build, the same as -O3
     #[structopt(long)]
     release: bool,
 
+    /// The optimization level to build with, which takes precedence over --release {n}
+    /// Options: 0, 1, 2, 3, s
+    #[structopt(short = "O", long)]
+    opt_level: Option<OptLevel>,
+
     /// Where to find the c builtins library, which must be linked
     #[structopt(short, long)]
     c_builtins: PathBuf,
@@ -56,6 +65,26 @@ struct Args {
     #[cfg(not(debug_assertions))]
     #[structopt(long)]
     verify: bool,
+
+    /// Silence a warning, or group of warnings {n}
+    /// Options: all, unused, unused-variable, unused-param, unreachable-code, unused-function
+    #[structopt(short = "A", long, number_of_values = 1)]
+    allow: Vec<WarningName>,
+
+    /// Report a warning, or group of warnings, without failing the build {n}
+    /// Options: all, unused, unused-variable, unused-param, unreachable-code, unused-function
+    #[structopt(short = "W", long, number_of_values = 1)]
+    warn: Vec<WarningName>,
+
+    /// Fail the build if a warning, or group of warnings, is emitted {n}
+    /// Options: all, unused, unused-variable, unused-param, unreachable-code, unused-function
+    #[structopt(short = "D", long, number_of_values = 1)]
+    deny: Vec<WarningName>,
+
+    /// Keep count of the reference counted objects that are alive, so that __live_objects__() can report it {n}
+    /// This slows down every allocation, so it is meant for finding memory leaks
+    #[structopt(long)]
+    count_live_objects: bool,
 }
 
 #[derive(Debug)]
@@ -107,18 +136,35 @@ fn get_crabfiles(paths: Vec<PathBuf>) -> Vec<PathBuf> {
 
 fn handle_crabfile(
     crabfiles: &[PathBuf],
+    libraries: &[PathBuf],
     verify: bool,
     artifact_path: &Path,
     artifact_type: &ArtifactType,
+    opt_level: &OptLevel,
+    warning_config: WarningConfig,
+    count_live_objects: bool,
 ) -> Result<()> {
     // parse crabfile
     info!("Parsing crabfiles");
-    let parse_result = parse(crabfiles)?;
+    let parse_result = parse_with_libraries(crabfiles, libraries)?;
     debug!("Crabfiles parsed");
 
+    // type check every function before any ir is generated
+    debug!("Analysing crabfiles");
+    let typed_ast = analyse(parse_result)?;
+    debug!("Crabfiles analysed");
+
     // build llvm ir
     debug!("Generating IR");
-    compile(parse_result, artifact_path, &artifact_type, verify)?;
+    compile(
+        typed_ast,
+        artifact_path,
+        &artifact_type,
+        opt_level,
+        verify,
+        warning_config,
+        count_live_objects,
+    )?;
 
     info!("Successfully wrote intermediate artifact");
     Ok(())
@@ -128,9 +174,8 @@ fn clang_compile(
     artifact_path: &Path,
     output: &Path,
     c_builtins: &Path,
-    release: bool,
+    opt_level: &OptLevel,
 ) -> Result<()> {
-    let opt_level = if release { "-O3" } else { "-O0" };
     let cmd_output = Command::new("clang")
         .arg("-fuse-ld=lld-link")
         .args([
@@ -148,7 +193,7 @@ fn clang_compile(
                 .to_str()
                 .expect("Failed to stringify output"),
         ])
-        .arg(opt_level)
+        .arg(opt_level.get_flag())
         .arg(artifact_path)
         .output()?;
 
@@ -193,6 +238,13 @@ fn _main() -> Result<()> {
     info!("Compiling {:#?}", args.paths);
 
     let paths = get_crabfiles(args.paths);
+    let libraries = get_crabfiles(args.libraries);
+
+    // Deny takes precedence over warn, which takes precedence over allow
+    let warning_config = WarningConfig::default()
+        .with_level(&args.allow, WarningLevel::ALLOW)
+        .with_level(&args.warn, WarningLevel::WARN)
+        .with_level(&args.deny, WarningLevel::DENY);
 
     // Use debug_assertions to tell whether this is a debug or release build
     // If it is a debug build, enable verify by default, but override with the no_verify flag
@@ -202,6 +254,12 @@ fn _main() -> Result<()> {
     #[cfg(not(debug_assertions))]
     let verify = args.verify;
 
+    let opt_level = match (args.opt_level, args.release) {
+        (Some(opt_level), _) => opt_level,
+        (None, true) => OptLevel::O3,
+        (None, false) => OptLevel::O0,
+    };
+
     let artifact_type = match &args.output_type {
         OutputType::QIR => ArtifactType::QIR,
         OutputType::BITCODE => ArtifactType::Bitcode,
@@ -218,11 +276,20 @@ fn _main() -> Result<()> {
         }
     };
 
-    handle_crabfile(&paths, verify, &artifact_path, &artifact_type)?;
+    handle_crabfile(
+        &paths,
+        &libraries,
+        verify,
+        &artifact_path,
+        &artifact_type,
+        &opt_level,
+        warning_config,
+        args.count_live_objects,
+    )?;
 
     match args.output_type {
         OutputType::EXECUTABLE => {
-            clang_compile(&artifact_path, &args.output, &args.c_builtins, args.release)?
+            clang_compile(&artifact_path, &args.output, &args.c_builtins, &opt_level)?
         }
         _ => {} // Do nothing
     }
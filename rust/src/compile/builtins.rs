@@ -1,19 +1,26 @@
-use crate::compile::{CompileError, Result};
+use crate::compile::{
+    add_element_loop, add_release, add_retain, get_scalar_type, is_refcounted, CompileError, Result,
+};
 use crate::parse::ast::{CrabType, FuncSignature, Ident, StructId};
 use crate::quill::{
-    FnNib, IntCmpType, Nib, PolyQuillType, Quill, QuillBoolType, QuillFloatType, QuillFnType,
-    QuillIntType, QuillListType, QuillPointerType, QuillStructType, QuillVoidType,
+    self, FnNib, IntCmpType, Nib, PolyQuillType, Quill, QuillBoolType, QuillFloatType, QuillFnType,
+    QuillIntType, QuillListType, QuillPointerType, QuillStructType, QuillType, QuillValue,
+    QuillVoidType,
 };
 use crate::util::{
-    bool_struct_name, capacity_field_name, format_i_c_name, get_fn_name, inner_add_fn_name,
-    int_struct_name, length_field_name, length_fn_name, list_struct_name, magic_main_func_name,
-    main_func_name, new_list_name, operator_add_name, operator_lt_name, primitive_field_name,
-    printf_c_name, printf_crab_name, string_struct_name, strlen_c_name, to_string_name,
-    ListFunctional, MapFunctional,
+    as_char_fn_name, bool_struct_name, byte_at_fn_name, byte_struct_name, bytes_lt_fn_name,
+    capacity_field_name, char_struct_name, format_i_c_name, get_fn_name, inner_add_fn_name,
+    int_struct_name, length_field_name, length_fn_name, list_struct_name, live_objects_fn_name,
+    live_objects_global_name, magic_main_func_name, main_func_name, matches_at_fn_name,
+    new_list_name, new_string_name, operator_add_name, operator_div_name, operator_lt_name,
+    operator_mult_name, operator_sub_name, primitive_field_name, printf_c_name, printf_crab_name,
+    push_byte_fn_name, push_fn_name, push_str_fn_name, self_var_name, set_fn_name, slice_fn_name,
+    string_struct_name, strlen_c_name, to_byte_fn_name, to_int_fn_name, to_string_name,
+    truncate_fn_name, unbox_fn_name, ListFunctional, MapFunctional,
 };
 use lazy_static::lazy_static;
 use std::collections::HashMap;
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 
 type FnDefFn =
     fn(&mut Quill, &mut FnNib, caller_opt: Option<StructId>, tmpls: Vec<StructId>) -> Result<()>;
@@ -38,6 +45,18 @@ fn init_builtin_fn_map() -> FnNameMap {
             mangle_fn_name(&operator_add_name(), &int_struct_name()),
             add_int as FnDefFn,
         ),
+        (
+            mangle_fn_name(&operator_sub_name(), &int_struct_name()),
+            sub_int as FnDefFn,
+        ),
+        (
+            mangle_fn_name(&operator_mult_name(), &int_struct_name()),
+            mult_int as FnDefFn,
+        ),
+        (
+            mangle_fn_name(&operator_div_name(), &int_struct_name()),
+            div_int as FnDefFn,
+        ),
         (
             mangle_fn_name(&operator_lt_name(), &int_struct_name()),
             int_lt_fn as FnDefFn,
@@ -46,6 +65,30 @@ fn init_builtin_fn_map() -> FnNameMap {
             mangle_fn_name(&to_string_name(), &int_struct_name()),
             format_i as FnDefFn,
         ),
+        (
+            mangle_fn_name(&as_char_fn_name(), &int_struct_name()),
+            int_as_char_fn as FnDefFn,
+        ),
+        (
+            mangle_fn_name(&to_byte_fn_name(), &int_struct_name()),
+            int_to_byte_fn as FnDefFn,
+        ),
+        (
+            mangle_fn_name(&operator_lt_name(), &char_struct_name()),
+            char_lt_fn as FnDefFn,
+        ),
+        (
+            mangle_fn_name(&to_int_fn_name(), &char_struct_name()),
+            char_to_int_fn as FnDefFn,
+        ),
+        (
+            mangle_fn_name(&operator_lt_name(), &byte_struct_name()),
+            byte_lt_fn as FnDefFn,
+        ),
+        (
+            mangle_fn_name(&to_int_fn_name(), &byte_struct_name()),
+            byte_to_int_fn as FnDefFn,
+        ),
         (
             mangle_fn_name(&printf_crab_name(), ""),
             add_printf as FnDefFn,
@@ -55,8 +98,16 @@ fn init_builtin_fn_map() -> FnNameMap {
             add_new_list as FnDefFn,
         ),
         (
-            mangle_fn_name(&operator_add_name(), &list_struct_name()),
-            list_add_fn as FnDefFn,
+            mangle_fn_name(&live_objects_fn_name(), ""),
+            live_objects_fn as FnDefFn,
+        ),
+        (
+            mangle_fn_name(&push_fn_name(), &list_struct_name()),
+            list_push_fn as FnDefFn,
+        ),
+        (
+            mangle_fn_name(&slice_fn_name(), &list_struct_name()),
+            list_slice_fn as FnDefFn,
         ),
         (
             mangle_fn_name(&get_fn_name(), &list_struct_name()),
@@ -66,6 +117,14 @@ fn init_builtin_fn_map() -> FnNameMap {
             mangle_fn_name(&length_fn_name(), &list_struct_name()),
             list_len_fn as FnDefFn,
         ),
+        (
+            mangle_fn_name(&set_fn_name(), &list_struct_name()),
+            list_set_fn as FnDefFn,
+        ),
+        (
+            mangle_fn_name(&truncate_fn_name(), &list_struct_name()),
+            list_truncate_fn as FnDefFn,
+        ),
         (
             mangle_fn_name(&length_fn_name(), &string_struct_name()),
             // Cheating a little here
@@ -76,6 +135,34 @@ fn init_builtin_fn_map() -> FnNameMap {
             mangle_fn_name(&inner_add_fn_name(), &string_struct_name()),
             string_add_fn as FnDefFn,
         ),
+        (
+            mangle_fn_name(&new_string_name(), ""),
+            add_new_string as FnDefFn,
+        ),
+        (
+            mangle_fn_name(&push_str_fn_name(), &string_struct_name()),
+            string_push_str_fn as FnDefFn,
+        ),
+        (
+            mangle_fn_name(&push_byte_fn_name(), &string_struct_name()),
+            string_push_byte_fn as FnDefFn,
+        ),
+        (
+            mangle_fn_name(&byte_at_fn_name(), &string_struct_name()),
+            string_byte_at_fn as FnDefFn,
+        ),
+        (
+            mangle_fn_name(&slice_fn_name(), &string_struct_name()),
+            string_slice_fn as FnDefFn,
+        ),
+        (
+            mangle_fn_name(&matches_at_fn_name(), &string_struct_name()),
+            string_matches_at_fn as FnDefFn,
+        ),
+        (
+            mangle_fn_name(&bytes_lt_fn_name(), &string_struct_name()),
+            string_bytes_lt_fn as FnDefFn,
+        ),
     ]);
     map
 }
@@ -100,7 +187,21 @@ fn init_builtin_strct_map() -> StrctNameMap {
             bool_struct_name(),
             HashMap::from([(
                 primitive_field_name(),
-                StructTypeResolver::QuillType(QuillIntType::new(1).into()),
+                StructTypeResolver::QuillType(QuillBoolType::new().into()),
+            )]),
+        ),
+        (
+            char_struct_name(),
+            HashMap::from([(
+                primitive_field_name(),
+                StructTypeResolver::QuillType(QuillIntType::new(32).into()),
+            )]),
+        ),
+        (
+            byte_struct_name(),
+            HashMap::from([(
+                primitive_field_name(),
+                StructTypeResolver::QuillType(QuillIntType::new(8).into()),
             )]),
         ),
         (
@@ -170,25 +271,32 @@ fn resolve_type(ct: &CrabType, index: usize) -> Result<PolyQuillType> {
             CrabType::PRIM_INT => Ok(QuillIntType::new(64).into()),
             CrabType::PRIM_STR => unimplemented!(),
             CrabType::PRIM_BOOL => Ok(QuillBoolType::new().into()),
-            CrabType::SIMPLE(name) => Ok(QuillPointerType::new(QuillStructType::new(
-                StructId::from_name(name.clone()).mangle(),
-            ))
-            .into()),
-            CrabType::TMPL(name, tmpls) => Ok(QuillPointerType::new(QuillStructType::new(
-                StructId {
-                    name: name.clone(),
-                    tmpls: tmpls.clone().into_iter().try_fold(vec![], |tmpls, tmpl| {
-                        Result::Ok(tmpls.fpush(tmpl.try_into()?))
-                    })?,
-                }
-                .mangle(),
-            ))
-            .into()),
+            CrabType::SIMPLE(name) => Ok(get_element_type(&StructId::from_name(name.clone()))),
+            CrabType::TMPL(name, tmpls) => Ok(get_element_type(&StructId {
+                name: name.clone(),
+                tmpls: tmpls.clone().into_iter().try_fold(vec![], |tmpls, tmpl| {
+                    Result::Ok(tmpls.fpush(tmpl.try_into()?))
+                })?,
+            })),
         },
         _ => Err(CompileError::NotATmpl(ct.clone())),
     }
 }
 
+///
+/// Gets the quill type of the elements a List of the given type holds
+/// Ints and Bools are held as they are, and everything else is held as a pointer to its struct
+///
+/// Params:
+/// * `element_id` - The type of the List's elements
+///
+pub(super) fn get_element_type(element_id: &StructId) -> PolyQuillType {
+    match get_scalar_type(&element_id.clone().into()) {
+        Some(scalar_t) => scalar_t,
+        None => QuillPointerType::new(QuillStructType::new(element_id.mangle())).into(),
+    }
+}
+
 pub(super) fn add_builtin_definition(
     peter: &mut Quill,
     nib: &mut FnNib,
@@ -310,23 +418,328 @@ fn string_add_fn(
     Ok(())
 }
 
-fn add_new_list(
+///
+/// Appends a string to the end of another one, in place
+/// The buffer is only reallocated if it doesn't have room for the new string
+///
+/// Returns:
+/// Self, with a new reference, so that calls can be chained
+///
+fn string_push_str_fn(
     _: &mut Quill,
     nib: &mut FnNib,
     _: Option<StructId>,
-    tmpls: Vec<StructId>,
+    _: Vec<StructId>,
 ) -> Result<()> {
-    let capacity_param = nib.get_fn_param(
-        capacity_field_name(),
-        QuillPointerType::new(QuillStructType::new(int_struct_name())),
+    let self_str = nib.get_fn_param(
+        String::from("self"),
+        QuillPointerType::new(QuillStructType::new(string_struct_name())),
     );
-    let capacity = nib.get_value_from_struct(
-        &capacity_param,
+    let other_str = nib.get_fn_param(
+        String::from("other"),
+        QuillPointerType::new(QuillStructType::new(string_struct_name())),
+    );
+    let self_len =
+        nib.get_value_from_struct(&self_str, length_field_name(), QuillIntType::new(64))?;
+    let capacity =
+        nib.get_value_from_struct(&self_str, capacity_field_name(), QuillIntType::new(64))?;
+    let other_char_star = nib.get_value_from_struct(
+        &other_str,
         primitive_field_name(),
-        QuillIntType::new(64),
+        QuillPointerType::new(QuillIntType::new(8)),
+    )?;
+    let other_len =
+        nib.get_value_from_struct(&other_str, length_field_name(), QuillIntType::new(64))?;
+    let new_len = nib.int_add(&self_len, &other_len)?;
+
+    // Resize the buffer if needed
+    let mut then_nib = nib.create_child();
+    let new_char_star = then_nib.add_malloc(QuillListType::new_var_length(
+        QuillIntType::new(8),
+        new_len.clone(),
+    ));
+    let old_char_star = then_nib.get_value_from_struct(
+        &self_str,
+        primitive_field_name(),
+        QuillPointerType::new(QuillIntType::new(8)),
+    )?;
+    let zero = then_nib.const_int(64, 0);
+    then_nib.list_copy(&old_char_star, &new_char_star, &self_len, &zero)?;
+    then_nib.set_value_in_struct(&self_str, primitive_field_name(), &new_char_star)?;
+    then_nib.free(old_char_star);
+    then_nib.set_value_in_struct(&self_str, capacity_field_name(), &new_len)?;
+    let cond = nib.int_cmp(&new_len, &capacity, IntCmpType::UGT)?;
+    nib.add_cond_branch(&cond, then_nib, None);
+
+    // Copy other to the end of the buffer
+    let self_char_star = nib.get_value_from_struct(
+        &self_str,
+        primitive_field_name(),
+        QuillPointerType::new(QuillIntType::new(8)),
+    )?;
+    nib.list_copy(&other_char_star, &self_char_star, &other_len, &self_len)?;
+    nib.set_value_in_struct(&self_str, length_field_name(), &new_len)?;
+
+    add_retain(nib, &self_str)?;
+    nib.add_return(Some(&self_str));
+    Ok(())
+}
+
+///
+/// Appends a single byte to the end of a string, in place
+/// The buffer doubles in size when it runs out of capacity
+///
+/// Returns:
+/// Self, with a new reference, so that calls can be chained
+///
+fn string_push_byte_fn(
+    _: &mut Quill,
+    nib: &mut FnNib,
+    _: Option<StructId>,
+    _: Vec<StructId>,
+) -> Result<()> {
+    let self_str = nib.get_fn_param(
+        String::from("self"),
+        QuillPointerType::new(QuillStructType::new(string_struct_name())),
+    );
+    let byte = get_int_param(nib, "byte")?;
+    let self_len =
+        nib.get_value_from_struct(&self_str, length_field_name(), QuillIntType::new(64))?;
+    let capacity =
+        nib.get_value_from_struct(&self_str, capacity_field_name(), QuillIntType::new(64))?;
+
+    // Resize the buffer if needed
+    let mut then_nib = nib.create_child();
+    let one = then_nib.const_int(64, 1);
+    let double_capacity = then_nib.int_add(&capacity, &capacity)?;
+    let new_capacity = then_nib.int_add(&double_capacity, &one)?;
+    let new_char_star = then_nib.add_malloc(QuillListType::new_var_length(
+        QuillIntType::new(8),
+        new_capacity.clone(),
+    ));
+    let old_char_star = then_nib.get_value_from_struct(
+        &self_str,
+        primitive_field_name(),
+        QuillPointerType::new(QuillIntType::new(8)),
+    )?;
+    let zero = then_nib.const_int(64, 0);
+    then_nib.list_copy(&old_char_star, &new_char_star, &self_len, &zero)?;
+    then_nib.set_value_in_struct(&self_str, primitive_field_name(), &new_char_star)?;
+    then_nib.free(old_char_star);
+    then_nib.set_value_in_struct(&self_str, capacity_field_name(), &new_capacity)?;
+    let cond = nib.int_cmp(&self_len, &capacity, IntCmpType::EQ)?;
+    nib.add_cond_branch(&cond, then_nib, None);
+
+    // Write the byte after the end of the string
+    let self_char_star = nib.get_value_from_struct(
+        &self_str,
+        primitive_field_name(),
+        QuillPointerType::new(QuillIntType::new(8)),
+    )?;
+    let byte = nib.int_resize(&byte, 8);
+    nib.set_list_value(&self_char_star, &byte, &self_len)?;
+    let one = nib.const_int(64, 1);
+    let new_len = nib.int_add(&self_len, &one)?;
+    nib.set_value_in_struct(&self_str, length_field_name(), &new_len)?;
+
+    add_retain(nib, &self_str)?;
+    nib.add_return(Some(&self_str));
+    Ok(())
+}
+
+///
+/// Gets the byte at an index of a string, as an Int
+/// The index is not bounds checked
+///
+fn string_byte_at_fn(
+    _: &mut Quill,
+    nib: &mut FnNib,
+    _: Option<StructId>,
+    _: Vec<StructId>,
+) -> Result<()> {
+    let (_, self_char_star, _) = get_string_param(nib, "self")?;
+    let index = get_int_param(nib, "index")?;
+    let byte = nib.get_list_value(&self_char_star, &index, QuillIntType::new(8))?;
+    let byte = nib.int_resize(&byte, 64);
+    nib.add_return(Some(&byte));
+    Ok(())
+}
+
+///
+/// Copies `length` bytes of a string, starting at `start`, into a new string
+/// The range is not bounds checked
+///
+fn string_slice_fn(
+    _: &mut Quill,
+    nib: &mut FnNib,
+    _: Option<StructId>,
+    _: Vec<StructId>,
+) -> Result<()> {
+    let (_, self_char_star, _) = get_string_param(nib, "self")?;
+    let start = get_int_param(nib, "start")?;
+    let length = get_int_param(nib, "length")?;
+    let start_char_star = nib.list_offset(&self_char_star, &start);
+    let new_char_star = nib.add_malloc(QuillListType::new_var_length(
+        QuillIntType::new(8),
+        length.clone(),
+    ));
+    let zero = nib.const_int(64, 0);
+    nib.list_copy(&start_char_star, &new_char_star, &length, &zero)?;
+    let new_str = nib.add_malloc(QuillStructType::new(string_name_mangled()));
+    nib.set_value_in_struct(&new_str, primitive_field_name(), &new_char_star)?;
+    nib.set_value_in_struct(&new_str, length_field_name(), &length)?;
+    nib.set_value_in_struct(&new_str, capacity_field_name(), &length)?;
+    nib.add_return(Some(&new_str));
+    Ok(())
+}
+
+///
+/// Returns whether the bytes of a string starting at `index` are the same as the bytes of `other`
+/// The index is not bounds checked, so self must have at least `index + other.len()` bytes
+///
+fn string_matches_at_fn(
+    _: &mut Quill,
+    nib: &mut FnNib,
+    _: Option<StructId>,
+    _: Vec<StructId>,
+) -> Result<()> {
+    let (_, self_char_star, _) = get_string_param(nib, "self")?;
+    let index = get_int_param(nib, "index")?;
+    let (_, other_char_star, other_len) = get_string_param(nib, "other")?;
+    let start_char_star = nib.list_offset(&self_char_star, &index);
+    let cmp = nib.mem_cmp(&start_char_star, &other_char_star, &other_len);
+    let zero = nib.const_int(64, 0);
+    let result = nib.int_cmp(&cmp, &zero, IntCmpType::EQ)?;
+    nib.add_return(Some(&result));
+    Ok(())
+}
+
+///
+/// Returns whether the first `length` bytes of a string sort before the first `length` bytes of `other`
+/// Both strings must have at least `length` bytes
+///
+fn string_bytes_lt_fn(
+    _: &mut Quill,
+    nib: &mut FnNib,
+    _: Option<StructId>,
+    _: Vec<StructId>,
+) -> Result<()> {
+    let (_, self_char_star, _) = get_string_param(nib, "self")?;
+    let (_, other_char_star, _) = get_string_param(nib, "other")?;
+    let length = get_int_param(nib, "length")?;
+    let cmp = nib.mem_cmp(&self_char_star, &other_char_star, &length);
+    let zero = nib.const_int(64, 0);
+    let result = nib.int_cmp(&cmp, &zero, IntCmpType::SLT)?;
+    nib.add_return(Some(&result));
+    Ok(())
+}
+
+///
+/// Gets a String param, along with its buffer and its length
+///
+fn get_string_param(
+    nib: &mut FnNib,
+    name: &str,
+) -> Result<(
+    QuillValue<QuillPointerType>,
+    QuillValue<QuillPointerType>,
+    QuillValue<QuillIntType>,
+)> {
+    let string = nib.get_fn_param(
+        String::from(name),
+        QuillPointerType::new(QuillStructType::new(string_struct_name())),
+    );
+    let char_star = nib.get_value_from_struct(
+        &string,
+        primitive_field_name(),
+        QuillPointerType::new(QuillIntType::new(8)),
     )?;
+    let len = nib.get_value_from_struct(&string, length_field_name(), QuillIntType::new(64))?;
+    Ok((string, char_star, len))
+}
+
+///
+/// Gets the value of an Int param
+///
+fn get_int_param(nib: &mut FnNib, name: &str) -> Result<QuillValue<QuillIntType>> {
+    get_prim_param(nib, name, &int_struct_name(), 64)
+}
+
+///
+/// Gets the int held by a param, such as the value of an Int, Char or Byte
+/// Ints are passed as they are, while Chars and Bytes are structs wrapping their int
+///
+fn get_prim_param(
+    nib: &mut FnNib,
+    name: &str,
+    struct_name: &Ident,
+    bits: u32,
+) -> Result<QuillValue<QuillIntType>> {
+    if get_scalar_type(&CrabType::SIMPLE(struct_name.clone())).is_some() {
+        return Ok(nib.get_fn_param(String::from(name), QuillIntType::new(bits)));
+    }
+    let prim = nib.get_fn_param(
+        String::from(name),
+        QuillPointerType::new(QuillStructType::new(
+            StructId::from_name(struct_name.clone()).mangle(),
+        )),
+    );
+    Ok(nib.get_value_from_struct(&prim, primitive_field_name(), QuillIntType::new(bits))?)
+}
+
+///
+/// Returns a value of a primitive type, such as an Int, Bool, Char or Byte
+/// Ints and Bools are returned as they are, while Chars and Bytes are wrapped in a new struct
+///
+fn add_prim_return<T: QuillType>(
+    nib: &mut FnNib,
+    struct_name: Ident,
+    value: &QuillValue<T>,
+) -> Result<()> {
+    if get_scalar_type(&CrabType::SIMPLE(struct_name.clone())).is_some() {
+        nib.add_return(Some(value));
+        return Ok(());
+    }
+    let ret_val = nib.add_malloc(QuillStructType::new(
+        StructId::from_name(struct_name).mangle(),
+    ));
+    nib.set_value_in_struct(&ret_val, primitive_field_name(), value)?;
+    nib.add_return(Some(&ret_val));
+    Ok(())
+}
+
+///
+/// Creates an empty string with room for `capacity` bytes
+///
+fn add_new_string(
+    _: &mut Quill,
+    nib: &mut FnNib,
+    _: Option<StructId>,
+    _: Vec<StructId>,
+) -> Result<()> {
+    let capacity = get_int_param(nib, &capacity_field_name())?;
+    let char_star = nib.add_malloc(QuillListType::new_var_length(
+        QuillIntType::new(8),
+        capacity.clone(),
+    ));
+    let string = nib.add_malloc(QuillStructType::new(string_name_mangled()));
+    nib.set_value_in_struct(&string, primitive_field_name(), &char_star)?;
+    let zero = nib.const_int(64, 0);
+    nib.set_value_in_struct(&string, length_field_name(), &zero)?;
+    nib.set_value_in_struct(&string, capacity_field_name(), &capacity)?;
+    nib.add_return(Some(&string));
+    Ok(())
+}
+
+fn add_new_list(
+    _: &mut Quill,
+    nib: &mut FnNib,
+    _: Option<StructId>,
+    tmpls: Vec<StructId>,
+) -> Result<()> {
+    let capacity = get_int_param(nib, &capacity_field_name())?;
     let t_star = nib.add_malloc(QuillListType::new_var_length(
-        QuillPointerType::new(QuillStructType::new(tmpls[0].mangle())),
+        get_element_type(&tmpls[0]),
         capacity.clone(),
     ));
     let list = nib.add_malloc(QuillStructType::new(
@@ -344,7 +757,27 @@ fn add_new_list(
     Ok(())
 }
 
-fn list_add_fn(
+///
+/// Returns the number of reference counted values that have been malloc'd but not yet freed
+///
+fn live_objects_fn(
+    _: &mut Quill,
+    nib: &mut FnNib,
+    _: Option<StructId>,
+    _: Vec<StructId>,
+) -> Result<()> {
+    let count_ptr = nib.get_global(live_objects_global_name(), QuillIntType::new(64));
+    let count = nib.add_load(&count_ptr, QuillIntType::new(64))?;
+    nib.add_return(Some(&count));
+    Ok(())
+}
+
+///
+/// Appends an element to the end of the list, in place
+/// When the list is full, its elements are copied to a new buffer with a little over twice the capacity
+/// The list holds a reference to the element
+///
+fn list_push_fn(
     _: &mut Quill,
     nib: &mut FnNib,
     caller: Option<StructId>,
@@ -366,19 +799,20 @@ fn list_add_fn(
         nib.get_value_from_struct(&list, capacity_field_name(), QuillIntType::new(64))?;
 
     // Resize the array if needed
+    // One more than double, so that lists with no capacity can grow too
     let mut then_nib = nib.create_child();
-    let new_capacity = then_nib.int_add(&capacity, &capacity)?;
+    let double_capacity = then_nib.int_add(&capacity, &capacity)?;
+    let one = then_nib.const_int(64, 1);
+    let new_capacity = then_nib.int_add(&double_capacity, &one)?;
     then_nib.set_value_in_struct(&list, capacity_field_name(), &new_capacity)?;
     let new_t_star = then_nib.add_malloc(QuillListType::new_var_length(
-        QuillPointerType::new(QuillStructType::new(caller.tmpls[0].mangle())),
+        get_element_type(&caller.tmpls[0]),
         new_capacity,
     ));
     let old_t_star = then_nib.get_value_from_struct(
         &list,
         primitive_field_name(),
-        QuillPointerType::new(QuillPointerType::new(QuillStructType::new(
-            caller.tmpls[0].mangle(),
-        ))),
+        QuillPointerType::new(get_element_type(&caller.tmpls[0])),
     )?;
     let zero = then_nib.const_int(64, 0);
     then_nib.list_copy(&old_t_star, &new_t_star, &capacity, &zero)?;
@@ -390,28 +824,79 @@ fn list_add_fn(
     nib.add_cond_branch(&cond, then_nib, None);
 
     // Continue the rest of the function
-    let element = nib.get_fn_param(
-        Ident::from("element"),
-        QuillPointerType::new(QuillStructType::new(caller.tmpls[0].mangle())),
-    );
+    let element = nib.get_fn_param(Ident::from("element"), get_element_type(&caller.tmpls[0]));
     let t_star = nib.get_value_from_struct(
         &list,
         primitive_field_name(),
-        QuillPointerType::new(QuillPointerType::new(QuillStructType::new(
-            caller.tmpls[0].mangle(),
-        ))),
+        QuillPointerType::new(get_element_type(&caller.tmpls[0])),
     )?;
+    if is_refcounted(&caller.tmpls[0].clone().into()) {
+        add_retain(nib, &element.clone().try_into()?)?;
+    }
     nib.set_list_value(&t_star, &element, &length)?;
     let one = nib.const_int(64, 1);
     let new_len = nib.int_add(&length, &one)?;
     nib.set_value_in_struct(&list, length_field_name(), &new_len)?;
 
-    // Return nothing
+    // Return the list, with a new reference for the caller
+    add_retain(nib, &list)?;
     nib.add_return(Some(&list));
 
     Ok(())
 }
 
+///
+/// Copies `length` elements of the list, starting at `start`, into a new list with just enough capacity
+/// The new list holds its own reference to each element
+/// The range is not bounds checked
+///
+fn list_slice_fn(
+    _: &mut Quill,
+    nib: &mut FnNib,
+    caller: Option<StructId>,
+    _: Vec<StructId>,
+) -> Result<()> {
+    let caller = caller.unwrap();
+    let list_id = StructId {
+        name: list_struct_name(),
+        tmpls: caller.tmpls.clone(),
+    };
+    let list = nib.get_fn_param(
+        Ident::from("self"),
+        QuillPointerType::new(QuillStructType::new(list_id.mangle())),
+    );
+    let start = get_int_param(nib, "start")?;
+    let length = get_int_param(nib, "length")?;
+    let t_star = nib.get_value_from_struct(
+        &list,
+        primitive_field_name(),
+        QuillPointerType::new(get_element_type(&caller.tmpls[0])),
+    )?;
+    let start_t_star = nib.list_offset(&t_star, &start);
+    let new_t_star = nib.add_malloc(QuillListType::new_var_length(
+        get_element_type(&caller.tmpls[0]),
+        length.clone(),
+    ));
+    let zero = nib.const_int(64, 0);
+    nib.list_copy(&start_t_star, &new_t_star, &length, &zero)?;
+    if is_refcounted(&caller.tmpls[0].clone().into()) {
+        add_element_loop(nib, &new_t_star, &zero, &length, |loop_nib, element| {
+            add_retain(loop_nib, element)
+        })?;
+    }
+
+    let new_list = nib.add_malloc(QuillStructType::new(list_id.mangle()));
+    nib.set_value_in_struct(&new_list, primitive_field_name(), &new_t_star)?;
+    nib.set_value_in_struct(&new_list, length_field_name(), &length)?;
+    nib.set_value_in_struct(&new_list, capacity_field_name(), &length)?;
+    nib.add_return(Some(&new_list));
+    Ok(())
+}
+
+///
+/// Gets the element of the list at a given index, with a new reference for the caller
+/// The index is not bounds checked
+///
 fn list_get_fn(
     _: &mut Quill,
     nib: &mut FnNib,
@@ -429,24 +914,100 @@ fn list_get_fn(
             .mangle(),
         )),
     );
-    let index = nib.get_fn_param(
-        Ident::from("index"),
-        QuillPointerType::new(QuillStructType::new(int_struct_name())),
-    );
+    let index = get_int_param(nib, "index")?;
     let t_star = nib.get_value_from_struct(
         &list,
         primitive_field_name(),
-        QuillPointerType::new(QuillPointerType::new(QuillStructType::new(
-            caller.tmpls[0].mangle(),
-        ))),
+        QuillPointerType::new(get_element_type(&caller.tmpls[0])),
     )?;
-    let index_value =
-        nib.get_value_from_struct(&index, primitive_field_name(), QuillIntType::new(64))?;
-    let value = nib.get_list_value(&t_star, &index_value, t_star.get_type().get_inner_type())?;
+    let value = nib.get_list_value(&t_star, &index, get_element_type(&caller.tmpls[0]))?;
+    if is_refcounted(&caller.tmpls[0].clone().into()) {
+        add_retain(nib, &value.clone().try_into()?)?;
+    }
     nib.add_return(Some(&value));
     Ok(())
 }
 
+///
+/// Replaces the element of the list at a given index, releasing the element that was there
+/// Like get, the index is not bounds checked
+///
+fn list_set_fn(
+    _: &mut Quill,
+    nib: &mut FnNib,
+    caller: Option<StructId>,
+    _: Vec<StructId>,
+) -> Result<()> {
+    let caller = caller.unwrap();
+    let list = nib.get_fn_param(
+        Ident::from("self"),
+        QuillPointerType::new(QuillStructType::new(
+            StructId {
+                name: list_struct_name(),
+                tmpls: caller.tmpls.clone(),
+            }
+            .mangle(),
+        )),
+    );
+    let index = get_int_param(nib, "index")?;
+    let element = nib.get_fn_param(Ident::from("element"), get_element_type(&caller.tmpls[0]));
+    let t_star = nib.get_value_from_struct(
+        &list,
+        primitive_field_name(),
+        QuillPointerType::new(get_element_type(&caller.tmpls[0])),
+    )?;
+    let old_element = nib.get_list_value(&t_star, &index, get_element_type(&caller.tmpls[0]))?;
+    nib.set_list_value(&t_star, &element, &index)?;
+    if is_refcounted(&caller.tmpls[0].clone().into()) {
+        add_retain(nib, &element.try_into()?)?;
+        add_release(nib, &old_element.try_into()?, &caller.tmpls[0].mangle());
+    }
+    nib.add_return(QuillFnType::void_return_value());
+    Ok(())
+}
+
+///
+/// Shrinks the list to the given length, keeping its capacity
+/// The elements past the new length are released
+/// The length must not be more than the list's current length
+///
+fn list_truncate_fn(
+    _: &mut Quill,
+    nib: &mut FnNib,
+    caller: Option<StructId>,
+    _: Vec<StructId>,
+) -> Result<()> {
+    let caller = caller.unwrap();
+    let list = nib.get_fn_param(
+        Ident::from("self"),
+        QuillPointerType::new(QuillStructType::new(
+            StructId {
+                name: list_struct_name(),
+                tmpls: caller.tmpls.clone(),
+            }
+            .mangle(),
+        )),
+    );
+    let length = get_int_param(nib, "length")?;
+    let old_length =
+        nib.get_value_from_struct(&list, length_field_name(), QuillIntType::new(64))?;
+    let t_star = nib.get_value_from_struct(
+        &list,
+        primitive_field_name(),
+        QuillPointerType::new(get_element_type(&caller.tmpls[0])),
+    )?;
+    if is_refcounted(&caller.tmpls[0].clone().into()) {
+        let element_struct = caller.tmpls[0].mangle();
+        add_element_loop(nib, &t_star, &length, &old_length, |loop_nib, element| {
+            add_release(loop_nib, element, &element_struct);
+            Ok(())
+        })?;
+    }
+    nib.set_value_in_struct(&list, length_field_name(), &length)?;
+    nib.add_return(QuillFnType::void_return_value());
+    Ok(())
+}
+
 fn list_len_fn(
     _: &mut Quill,
     nib: &mut FnNib,
@@ -466,54 +1027,128 @@ fn list_len_fn(
     );
 
     let list_len = nib.get_value_from_struct(&list, length_field_name(), QuillIntType::new(64))?;
-    let len_str = nib.add_malloc(QuillStructType::new(int_name_mangled()));
-    nib.set_value_in_struct(&len_str, primitive_field_name(), &list_len)?;
-    nib.add_return(Some(&len_str));
+    nib.add_return(Some(&list_len));
 
     Ok(())
 }
 
 fn int_lt_fn(_: &mut Quill, nib: &mut FnNib, _: Option<StructId>, _: Vec<StructId>) -> Result<()> {
-    let self_arg = nib.get_fn_param(
-        String::from("self"),
-        QuillPointerType::new(QuillStructType::new(int_name_mangled())),
-    );
-    let other_arg = nib.get_fn_param(
-        String::from("other"),
-        QuillPointerType::new(QuillStructType::new(int_name_mangled())),
-    );
-    let ret_val = nib.add_malloc(QuillStructType::new(bool_name_mangled()));
+    prim_lt(nib, int_struct_name(), 64)
+}
+
+fn char_lt_fn(_: &mut Quill, nib: &mut FnNib, _: Option<StructId>, _: Vec<StructId>) -> Result<()> {
+    prim_lt(nib, char_struct_name(), 32)
+}
 
-    let self_int =
-        nib.get_value_from_struct(&self_arg, primitive_field_name(), QuillIntType::new(64))?;
-    let other_int =
-        nib.get_value_from_struct(&other_arg, primitive_field_name(), QuillIntType::new(64))?;
+fn byte_lt_fn(_: &mut Quill, nib: &mut FnNib, _: Option<StructId>, _: Vec<StructId>) -> Result<()> {
+    prim_lt(nib, byte_struct_name(), 8)
+}
+
+///
+/// Compares self and other, which both hold an unsigned int
+///
+/// Params:
+/// * `struct_name` - The name of the struct that wraps the int
+/// * `bits` - The bit width of the wrapped int
+///
+fn prim_lt(nib: &mut FnNib, struct_name: Ident, bits: u32) -> Result<()> {
+    let self_int = get_prim_param(nib, "self", &struct_name, bits)?;
+    let other_int = get_prim_param(nib, "other", &struct_name, bits)?;
     let result = nib.int_cmp(&self_int, &other_int, IntCmpType::ULT)?;
-    nib.set_value_in_struct(&ret_val, primitive_field_name(), &result)?;
-    nib.add_return(Some(&ret_val));
+    nib.add_return(Some(&result));
     Ok(())
 }
 
+///
+/// Truncates an Int to the bits of a Char
+/// This does not check that the Int is a valid Unicode scalar value, Int::toChar does that
+///
+fn int_as_char_fn(
+    _: &mut Quill,
+    nib: &mut FnNib,
+    _: Option<StructId>,
+    _: Vec<StructId>,
+) -> Result<()> {
+    prim_resize(nib, (int_struct_name(), 64), (char_struct_name(), 32))
+}
+
+///
+/// Truncates an Int to its lowest 8 bits
+///
+fn int_to_byte_fn(
+    _: &mut Quill,
+    nib: &mut FnNib,
+    _: Option<StructId>,
+    _: Vec<StructId>,
+) -> Result<()> {
+    prim_resize(nib, (int_struct_name(), 64), (byte_struct_name(), 8))
+}
+
+fn char_to_int_fn(
+    _: &mut Quill,
+    nib: &mut FnNib,
+    _: Option<StructId>,
+    _: Vec<StructId>,
+) -> Result<()> {
+    prim_resize(nib, (char_struct_name(), 32), (int_struct_name(), 64))
+}
+
+fn byte_to_int_fn(
+    _: &mut Quill,
+    nib: &mut FnNib,
+    _: Option<StructId>,
+    _: Vec<StructId>,
+) -> Result<()> {
+    prim_resize(nib, (byte_struct_name(), 8), (int_struct_name(), 64))
+}
+
+///
+/// Converts self, which holds an unsigned int, into a primitive holding an int of another width
+///
+/// Params:
+/// * `from` - The name of the struct self is, and the bit width of the int it holds
+/// * `to` - The name of the struct to return, and the bit width of the int it holds
+///
+fn prim_resize(nib: &mut FnNib, from: (Ident, u32), to: (Ident, u32)) -> Result<()> {
+    let (from_name, from_bits) = from;
+    let (to_name, to_bits) = to;
+    let self_int = get_prim_param(nib, "self", &from_name, from_bits)?;
+    let result = nib.int_resize(&self_int, to_bits);
+    add_prim_return(nib, to_name, &result)
+}
+
 fn add_int(_: &mut Quill, nib: &mut FnNib, _: Option<StructId>, _: Vec<StructId>) -> Result<()> {
-    let self_arg = nib.get_fn_param(
-        String::from("self"),
-        QuillPointerType::new(QuillStructType::new(int_name_mangled())),
-    );
-    let other_arg = nib.get_fn_param(
-        String::from("other"),
-        QuillPointerType::new(QuillStructType::new(int_name_mangled())),
-    );
+    int_arithmetic(nib, FnNib::int_add)
+}
+
+fn sub_int(_: &mut Quill, nib: &mut FnNib, _: Option<StructId>, _: Vec<StructId>) -> Result<()> {
+    int_arithmetic(nib, FnNib::int_sub)
+}
 
-    let self_int =
-        nib.get_value_from_struct(&self_arg, primitive_field_name(), QuillIntType::new(64))?;
-    let other_int =
-        nib.get_value_from_struct(&other_arg, primitive_field_name(), QuillIntType::new(64))?;
+fn mult_int(_: &mut Quill, nib: &mut FnNib, _: Option<StructId>, _: Vec<StructId>) -> Result<()> {
+    int_arithmetic(nib, FnNib::int_mul)
+}
 
-    let result_int = nib.int_add(&self_int, &other_int)?;
+fn div_int(_: &mut Quill, nib: &mut FnNib, _: Option<StructId>, _: Vec<StructId>) -> Result<()> {
+    int_arithmetic(nib, FnNib::int_div)
+}
 
-    let ret_val = nib.add_malloc(QuillStructType::new(int_name_mangled()));
-    nib.set_value_in_struct(&ret_val, primitive_field_name(), &result_int)?;
-    nib.add_return(Some(&ret_val));
+///
+/// Builds an Int operator that combines self and other into a new Int
+///
+fn int_arithmetic(
+    nib: &mut FnNib,
+    op: fn(
+        &mut FnNib,
+        &QuillValue<QuillIntType>,
+        &QuillValue<QuillIntType>,
+    ) -> quill::Result<QuillValue<QuillIntType>>,
+) -> Result<()> {
+    let self_int = get_int_param(nib, "self")?;
+    let other_int = get_int_param(nib, "other")?;
+
+    let result_int = op(nib, &self_int, &other_int)?;
+    nib.add_return(Some(&result_int));
 
     Ok(())
 }
@@ -537,12 +1172,7 @@ fn format_i(
         QuillFnType::new(QuillFnType::void_return(), params),
     )?;
 
-    let self_arg = nib.get_fn_param(
-        String::from("self"),
-        QuillPointerType::new(QuillStructType::new(int_name_mangled())),
-    );
-    let self_int =
-        nib.get_value_from_struct(&self_arg, primitive_field_name(), QuillIntType::new(64))?;
+    let self_int = get_int_param(nib, "self")?;
 
     let arbitrary_capacity = 50;
     let char_star = nib.add_malloc(QuillListType::new_const_length(
@@ -595,28 +1225,71 @@ pub(super) fn add_main_func(peter: &mut Quill) -> Result<()> {
         named_params: Default::default(),
         caller_id: None,
         tmpls: vec![],
+        bounds: Default::default(),
+        is_static: false,
+        variadic: false,
     }
     .mangled();
-    let result = nib.add_fn_call(
-        magic_main_func.name,
-        vec![],
-        QuillPointerType::new(QuillStructType::new(int_struct_name())),
-    );
-    let result_value =
-        nib.get_value_from_struct(&result, primitive_field_name(), QuillIntType::new(64))?;
-    nib.add_return(Some(&result_value));
+    let result = nib.add_fn_call(magic_main_func.name, vec![], QuillIntType::new(64));
+    nib.add_return(Some(&result));
 
     peter.add_fn(nib);
 
     Ok(())
 }
 
-fn int_name_mangled() -> String {
-    StructId::from_name(int_struct_name()).mangle()
+///
+/// Adds a fn that calls a method of an Int or Bool on a boxed self, so that it can be called through a vtable
+/// Interface objects hold a pointer to a struct wrapping the value, but the method expects the value itself
+///
+/// Params:
+/// * `method_name` - The mangled name of the method
+/// * `method_t` - The type of the method
+/// * `box_struct` - The mangled name of the struct the value is boxed in
+///
+pub(super) fn add_unbox_fn(
+    peter: &mut Quill,
+    method_name: &Ident,
+    method_t: &QuillFnType,
+    box_struct: String,
+) -> Result<()> {
+    let (self_t, params) = match method_t.get_params().split_first() {
+        Some(((_, self_t), params)) => (self_t.clone(), params.to_vec()),
+        None => return Err(CompileError::CouldNotFindFunction(method_name.clone())),
+    };
+    let unbox_t = QuillFnType::new(
+        method_t.get_ret_type().clone(),
+        vec![(self_var_name(), QuillPointerType::opaque().into())]
+            .into_iter()
+            .chain(params.iter().cloned())
+            .collect(),
+    );
+    let mut nib = FnNib::new(unbox_fn_name(method_name), unbox_t);
+    let data = nib.get_fn_param(self_var_name(), QuillPointerType::opaque());
+    let boxed = nib.add_pointer_cast(
+        &data,
+        QuillPointerType::new(QuillStructType::new(box_struct)),
+    );
+    let value = nib.get_value_from_struct(&boxed, primitive_field_name(), self_t)?;
+    let args = params
+        .into_iter()
+        .fold(vec![value], |args, (name, param_t)| {
+            args.fpush(nib.get_fn_param(name, param_t))
+        });
+    match method_t.get_ret_type().clone() {
+        None => {
+            nib.add_fn_call(method_name.clone(), args, QuillVoidType::new());
+            nib.add_return(QuillFnType::void_return_value());
+        }
+        Some(ret_t) => {
+            let result = nib.add_fn_call(method_name.clone(), args, ret_t);
+            nib.add_return(Some(&result));
+        }
+    }
+    peter.add_fn(nib);
+    Ok(())
 }
+
 fn string_name_mangled() -> String {
     StructId::from_name(string_struct_name()).mangle()
 }
-fn bool_name_mangled() -> String {
-    StructId::from_name(bool_struct_name()).mangle()
-}
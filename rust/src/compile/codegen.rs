@@ -1,18 +1,23 @@
 use crate::compile::{
-    add_builtin_definition, add_main_func, CompileError, FnManager, Result, TypeManager, VarManager,
+    add_builtin_definition, add_interface_release_fn, add_main_func, add_release, add_retain,
+    add_struct_release_fn, add_unbox_fn, find_stack_vars, get_scalar_type, is_refcounted,
+    statement_always_returns, CompileError, ConstManager, FnManager, Result, TypeManager,
+    TypedCrabAst, VarManager, Warning, WarningConfig, WarningManager,
 };
 use crate::parse::ast::{
-    Assignment, CodeBlock, CrabAst, CrabType, DoWhileStmt, Expression, ExpressionType, FnBodyType,
-    FnCall, Ident, IfStmt, NamedArg, PosParam, Primitive, Statement, StructFieldInit, StructId,
-    StructInit, WhileStmt,
+    Assignment, CodeBlock, CrabType, DoWhileStmt, Expression, ExpressionType, FnBodyType, FnCall,
+    Ident, IfStmt, NamedArg, PosParam, Primitive, Statement, StaticFnCall, StructFieldInit,
+    StructId, StructInit, WhileStmt,
 };
 use crate::quill::{
-    ArtifactType, ChildNib, FnNib, Nib, PolyQuillType, Quill, QuillBoolType, QuillFnType,
-    QuillPointerType, QuillStructType, QuillValue,
+    ArtifactType, ChildNib, FnNib, Nib, OptLevel, PolyQuillType, Quill, QuillBoolType, QuillFnType,
+    QuillIntType, QuillPointerType, QuillStructType, QuillValue,
 };
 use crate::util::{
-    capacity_field_name, int_struct_name, length_field_name, new_list_name, operator_add_name,
-    primitive_field_name, string_struct_name, ListFunctional, MapFunctional, SetFunctional,
+    capacity_field_name, const_fn_name, const_global_name, int_struct_name,
+    interface_data_field_name, interface_vtable_field_name, length_field_name, new_list_name,
+    primitive_field_name, push_fn_name, refcount_field_name, string_struct_name,
+    vtable_struct_name, ListFunctional, MapFunctional, SetFunctional,
 };
 use log::{debug, trace};
 use std::cell::RefCell;
@@ -23,48 +28,48 @@ use std::rc::Rc;
 use uuid::Uuid;
 
 ///
-/// Compiles the given CrabAst and writes the output to out_path
+/// Compiles the given TypedCrabAst and writes the output to out_path
 ///
 /// Params:
-/// * `ast` - The CrabAst to compile
+/// * `ast` - The TypedCrabAst to compile
 /// * `out_path` - The path to write the output to
 /// * `artifact_type` - The type of artifact to output
+/// * `opt_level` - How much llvm should optimize the artifact
+/// * `warning_config` - Which warnings to report, and whether they should fail the compilation
+/// * `count_live_objects` - Whether to keep the count of live objects returned by __live_objects__ up to date
 ///
 pub fn compile(
-    ast: CrabAst,
+    ast: TypedCrabAst,
     out_path: &Path,
     artifact_type: &ArtifactType,
+    opt_level: &OptLevel,
     verify: bool,
+    warning_config: WarningConfig,
+    count_live_objects: bool,
 ) -> Result<()> {
     trace!("Called parse::compile");
-    let mut peter: Quill = Quill::new();
-    let mut type_manager = TypeManager::new();
+    let mut peter: Quill = Quill::new().with_live_object_count(count_live_objects);
+    let warnings = Rc::new(RefCell::new(WarningManager::new(warning_config)));
 
-    ast.structs
-        .into_iter()
-        .try_for_each(|crab_struct| type_manager.register_struct(crab_struct))?;
-    ast.interfaces
-        .into_iter()
-        .try_for_each(|(_, crab_interface)| type_manager.register_interface(crab_interface))?;
-    ast.intrs
-        .into_iter()
-        .try_for_each(|crab_intr| type_manager.register_intr(crab_intr))?;
-
-    let type_manager = Rc::new(RefCell::new(type_manager));
+    let library_items = ast.library_items;
+    let type_manager = Rc::new(RefCell::new(ast.types));
     let fn_manager = Rc::new(RefCell::new(FnManager::new(type_manager.clone())));
+    let const_manager = Rc::new(ConstManager::new(ast.consts));
+    const_manager
+        .get_globals()
+        .into_iter()
+        .for_each(|(name, value)| peter.register_global(name, value));
 
     ast.functions
         .into_iter()
-        .for_each(|(_, func)| fn_manager.borrow_mut().add_source(func));
-    ast.impls.into_iter().for_each(|(_, simp)| {
-        simp.fns
-            .into_iter()
-            .for_each(|(_, ifunc)| fn_manager.borrow_mut().add_source(ifunc))
-    });
+        .for_each(|func| fn_manager.borrow_mut().add_source(func));
     fn_manager.borrow_mut().add_main_to_queue()?;
 
     while !fn_manager.borrow_mut().build_queue_empty() {
         let func = fn_manager.borrow_mut().pop_build_queue().unwrap();
+        let display_name = func.signature.display_name();
+        let func = func.mangled();
+        let return_type = func.signature.return_type.clone();
         let name = func.signature.name.clone();
         debug!("Building function with name {}", name);
         let fn_t = type_manager
@@ -85,9 +90,20 @@ pub fn compile(
                                 },
                             ))
                             .collect();
-                    let mut codegen =
-                        Codegen::new(nib, type_manager.clone(), fn_manager.clone(), all_params)?;
+                    let stack_vars = find_stack_vars(&cb, &type_manager.borrow());
+                    let mut codegen = Codegen::new(
+                        nib,
+                        type_manager.clone(),
+                        fn_manager.clone(),
+                        const_manager.clone(),
+                        warnings.clone(),
+                        display_name,
+                        return_type,
+                        all_params,
+                        stack_vars,
+                    )?;
                     let returns = codegen.build_codeblock(cb)?;
+                    codegen.check_unread_vars();
                     (codegen.into_nib(), returns)
                 }
                 FnBodyType::COMPILER_PROVIDED => {
@@ -108,18 +124,103 @@ pub fn compile(
     }
 
     let mut tm = type_manager.borrow_mut();
+
+    // Registering one type can include more types, so keep going until there are no new ones
+    let mut registered_structs = HashSet::new();
+    let mut registered_interfaces = HashSet::new();
+    while registered_structs.len() < tm.get_included_type_names().len()
+        || registered_interfaces.len() < tm.get_included_interfaces().len()
+    {
+        tm.get_included_type_names()
+            .clone()
+            .into_iter()
+            .filter(|crab_struct| !registered_structs.contains(crab_struct))
+            .try_for_each(|crab_struct| {
+                // Every struct counts its references in a hidden field
+                peter.register_struct_type(
+                    crab_struct.id.mangle(),
+                    tm.get_fields(&crab_struct.id.clone().into())?
+                        .finsert(refcount_field_name(), QuillIntType::new(64).into()),
+                );
+                registered_structs.insert(crab_struct);
+                Result::Ok(())
+            })?;
+        tm.get_included_interfaces()
+            .clone()
+            .into_iter()
+            .filter(|interface| !registered_interfaces.contains(interface))
+            .try_for_each(|interface| {
+                peter.register_struct_type(
+                    StructId::from_name(interface.clone()).mangle(),
+                    tm.get_interface_fields(&interface)?
+                        .finsert(refcount_field_name(), QuillIntType::new(64).into()),
+                );
+                peter.register_struct_type(
+                    vtable_struct_name(&interface),
+                    tm.get_vtable_fields(&interface)?,
+                );
+                registered_interfaces.insert(interface);
+                Result::Ok(())
+            })?;
+    }
     tm.get_included_type_names()
         .clone()
-        .into_iter()
+        .iter()
         .try_for_each(|crab_struct| {
-            peter.register_struct_type(
-                crab_struct.id.mangle(),
-                tm.get_fields(&crab_struct.id.into())?,
-            );
-            Result::Ok(())
+            add_struct_release_fn(
+                &mut peter,
+                crab_struct,
+                fn_manager
+                    .borrow()
+                    .get_drop_fns()
+                    .get(&crab_struct.id.mangle()),
+            )
+        })?;
+    tm.get_included_interfaces()
+        .clone()
+        .iter()
+        .try_for_each(|interface| {
+            add_interface_release_fn(&mut peter, interface, tm.get_quill_vtable(interface)?)
+        })?;
+    fn_manager
+        .borrow()
+        .get_vtables()
+        .values()
+        .flat_map(|vtable| vtable.unboxed.iter().cloned())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .try_for_each(|signature| {
+            let box_struct = StructId::try_from(signature.pos_params[0].crab_type.clone())?;
+            add_unbox_fn(
+                &mut peter,
+                &signature.name,
+                &tm.get_quill_fn_type(signature.clone())?,
+                box_struct.mangle(),
+            )
         })?;
+    fn_manager
+        .borrow()
+        .get_vtables()
+        .iter()
+        .for_each(|(name, vtable)| {
+            peter.register_vtable(
+                name.clone(),
+                QuillStructType::new(vtable_struct_name(&vtable.interface)),
+                vtable.fns.clone(),
+            )
+        });
     add_main_func(&mut peter)?;
-    peter.commit(out_path, &artifact_type, verify)?;
+
+    // Now that everything reachable from main has been built, we know which functions never will be
+    fn_manager
+        .borrow()
+        .get_unused_fns(&library_items)
+        .into_iter()
+        .for_each(|name| warnings.borrow_mut().emit(Warning::UnusedFunction(name)));
+    warnings.borrow().check()?;
+
+    peter.fold_constants();
+    peter.commit(out_path, &artifact_type, opt_level, verify)?;
     Ok(())
 }
 
@@ -128,6 +229,25 @@ struct Codegen<NibType: Nib> {
     vars: VarManager,
     types: Rc<RefCell<TypeManager>>,
     fns: Rc<RefCell<FnManager>>,
+    consts: Rc<ConstManager>,
+    warnings: Rc<RefCell<WarningManager>>,
+
+    /// The human-readable name of the function being built, used when reporting warnings
+    fn_name: Ident,
+
+    /// The return type of the function being built, which returned values may need to be coerced to
+    return_type: CrabType,
+
+    /// Values built by the current statement that this codegen owns a reference to
+    /// They are released once the statement is done with them, unless something takes their reference
+    temps: Vec<CrabValue>,
+
+    /// The variables assigned in this codegen's codeblock, which are released when it ends
+    scope_vars: Vec<Ident>,
+
+    /// The local variables of the function being built that never escape it
+    /// Their structs are allocated on the stack, so only their fields are released
+    stack_vars: Rc<HashSet<Ident>>,
 }
 impl<NibType: Nib> Codegen<NibType> {
     ///
@@ -139,6 +259,13 @@ impl<NibType: Nib> Codegen<NibType> {
             vars: self.vars.clone(),
             types: self.types.clone(),
             fns: self.fns.clone(),
+            consts: self.consts.clone(),
+            warnings: self.warnings.clone(),
+            fn_name: self.fn_name.clone(),
+            return_type: self.return_type.clone(),
+            temps: vec![],
+            scope_vars: vec![],
+            stack_vars: self.stack_vars.clone(),
         }
     }
 
@@ -146,6 +273,24 @@ impl<NibType: Nib> Codegen<NibType> {
         self.nib
     }
 
+    ///
+    /// Emits a warning for every variable and param in this function that has never been read
+    /// Variables and params whose names start with an underscore are assumed to be unused on purpose
+    /// This should only be called once the entire function body has been built
+    ///
+    fn check_unread_vars(&self) {
+        self.vars
+            .get_unread()
+            .into_iter()
+            .filter(|usage| !usage.name.starts_with('_') && usage.name != "self")
+            .for_each(|usage| {
+                self.warnings.borrow_mut().emit(match usage.is_param {
+                    true => Warning::UnusedParam(self.fn_name.clone(), usage.name),
+                    false => Warning::UnusedVariable(self.fn_name.clone(), usage.name),
+                })
+            });
+    }
+
     ///
     /// Build a Nib for a given codeblock
     ///
@@ -158,16 +303,39 @@ impl<NibType: Nib> Codegen<NibType> {
     ///
     fn build_codeblock(&mut self, codeblock: CodeBlock) -> Result<bool> {
         trace!("Codegen::build_codeblock");
-        let returns = codeblock
-            .statements
-            .into_iter()
-            .try_fold(false, |returns, stmt| {
-                if returns {
-                    Ok(true)
-                } else {
-                    self.build_statement(stmt)
+        let mut statements = codeblock.statements.into_iter().peekable();
+        let mut returns = false;
+        while let Some(stmt) = statements.next() {
+            if returns {
+                // Void functions get an implied return appended to their body, which is allowed to be unreachable
+                let implied_return = stmt == Statement::RETURN(None) && statements.peek().is_none();
+                if !implied_return {
+                    self.warnings
+                        .borrow_mut()
+                        .emit(Warning::UnreachableCode(self.fn_name.clone()));
                 }
-            })?;
+                break;
+            }
+            returns = self.build_statement(stmt)?;
+        }
+        Ok(returns)
+    }
+
+    ///
+    /// Build a Nib for a codeblock that has its own scope, such as the body of an if statement
+    /// The variables assigned in the codeblock are released at its end, unless it always returns
+    ///
+    /// Params:
+    /// * `codeblock` - The codeblock to build
+    ///
+    /// Returns:
+    /// True if the built codeblock will always return a value, or false otherwise
+    ///
+    fn build_scope(&mut self, codeblock: CodeBlock) -> Result<bool> {
+        let returns = self.build_codeblock(codeblock)?;
+        if !returns {
+            self.release_scope_vars()?;
+        }
         Ok(returns)
     }
 
@@ -182,59 +350,89 @@ impl<NibType: Nib> Codegen<NibType> {
     ///
     fn build_statement(&mut self, stmt: Statement) -> Result<bool> {
         trace!("Codegen::build_statement");
+        let returns = statement_always_returns(&stmt);
+        let is_return = matches!(stmt, Statement::RETURN(_));
         match stmt {
             Statement::IF_STATEMENT(is) => self.build_if_stmt(is),
             Statement::WHILE_STATEMENT(ws) => self.build_while_statement(ws),
             Statement::DO_WHILE_STATEMENT(dws) => self.build_do_while_statement(dws),
-            Statement::EXPRESSION(expr) => self.build_expression(expr, None).map(|_| false),
+            Statement::EXPRESSION(expr) => self.build_expression(expr, None).map(|_| ()),
             Statement::ASSIGNMENT(ass) => self.build_assignment(ass),
             Statement::REASSIGNMENT(reass) => self.build_reassignment(reass),
             Statement::RETURN(ret) => self.build_return(ret),
+        }?;
+        self.release_temps()?;
+        // Every path through a branch that always returns has already returned, so nothing can follow it
+        if returns && !is_return {
+            self.nib.build_unreachable();
         }
+        Ok(returns)
     }
 
     ///
     /// Adds the given return statement to the Nib
-    /// Yes, this function always returns true
     ///
     /// Params:
     /// * `expr` - The optional expression to return
     ///
-    /// Returns:
-    /// True if the statement always returns a value, or false otherwise
-    ///
-    fn build_return(&mut self, ret: Option<Expression>) -> Result<bool> {
+    fn build_return(&mut self, ret: Option<Expression>) -> Result<()> {
         trace!("Codegen::build_return");
+        // The caller gets its own reference to the returned value, and every local is released
         match ret {
-            None => self.nib.add_return(QuillFnType::void_return_value()),
+            None => {
+                self.release_vars(self.vars.get_locals())?;
+                self.nib.add_return(QuillFnType::void_return_value());
+            }
             Some(expr) => {
                 let expr_res = self.build_expression(expr, None)?;
+                let return_type = self.return_type.clone();
+                let expr_res = self.coerce(expr_res, &return_type)?;
+                self.take(&expr_res)?;
+                self.release_temps()?;
+                self.release_vars(self.vars.get_locals())?;
                 self.nib.add_return(Some(&expr_res.quill_value));
             }
         }
-        Ok(true)
+        Ok(())
     }
 
     ///
     /// Builds the given assignment statement
     /// Keeps a local copy of a value, by name
     /// Adds any necessary expression to the Nib
-    /// This function always returns false
     ///
     /// Params:
     /// * `ass` - The assignment to build
     ///
-    /// Returns:
-    /// True if the statement always returns a value, or false otherwise
-    ///
-    fn build_assignment(&mut self, ass: Assignment) -> Result<bool> {
+    fn build_assignment(&mut self, ass: Assignment) -> Result<()> {
         trace!("Codegen::build_assignment");
-        let value = self.build_expression(ass.expr, None)?;
+        let value = match ass.expr {
+            Expression {
+                this: ExpressionType::STRUCT_INIT(si),
+                next: None,
+                ..
+            } if self.stack_vars.contains(&ass.var_name) => self.build_struct_init(si, true)?,
+            expr => self.build_expression(expr, None)?,
+        };
+        self.store_var(ass.var_name, value)
+    }
+
+    ///
+    /// Stores a value in a new local variable
+    ///
+    /// Params:
+    /// * `var_name` - The name of the variable
+    /// * `value` - The value to store
+    ///
+    fn store_var(&mut self, var_name: Ident, value: CrabValue) -> Result<()> {
+        if !self.stack_vars.contains(&var_name) {
+            self.take(&value)?;
+        }
         let ptr = self.nib.add_alloca(value.quill_value.get_type().clone());
         self.nib.add_store(&ptr, &value.quill_value)?;
+        self.scope_vars.push(var_name.clone());
         self.vars
-            .assign(ass.var_name, CrabValue::new(ptr.into(), value.crab_type))?;
-        Ok(false)
+            .assign(var_name, CrabValue::new(ptr.into(), value.crab_type))
     }
 
     ///
@@ -242,25 +440,31 @@ impl<NibType: Nib> Codegen<NibType> {
     /// Keeps a local copy of a value, by name
     /// Any previous value will be cleared
     /// Adds any necessary expression to the Nib
-    /// This function always returns false
     ///
     /// Params:
     /// * `reass` - The assignment to build
     ///
-    /// Returns:
-    /// True if the statement always returns a value, or false otherwise
-    ///
-    fn build_reassignment(&mut self, reass: Assignment) -> Result<bool> {
+    fn build_reassignment(&mut self, reass: Assignment) -> Result<()> {
         trace!("Codegen::build_reassignment");
+        let ptr = self.vars.peek(&reass.var_name)?.clone();
         let value = self.build_expression(reass.expr, None)?;
-        let ptr = self.vars.get(&reass.var_name)?.clone();
-        self.nib
-            .add_store(&ptr.quill_value.clone().try_into()?, &value.quill_value)?;
+        let value = self.coerce(value, &ptr.crab_type)?;
+        self.take(&value)?;
+
+        // The old value is only released after the new one is stored, in case they are the same value
+        let var_ptr: QuillValue<QuillPointerType> = ptr.quill_value.clone().try_into()?;
+        let old_value = match is_refcounted(&ptr.crab_type) {
+            true => Some(self.load_var(&var_ptr, &ptr.crab_type)?),
+            false => None,
+        };
+        self.nib.add_store(&var_ptr, &value.quill_value)?;
+        if let Some(old_value) = old_value {
+            self.release(&old_value)?;
+        }
         self.vars.reassign(
             reass.var_name,
             CrabValue::new(ptr.quill_value.into(), value.crab_type),
-        )?;
-        Ok(false)
+        )
     }
 
     ///
@@ -269,45 +473,28 @@ impl<NibType: Nib> Codegen<NibType> {
     /// Params:
     /// * `is` - The if statement to build
     ///
-    /// Returns:
-    /// True if the statement always returns a value, or false otherwise
-    ///
-    fn build_if_stmt(&mut self, is: IfStmt) -> Result<bool> {
+    fn build_if_stmt(&mut self, is: IfStmt) -> Result<()> {
         trace!("Codegen::build_if_stmt");
         // Build all the different blocks
         let mut then_codegen = self.create_child();
-        let then_returns = then_codegen.build_codeblock(is.then)?;
-        let (else_codegen, else_returns) = match is.else_stmt {
-            None => (None, None),
+        then_codegen.build_scope(is.then)?;
+        let else_codegen = match is.else_stmt {
+            None => None,
             Some(cb) => {
                 let mut else_codegen = self.create_child();
-                let returns = else_codegen.build_codeblock(cb)?;
-                (Some(else_codegen), Some(returns))
+                else_codegen.build_scope(cb)?;
+                Some(else_codegen)
             }
         };
 
         // Build the branch statement
-        let value = self.build_expression(is.expr, None)?;
-        let value_value = self.nib.get_value_from_struct(
-            &value.quill_value.try_into()?,
-            primitive_field_name(),
-            QuillBoolType::new(),
-        )?;
+        let value_value = self.build_condition(is.expr)?;
         self.nib.add_cond_branch(
             &value_value,
             then_codegen.into_nib(),
             else_codegen.map(|ec| ec.into_nib()),
         );
-
-        // Handle different cases that may occur with return values
-        let always_returns = match else_returns {
-            None => then_returns,
-            Some(else_returns) => then_returns && else_returns,
-        };
-        if always_returns {
-            self.nib.build_unreachable();
-        }
-        Ok(always_returns)
+        Ok(())
     }
 
     ///
@@ -316,36 +503,23 @@ impl<NibType: Nib> Codegen<NibType> {
     /// Params:
     /// * `ws` - The while statement to build
     ///
-    /// Returns:
-    /// True if the while statement always returns a value, or false otherwise
-    ///
-    fn build_while_statement(&mut self, ws: WhileStmt) -> Result<bool> {
+    fn build_while_statement(&mut self, ws: WhileStmt) -> Result<()> {
         trace!("Codegen::build_while_statement");
         // Build the internal codeblock
         let mut while_codegen = self.create_child();
-        let while_returns = while_codegen.build_codeblock(ws.then)?;
-        let value = while_codegen.build_expression(ws.expr.clone(), None)?;
-        let value_value = while_codegen.nib.get_value_from_struct(
-            &value.quill_value.try_into()?,
-            primitive_field_name(),
-            QuillBoolType::new(),
-        )?;
-        let mut while_nib = while_codegen.into_nib();
-        while_nib.add_cond_loop(&value_value);
+        let body_returns = while_codegen.build_codeblock(ws.then)?;
+        // A body that always returns never gets to loop, so it must not build anything after its return
+        if !body_returns {
+            let value_value = while_codegen.build_condition(ws.expr.clone())?;
+            while_codegen.release_scope_vars()?;
+            while_codegen.nib.add_cond_loop(&value_value);
+        }
+        let while_nib = while_codegen.into_nib();
 
         // Build our entrypoint into the while codeblock
-        let value = self.build_expression(ws.expr, None)?;
-        let value_value = self.nib.get_value_from_struct(
-            &value.quill_value.try_into()?,
-            primitive_field_name(),
-            QuillBoolType::new(),
-        )?;
+        let value_value = self.build_condition(ws.expr)?;
         self.nib.add_cond_branch(&value_value, while_nib, None);
-
-        if while_returns {
-            self.nib.build_unreachable();
-        }
-        Ok(while_returns)
+        Ok(())
     }
 
     ///
@@ -354,34 +528,42 @@ impl<NibType: Nib> Codegen<NibType> {
     /// Params:
     /// * `dws` - The while statement to build
     ///
-    /// Returns:
-    /// True if the while statement always returns a value, or false otherwise
-    ///
-    fn build_do_while_statement(&mut self, dws: DoWhileStmt) -> Result<bool> {
+    fn build_do_while_statement(&mut self, dws: DoWhileStmt) -> Result<()> {
         trace!("Codegen::build_do_while_statement");
         // Build the internal codeblock
         let mut do_while_codegen = self.create_child();
-        let do_while_returns = do_while_codegen.build_codeblock(dws.then)?;
-        let value = do_while_codegen.build_expression(dws.expr, None)?;
-        let value_value = do_while_codegen.nib.get_value_from_struct(
-            &value.quill_value.try_into()?,
-            primitive_field_name(),
-            QuillBoolType::new(),
-        )?;
+        let body_returns = do_while_codegen.build_codeblock(dws.then)?;
+        // A body that always returns never gets to loop, so it must not build anything after its return
+        if !body_returns {
+            let value_value = do_while_codegen.build_condition(dws.expr)?;
+            do_while_codegen.release_scope_vars()?;
+            do_while_codegen.nib.add_cond_loop(&value_value);
+        }
 
-        let mut do_while_nib = do_while_codegen.into_nib();
-        do_while_nib.add_cond_loop(&value_value);
         // Build our entrypoint into the do-while codeblock
+        let do_while_nib = do_while_codegen.into_nib();
         self.nib.add_branch(do_while_nib);
+        Ok(())
+    }
 
-        if do_while_returns {
-            self.nib.build_unreachable();
-        }
-        Ok(do_while_returns)
+    ///
+    /// Adds the condition of an if or while statement to the Nib
+    ///
+    /// Params:
+    /// * `expr` - The condition to build
+    ///
+    /// Returns:
+    /// The value of the condition
+    ///
+    fn build_condition(&mut self, expr: Expression) -> Result<QuillValue<QuillBoolType>> {
+        let value = self.build_expression(expr, None)?;
+        self.release_temps()?;
+        Ok(value.quill_value.try_into()?)
     }
 
     ///
     /// Adds the given expression to the Nib
+    /// Wherever the type checker annotated the expression with a type, that type is used instead of working it out again
     ///
     /// Params:
     /// * `expr` - The expression to build
@@ -392,37 +574,44 @@ impl<NibType: Nib> Codegen<NibType> {
     ///
     fn build_expression(&mut self, expr: Expression, prev: Option<CrabValue>) -> Result<CrabValue> {
         trace!("Codegen::build_expression");
-        let val = match expr.this {
+        let Expression {
+            this,
+            next,
+            crab_type,
+        } = expr;
+        let val = match this {
             ExpressionType::PRIM(prim) => self.build_primitive(prim),
-            ExpressionType::STRUCT_INIT(si) => Ok(self.build_struct_init(si)?),
+            ExpressionType::STRUCT_INIT(si) => Ok(self.build_struct_init(si, false)?),
             ExpressionType::FN_CALL(fc) => self.build_fn_call(fc, prev),
+            ExpressionType::STATIC_FN_CALL(sfc) => self.build_static_fn_call(sfc),
             ExpressionType::VARIABLE(id) => {
                 match prev {
+                    // Local variables hide any constant with the same name
+                    None if self.vars.peek(&id).is_err() && self.consts.get(&id).is_some() => {
+                        self.build_const(&id)
+                    }
                     None => {
                         // This is a pretty chonky couple lines of code, so it deserves a comment
-                        // TODO: None of this would be necessary if we used the following memory strategy:
-                        // TODO:  * Only alloc local var
-                        // TODO:  * Only malloc vars that pass a function boundary (this includes params and return values)
-                        // TODO:  * Everything else gets neither alloced nor malloced, and goes into whatever llvm's memory pipeline is
-                        // But I'm tired and I don't want to put in that big of a change right now
+                        // Local variables are stack slots holding their value,
+                        // but params are the value itself
                         // SO:
                         // First, get the variable from the variable manager
                         // Then, try to load the variable as if it were a local variable
-                        // If that fails, try to load the variable as if it were a function parameter
-                        // If that fails, report an error
-                        // If either of those steps succeed, proceed with the loaded value
-                        let ptr = self.vars.get(&id)?;
-                        let local_loaded_res = self.nib.add_load(
-                            &ptr.quill_value.clone().try_into()?,
-                            QuillPointerType::new(QuillStructType::new(
-                                StructId::try_from(ptr.crab_type.clone())?.mangle(),
-                            )),
-                        );
+                        // If that fails, use the variable as if it were a function parameter
+                        // Ints and Bools are never pointers, so a param of theirs can't be loaded at all
+                        let ptr = self.vars.get(&id)?.clone();
+                        let value_t = self.types.borrow_mut().get_quill_type(&ptr.crab_type)?;
+                        let local_loaded_res =
+                            match QuillValue::<QuillPointerType>::try_from(ptr.quill_value.clone())
+                            {
+                                Ok(local_ptr) => self.nib.add_load(&local_ptr, value_t),
+                                Err(err) => Err(err),
+                            };
                         let loaded = match local_loaded_res {
-                            Ok(local_loaded) => Ok(local_loaded),
-                            Err(_) => ptr.quill_value.clone().try_into(),
-                        }?;
-                        Ok(CrabValue::new(loaded.into(), ptr.crab_type.clone()))
+                            Ok(local_loaded) => local_loaded,
+                            Err(_) => ptr.quill_value.clone(),
+                        };
+                        Ok(CrabValue::new(loaded, ptr.crab_type))
                     }
                     Some(prev) => {
                         // Figure out what type of value we should get from the struct
@@ -456,31 +645,73 @@ impl<NibType: Nib> Codegen<NibType> {
                             id.clone(),
                             expected_type.clone(),
                         )?;
-                        let expected_ct = self
-                            .types
-                            .borrow_mut()
-                            .get_field_types(&prev.crab_type)?
-                            .iter()
-                            .filter(|(name, _)| name == &&id)
-                            .next()
-                            .ok_or(CompileError::StructFieldName(
-                                prev.crab_type.clone(),
-                                id.clone(),
-                            ))?
-                            .1
-                            .clone();
+                        // Fields whose type depends on a template were left unannotated, so look them up instead
+                        let expected_ct = match crab_type {
+                            Some(ct) => ct,
+                            None => self
+                                .types
+                                .borrow_mut()
+                                .get_field_types(&prev.crab_type)?
+                                .iter()
+                                .filter(|(name, _)| name == &&id)
+                                .next()
+                                .ok_or(CompileError::StructFieldName(
+                                    prev.crab_type.clone(),
+                                    id.clone(),
+                                ))?
+                                .1
+                                .clone(),
+                        };
                         Ok(CrabValue::new(val.into(), expected_ct))
                     }
                 }
             }
         }?;
 
-        match expr.next {
+        match next {
             None => Ok(val),
             Some(next) => self.build_expression(*next, Some(val)),
         }
     }
 
+    ///
+    /// Reads a constant, whether it is global or associated with a struct
+    /// Constants that were evaluated at compile time are loaded from their global,
+    /// and any other constant is evaluated again by calling its const fn
+    ///
+    /// Params:
+    /// * `name` - The name of the constant
+    ///
+    /// Returns:
+    /// The value of the constant
+    ///
+    fn build_const(&mut self, name: &Ident) -> Result<CrabValue> {
+        let managed = self
+            .consts
+            .get(name)
+            .ok_or(CompileError::VarDoesNotExist(name.clone()))?
+            .clone();
+        match managed.value {
+            Some(value) => {
+                let global = self
+                    .nib
+                    .get_global(const_global_name(name), value.get_type());
+                let loaded = self.nib.add_load(&global, value.get_type())?;
+                Ok(CrabValue::new(loaded, managed.crab_type))
+            }
+            None => self.build_call(
+                FnCall {
+                    name: const_fn_name(name),
+                    tmpls: vec![],
+                    pos_args: vec![],
+                    named_args: vec![],
+                },
+                None,
+                None,
+            ),
+        }
+    }
+
     ///
     /// Gets a quill value for the given primitive
     ///
@@ -502,6 +733,14 @@ impl<NibType: Nib> Codegen<NibType> {
                 self.nib.const_int(64, value).into(),
                 CrabType::PRIM_INT,
             )),
+            Primitive::CHAR(value) => Ok(CrabValue::new(
+                self.nib.const_int(32, value as u64).into(),
+                CrabType::PRIM_INT,
+            )),
+            Primitive::BYTE(value) => Ok(CrabValue::new(
+                self.nib.const_int(8, value as u64).into(),
+                CrabType::PRIM_INT,
+            )),
             Primitive::LIST(exprs) => self.build_list_prim(exprs),
         }
     }
@@ -522,10 +761,10 @@ impl<NibType: Nib> Codegen<NibType> {
         self.nib
             .set_value_in_struct(&string_str, capacity_field_name(), &length)?;
 
-        Ok(CrabValue::new(
+        Ok(self.add_temp(CrabValue::new(
             string_str.into(),
             CrabType::SIMPLE(string_struct_name()),
-        ))
+        )))
     }
 
     fn build_list_prim(&mut self, exprs: Vec<Expression>) -> Result<CrabValue> {
@@ -544,14 +783,28 @@ impl<NibType: Nib> Codegen<NibType> {
             Expression {
                 this: ExpressionType::VARIABLE(var_names[0].clone()),
                 next: None,
+                crab_type: None,
             },
             None,
         )?;
+        self.build_list(first_value.crab_type, var_names)
+    }
 
+    ///
+    /// Adds a new list holding the values of some variables to the Nib
+    ///
+    /// Params:
+    /// * `elem_t` - The type of the list's elements
+    /// * `var_names` - The variables holding the elements, in order
+    ///
+    /// Returns:
+    /// The new list
+    ///
+    fn build_list(&mut self, elem_t: CrabType, var_names: Vec<Ident>) -> Result<CrabValue> {
         // Construct the vector
         let fn_call = FnCall {
             name: new_list_name(),
-            tmpls: vec![first_value.crab_type.clone()],
+            tmpls: vec![elem_t],
             pos_args: vec![],
             named_args: vec![NamedArg {
                 name: Ident::from("capacity"),
@@ -563,10 +816,13 @@ impl<NibType: Nib> Codegen<NibType> {
                             value: Expression {
                                 this: ExpressionType::PRIM(Primitive::UINT(var_names.len() as u64)),
                                 next: None,
+                                crab_type: None,
                             },
                         }],
+                        update: None,
                     }),
                     next: None,
+                    crab_type: None,
                 },
             }],
         };
@@ -575,11 +831,12 @@ impl<NibType: Nib> Codegen<NibType> {
         // Add elements to the array
         var_names.into_iter().try_for_each(|name| {
             let add_element_call = FnCall {
-                name: operator_add_name(),
+                name: push_fn_name(),
                 tmpls: vec![],
                 pos_args: vec![Expression {
                     this: ExpressionType::VARIABLE(name),
                     next: None,
+                    crab_type: None,
                 }],
                 named_args: vec![],
             };
@@ -592,19 +849,24 @@ impl<NibType: Nib> Codegen<NibType> {
 
     ///
     /// Adds a struct initialization to the Nib
+    /// Fields that are not supplied are copied from the update value if there is one,
+    /// and otherwise are built from their default values
     ///
     /// Params:
     /// * `si` - The struct init to add
+    /// * `on_stack` - Whether to allocate the struct on the stack, for a variable that never escapes the function
     ///
     /// Returns:
     /// The value of the new struct
     ///
-    fn build_struct_init(&mut self, si: StructInit) -> Result<CrabValue> {
+    fn build_struct_init(&mut self, si: StructInit, on_stack: bool) -> Result<CrabValue> {
+        if get_scalar_type(&si.id).is_some() {
+            return self.build_scalar_init(si);
+        }
         let struct_id = si.id;
-        let struct_field_names = self
-            .types
-            .borrow_mut()
-            .get_fields(&struct_id)?
+        let field_types = self.types.borrow().peek_field_types(&struct_id)?;
+        let struct_fields = self.types.borrow_mut().get_fields(&struct_id)?;
+        let struct_field_names = struct_fields
             .iter()
             .fold(HashSet::new(), |struct_field_names, (name, _)| {
                 struct_field_names.finsert(name.clone())
@@ -616,40 +878,158 @@ impl<NibType: Nib> Codegen<NibType> {
                     .get(&field.name)
                 {
                     Some(_) => {
-                        Ok(field_vals
-                            .finsert(field.name, self.build_expression(field.value, None)?))
+                        let value = self.build_expression(field.value, None)?;
+                        let value = match field_types
+                            .as_ref()
+                            .and_then(|field_types| field_types.get(&field.name))
+                        {
+                            Some(field_t) => self.coerce(value, field_t)?,
+                            None => value,
+                        };
+                        self.take(&value)?;
+                        Ok(field_vals.finsert(field.name, value))
                     }
                     None => Err(CompileError::StructFieldName(struct_id.clone(), field.name)),
                 })?;
-        struct_field_names
+        let update_opt = match si.update {
+            None => None,
+            Some(update) => Some(self.build_expression(*update, None)?),
+        };
+        let defaults = self.types.borrow().peek_field_defaults(&struct_id)?;
+        let fields = struct_field_names
             .into_iter()
-            .try_for_each(|name| match fields.contains_key(&name) {
-                true => Ok(()),
-                false => Err(CompileError::StructInitFieldName(
-                    struct_id.try_get_struct_name()?.clone(),
-                    name,
-                )),
+            .try_fold(fields, |fields, name| {
+                if fields.contains_key(&name) {
+                    return Result::Ok(fields);
+                }
+                let field_t = field_types
+                    .as_ref()
+                    .and_then(|field_types| field_types.get(&name))
+                    .ok_or(CompileError::StructInitFieldName(
+                        struct_id.try_get_struct_name()?,
+                        name.clone(),
+                    ))?;
+                let value = match (&update_opt, defaults.get(&name)) {
+                    (Some(update), _) => {
+                        let val = self.nib.get_value_from_struct(
+                            &update.quill_value.clone().try_into()?,
+                            name.clone(),
+                            struct_fields.get(&name).unwrap().clone(),
+                        )?;
+                        CrabValue::new(val.into(), field_t.clone())
+                    }
+                    (None, Some(default)) => {
+                        let value = self.build_expression(default.clone(), None)?;
+                        self.coerce(value, field_t)?
+                    }
+                    (None, None) => {
+                        return Err(CompileError::StructInitFieldName(
+                            struct_id.try_get_struct_name()?,
+                            name,
+                        ))
+                    }
+                };
+                self.take(&value)?;
+                Result::Ok(fields.finsert(name, value))
             })?;
+        // Every value of a Drop type is built here, so this is where its drop fn is first needed
+        if self.types.borrow().is_droppable(&struct_id) {
+            self.fns.borrow_mut().get_drop_fn(&struct_id)?;
+        }
         let struct_t = self.types.borrow_mut().get_quill_struct(&struct_id)?;
-        let new_struct_ptr = self.nib.add_malloc(struct_t);
+        let new_struct_ptr = match on_stack {
+            true => self.nib.add_alloca(struct_t),
+            false => self.nib.add_malloc(struct_t),
+        };
         fields.into_iter().try_for_each(|(name, value)| {
             self.nib
                 .set_value_in_struct(&new_struct_ptr, name, &value.quill_value)
         })?;
-        Ok(CrabValue::new(new_struct_ptr.into(), struct_id))
+        let new_struct = CrabValue::new(new_struct_ptr.into(), struct_id);
+        Ok(match on_stack {
+            true => new_struct,
+            false => self.add_temp(new_struct),
+        })
+    }
+
+    ///
+    /// Adds the initialization of an Int or Bool to the Nib
+    /// These are native scalars, so the value they wrap is used as it is
+    ///
+    /// Params:
+    /// * `si` - The struct init to add
+    ///
+    /// Returns:
+    /// The value of the new Int or Bool
+    ///
+    fn build_scalar_init(&mut self, si: StructInit) -> Result<CrabValue> {
+        let value = match (si.fields.into_iter().next(), si.update) {
+            (Some(field), _) if field.name == primitive_field_name() => {
+                self.build_expression(field.value, None)?
+            }
+            (Some(field), _) => return Err(CompileError::StructFieldName(si.id, field.name)),
+            (None, Some(update)) => self.build_expression(*update, None)?,
+            (None, None) => {
+                return Err(CompileError::StructInitFieldName(
+                    si.id.try_get_struct_name()?,
+                    primitive_field_name(),
+                ))
+            }
+        };
+        Ok(CrabValue::new(value.quill_value, si.id))
     }
 
     fn build_fn_call(&mut self, call: FnCall, caller_opt: Option<CrabValue>) -> Result<CrabValue> {
         trace!("Codegen::build_fn_call");
-        // Get the original function
+        // Methods called on interface objects can only be resolved at runtime
+        if let Some(caller) = &caller_opt {
+            if self.types.borrow().is_interface_type(&caller.crab_type) {
+                return self.build_dyn_fn_call(call, caller.clone());
+            }
+        }
+
         let caller_ct = caller_opt.clone().map(|caller| caller.crab_type);
-        let source_signature = self
-            .fns
-            .borrow_mut()
-            .get_source_signature(&call.name, caller_ct.clone())?;
+        self.build_call(call, caller_ct, caller_opt)
+    }
+
+    ///
+    /// Adds a call to a static fn to the Nib
+    ///
+    /// Params:
+    /// * `sfc` - The static fn call to build
+    ///
+    /// Returns:
+    /// The value returned by the static fn
+    ///
+    fn build_static_fn_call(&mut self, sfc: StaticFnCall) -> Result<CrabValue> {
+        trace!("Codegen::build_static_fn_call");
+        if let Some(name) = sfc.as_assoc_const() {
+            if self.consts.get(&name).is_some() {
+                return self.build_const(&name);
+            }
+        }
+        self.build_call(sfc.call, Some(sfc.caller), None)
+    }
 
+    ///
+    /// Adds a call to a fn that can be resolved at compile time to the Nib
+    ///
+    /// Params:
+    /// * `call` - The fn call to build
+    /// * `caller_ct` - The type the fn belongs to, if any
+    /// * `self_opt` - The value a method is called on, which is None for free and static fns
+    ///
+    /// Returns:
+    /// The value returned by the fn
+    ///
+    fn build_call(
+        &mut self,
+        call: FnCall,
+        caller_ct: Option<CrabType>,
+        self_opt: Option<CrabValue>,
+    ) -> Result<CrabValue> {
         // Handle all of the positional arguments
-        let unnamed_args = match caller_opt {
+        let unnamed_args = match self_opt {
             Some(caller) => vec![caller],
             None => vec![],
         };
@@ -674,6 +1054,15 @@ impl<NibType: Nib> Codegen<NibType> {
                         self.build_expression(named_arg.expr.clone(), None)?,
                     ))
                 })?;
+
+        // Get the original function
+        // Overloaded fns are told apart by their args, so this has to wait until the supplied args are built
+        let source_signature = self.fns.borrow().get_source_signature(
+            &call.name,
+            caller_ct.clone(),
+            &unnamed_args,
+            &named_args,
+        )?;
         let named_args = source_signature.named_params.into_iter().try_fold(
             named_args,
             |named_args, (_, named_param)| match named_args.get(&named_param.name) {
@@ -692,23 +1081,50 @@ impl<NibType: Nib> Codegen<NibType> {
                 .borrow_mut()
                 .get_signature(&call, caller_ct, &unnamed_args, &named_args)?;
 
+        // The args collected by a variadic param are passed to it as a single list
+        let unnamed_args = match signature.variadic_type().cloned() {
+            Some(elem_t) => {
+                let (args, collected) = signature.split_variadic_args(&unnamed_args);
+                let var_names = collected.iter().try_fold(vec![], |var_names, value| {
+                    let var_name = format!("{}", Uuid::new_v4().as_simple());
+                    self.store_var(var_name.clone(), value.clone())?;
+                    Result::Ok(var_names.fpush(var_name))
+                })?;
+                let list = self.build_list(elem_t, var_names)?;
+                args.to_vec().fpush(list)
+            }
+            None => unnamed_args,
+        };
+
         // Listify the named params in the correct order
+        // Any args passed to interface params need to be boxed into interface objects
+        let param_types = signature
+            .pos_params
+            .iter()
+            .map(|param| param.crab_type.clone())
+            .chain(
+                signature
+                    .named_params
+                    .iter()
+                    .map(|(_, param)| param.crab_type.clone()),
+            )
+            .collect::<Vec<_>>();
         let quill_fn_t = self
             .types
             .borrow_mut()
             .get_quill_fn_type(signature.clone())?;
-        let args =
-            quill_fn_t
-                .get_params()
-                .iter()
-                .enumerate()
-                .fold(vec![], |args, (i, (name, _))| {
-                    if i < unnamed_args.len() {
-                        args.fpush(unnamed_args.get(i).unwrap().clone())
-                    } else {
-                        args.fpush(named_args.get(name).unwrap().clone())
-                    }
-                });
+        let args = quill_fn_t
+            .get_params()
+            .iter()
+            .zip(param_types.iter())
+            .enumerate()
+            .try_fold(vec![], |args, (i, ((name, _), param_t))| {
+                let arg = match i < unnamed_args.len() {
+                    true => unnamed_args.get(i).unwrap().clone(),
+                    false => named_args.get(name).unwrap().clone(),
+                };
+                Result::Ok(args.fpush(self.coerce(arg, param_t)?))
+            })?;
         let qv = self.nib.add_fn_call(
             signature.name,
             args.into_iter().map(|cv| cv.quill_value.clone()).collect(),
@@ -716,7 +1132,290 @@ impl<NibType: Nib> Codegen<NibType> {
                 .borrow_mut()
                 .get_quill_type(&signature.return_type)?,
         );
-        Ok(CrabValue::new(qv.into(), signature.return_type))
+        Ok(self.add_temp(CrabValue::new(qv.into(), signature.return_type)))
+    }
+
+    ///
+    /// Adds a call to a method of an interface object to the Nib
+    /// The method that is actually called is looked up at runtime in the object's vtable
+    ///
+    /// Params:
+    /// * `call` - The method call to build
+    /// * `caller` - The interface object the method is called on
+    ///
+    /// Returns:
+    /// The value returned by the method
+    ///
+    fn build_dyn_fn_call(&mut self, call: FnCall, caller: CrabValue) -> Result<CrabValue> {
+        trace!("Codegen::build_dyn_fn_call");
+        let interface = caller.crab_type.try_get_struct_name()?;
+        let signature = self
+            .types
+            .borrow()
+            .get_interface_fn(&interface, &call.name)
+            .ok_or(CompileError::CouldNotFindFunction(call.name.clone()))?;
+
+        // Build the args in the same order as the vtable fn's params
+        let pos_args = call
+            .pos_args
+            .iter()
+            .zip(signature.pos_params.iter())
+            .try_fold(vec![], |pos_args, (arg, param)| {
+                let value = self.build_expression(arg.clone(), None)?;
+                Result::Ok(pos_args.fpush(self.coerce(value, &param.crab_type)?))
+            })?;
+        let args = signature
+            .named_params
+            .values()
+            .try_fold(pos_args, |args, named_param| {
+                let expr = call
+                    .named_args
+                    .iter()
+                    .find(|named_arg| named_arg.name == named_param.name)
+                    .map_or(named_param.expr.clone(), |named_arg| named_arg.expr.clone());
+                let value = self.build_expression(expr, None)?;
+                Result::Ok(args.fpush(self.coerce(value, &named_param.crab_type)?))
+            })?;
+
+        // Look up the fn in the vtable
+        let object = caller.quill_value.try_into()?;
+        let vtable_t = self.types.borrow_mut().get_quill_vtable(&interface)?;
+        let data = self.nib.get_value_from_struct(
+            &object,
+            interface_data_field_name(),
+            QuillPointerType::opaque(),
+        )?;
+        let vtable = self.nib.get_value_from_struct(
+            &object,
+            interface_vtable_field_name(),
+            QuillPointerType::new(vtable_t),
+        )?;
+        let fn_t = self
+            .types
+            .borrow_mut()
+            .get_vtable_fn_type(signature.clone())?;
+        let fn_ptr = self.nib.get_value_from_struct(
+            &vtable,
+            call.name.clone(),
+            QuillPointerType::new(fn_t),
+        )?;
+
+        let qv = self.nib.add_fn_ptr_call(
+            &fn_ptr,
+            vec![data.into()]
+                .into_iter()
+                .chain(args.into_iter().map(|cv| cv.quill_value))
+                .collect(),
+            self.types
+                .borrow_mut()
+                .get_quill_type(&signature.return_type)?,
+        )?;
+        Ok(self.add_temp(CrabValue::new(qv.into(), signature.return_type)))
+    }
+
+    ///
+    /// Converts a value so it can be used as the given type
+    /// Values of concrete types are boxed into interface objects when an interface is expected
+    /// Every other value is returned unchanged
+    ///
+    /// Params:
+    /// * `value` - The value to convert
+    /// * `ct` - The type the value is expected to have
+    ///
+    /// Returns:
+    /// A value that can be used as the given type
+    ///
+    fn coerce(&mut self, value: CrabValue, ct: &CrabType) -> Result<CrabValue> {
+        let needs_box = {
+            let types = self.types.borrow();
+            types.is_interface_type(ct) && !types.is_interface_type(&value.crab_type)
+        };
+        match needs_box {
+            true => self.build_interface_object(value, &ct.try_get_struct_name()?),
+            false => Ok(value),
+        }
+    }
+
+    ///
+    /// Boxes a value into a new interface object
+    /// The object holds a pointer to the value, and a pointer to the vtable for the value's type
+    /// Ints and Bools aren't pointers, so they are first put in a struct that the object can point to
+    ///
+    /// Params:
+    /// * `value` - The value to box
+    /// * `interface` - The name of the interface to box the value as
+    ///
+    /// Returns:
+    /// The new interface object
+    ///
+    fn build_interface_object(&mut self, value: CrabValue, interface: &Ident) -> Result<CrabValue> {
+        trace!("Codegen::build_interface_object");
+        let vtable_name = self
+            .fns
+            .borrow_mut()
+            .get_vtable(&value.crab_type, interface)?;
+        let vtable_t = self.types.borrow_mut().get_quill_vtable(interface)?;
+        let vtable = self.nib.get_global(vtable_name, vtable_t);
+        let value_ptr = match get_scalar_type(&value.crab_type) {
+            Some(_) => {
+                let box_t = self.types.borrow_mut().get_quill_struct(&value.crab_type)?;
+                let boxed = self.nib.add_malloc(box_t);
+                self.nib
+                    .set_value_in_struct(&boxed, primitive_field_name(), &value.quill_value)?;
+                boxed
+            }
+            None => {
+                self.take(&value)?;
+                value.quill_value.try_into()?
+            }
+        };
+        let data = self
+            .nib
+            .add_pointer_cast(&value_ptr, QuillPointerType::opaque());
+
+        let object_t = self.types.borrow_mut().get_quill_interface(interface)?;
+        let object = self.nib.add_malloc(object_t);
+        self.nib
+            .set_value_in_struct(&object, interface_data_field_name(), &data)?;
+        self.nib
+            .set_value_in_struct(&object, interface_vtable_field_name(), &vtable)?;
+        Ok(self.add_temp(CrabValue::new(
+            object.into(),
+            CrabType::SIMPLE(interface.clone()),
+        )))
+    }
+
+    ///
+    /// Records a fresh value, which this codegen owns a reference to until the end of the current statement
+    ///
+    /// Returns:
+    /// The same value
+    ///
+    fn add_temp(&mut self, value: CrabValue) -> CrabValue {
+        if is_refcounted(&value.crab_type) {
+            self.temps.push(value.clone());
+        }
+        value
+    }
+
+    ///
+    /// Takes a reference to a value, so that it can be kept past the end of the current statement
+    /// A temporary hands over the reference it already owns, and any other value gets a new one
+    ///
+    /// Params:
+    /// * `value` - The value to take a reference to
+    ///
+    fn take(&mut self, value: &CrabValue) -> Result<()> {
+        if !is_refcounted(&value.crab_type) {
+            return Ok(());
+        }
+        match self
+            .temps
+            .iter()
+            .position(|temp| temp.quill_value == value.quill_value)
+        {
+            Some(index) => {
+                self.temps.remove(index);
+                Ok(())
+            }
+            None => add_retain(&mut self.nib, &value.quill_value.clone().try_into()?),
+        }
+    }
+
+    ///
+    /// Removes a reference to a value, which frees it if that was its last reference
+    ///
+    /// Params:
+    /// * `value` - The value to release
+    ///
+    fn release(&mut self, value: &CrabValue) -> Result<()> {
+        if is_refcounted(&value.crab_type) {
+            add_release(
+                &mut self.nib,
+                &value.quill_value.clone().try_into()?,
+                &StructId::try_from(value.crab_type.clone())?.mangle(),
+            );
+        }
+        Ok(())
+    }
+
+    ///
+    /// Releases every temporary built by the current statement that nothing took a reference to
+    ///
+    fn release_temps(&mut self) -> Result<()> {
+        std::mem::take(&mut self.temps)
+            .iter()
+            .try_for_each(|temp| self.release(temp))
+    }
+
+    ///
+    /// Releases the values held by some variables
+    /// Variables on the stack can't be freed, so only the values in their fields are released
+    ///
+    /// Params:
+    /// * `names` - The names of the variables to release
+    ///
+    fn release_vars(&mut self, names: Vec<Ident>) -> Result<()> {
+        names.into_iter().try_for_each(|name| {
+            let var = self.vars.peek(&name)?.clone();
+            if !is_refcounted(&var.crab_type) {
+                return Result::Ok(());
+            }
+            let value = self.load_var(&var.quill_value.try_into()?, &var.crab_type)?;
+            match self.stack_vars.contains(&name) {
+                true => self.release_fields(&value),
+                false => self.release(&value),
+            }
+        })
+    }
+
+    ///
+    /// Releases the values held by the variables assigned in this codegen's codeblock
+    ///
+    fn release_scope_vars(&mut self) -> Result<()> {
+        self.release_vars(self.scope_vars.clone())
+    }
+
+    ///
+    /// Releases the values held by the fields of a struct, without releasing the struct itself
+    /// Compiler provided structs can only be initialized with primitives, so they have nothing to release
+    ///
+    /// Params:
+    /// * `value` - The struct whose fields should be released
+    ///
+    fn release_fields(&mut self, value: &CrabValue) -> Result<()> {
+        let ptr: QuillValue<QuillPointerType> = value.quill_value.clone().try_into()?;
+        let field_types = self.types.borrow().peek_field_types(&value.crab_type)?;
+        field_types
+            .into_iter()
+            .flatten()
+            .filter(|(_, field_t)| is_refcounted(field_t))
+            .try_for_each(|(name, field_t)| {
+                let field_struct = StructId::try_from(field_t)?.mangle();
+                let field = self.nib.get_value_from_struct(
+                    &ptr,
+                    name,
+                    QuillPointerType::new(QuillStructType::new(field_struct.clone())),
+                )?;
+                add_release(&mut self.nib, &field, &field_struct);
+                Result::Ok(())
+            })
+    }
+
+    ///
+    /// Loads the value held by a local variable
+    ///
+    /// Params:
+    /// * `ptr` - The pointer the variable is stored in
+    /// * `ct` - The type of the variable
+    ///
+    /// Returns:
+    /// The loaded value
+    ///
+    fn load_var(&mut self, ptr: &QuillValue<QuillPointerType>, ct: &CrabType) -> Result<CrabValue> {
+        let value_t = self.types.borrow_mut().get_quill_type(ct)?;
+        let loaded = self.nib.add_load(ptr, value_t)?;
+        Ok(CrabValue::new(loaded, ct.clone()))
     }
 }
 
@@ -727,12 +1426,25 @@ impl Codegen<FnNib> {
     /// Params:
     /// * `nib` - The nib to build everything into
     /// * `types` - The TypeManager to use for resolving types
+    /// * `fns` - The FnManager to use for resolving function calls
+    /// * `consts` - The ConstManager to use for reading constants
+    /// * `warnings` - Where to report any warnings found while building
+    /// * `fn_name` - The human-readable name of the function being built
+    /// * `return_type` - The return type of the function being built
+    /// * `fn_params` - The params of the function being built
+    /// * `stack_vars` - The local variables of the function being built that never escape it
     ///
+    #[allow(clippy::too_many_arguments)]
     fn new(
         mut nib: FnNib,
         types: Rc<RefCell<TypeManager>>,
         fns: Rc<RefCell<FnManager>>,
+        consts: Rc<ConstManager>,
+        warnings: Rc<RefCell<WarningManager>>,
+        fn_name: Ident,
+        return_type: CrabType,
         fn_params: Vec<PosParam>,
+        stack_vars: HashSet<Ident>,
     ) -> Result<Self> {
         let mut vars = VarManager::new();
         fn_params.into_iter().try_for_each(|fn_param| {
@@ -740,7 +1452,7 @@ impl Codegen<FnNib> {
                 fn_param.name.clone(),
                 types.borrow_mut().get_quill_type(&fn_param.crab_type)?,
             );
-            vars.assign(
+            vars.assign_param(
                 fn_param.name,
                 CrabValue::new(val.into(), fn_param.crab_type),
             )
@@ -751,7 +1463,14 @@ impl Codegen<FnNib> {
             nib,
             types,
             fns,
+            consts,
+            warnings,
+            fn_name,
+            return_type,
             vars,
+            temps: vec![],
+            scope_vars: vec![],
+            stack_vars: Rc::new(stack_vars),
         })
     }
 }
@@ -0,0 +1,189 @@
+use crate::compile::get_scalar_type;
+use crate::parse::ast::{
+    CrabConst, CrabType, Expression, ExpressionType, FnCall, Ident, Primitive,
+};
+use crate::quill::QuillConstant;
+use crate::util::{
+    const_global_name, operator_add_name, operator_div_name, operator_eq_name, operator_gt_name,
+    operator_gte_name, operator_lt_name, operator_lte_name, operator_mult_name, operator_sub_name,
+};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+pub(super) struct ManagedConst {
+    pub crab_type: CrabType,
+
+    /// The value of the constant, if it could be evaluated at compile time
+    /// Constants without one are evaluated every time they are read, by their const fn
+    pub value: Option<QuillConstant>,
+}
+
+///
+/// Keeps track of every constant, including the ones associated with a struct
+/// Associated constants are named after their struct, such as `GymGoer::STARTING_FITNESS`
+///
+#[derive(Debug, Clone, Default)]
+pub(super) struct ConstManager {
+    consts: HashMap<Ident, ManagedConst>,
+}
+
+impl ConstManager {
+    ///
+    /// Creates a new ConstManager, evaluating every constant that can be evaluated at compile time
+    /// Only Ints and Bools that are built from literals, other constants, and operators can be
+    ///
+    /// Params:
+    /// * `consts` - Every constant in the program
+    ///
+    pub fn new(consts: Vec<CrabConst>) -> Self {
+        // Constants can read each other in any order, so keep going until no more can be evaluated
+        let mut values = HashMap::new();
+        loop {
+            let evaluated = consts
+                .iter()
+                .filter(|crab_const| !values.contains_key(&crab_const.name))
+                .filter_map(|crab_const| {
+                    eval_const(crab_const, &values).map(|value| (crab_const.name.clone(), value))
+                })
+                .collect::<Vec<_>>();
+            if evaluated.is_empty() {
+                break;
+            }
+            values.extend(evaluated);
+        }
+
+        Self {
+            consts: consts
+                .into_iter()
+                .map(|crab_const| {
+                    let value = values.get(&crab_const.name).copied();
+                    (
+                        crab_const.name,
+                        ManagedConst {
+                            crab_type: crab_const.crab_type,
+                            value,
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    ///
+    /// Get a constant by name
+    ///
+    pub fn get(&self, name: &Ident) -> Option<&ManagedConst> {
+        self.consts.get(name)
+    }
+
+    ///
+    /// Get the global that holds every constant that was evaluated at compile time
+    ///
+    /// Returns:
+    /// The name of each global, paired with its value
+    ///
+    pub fn get_globals(&self) -> Vec<(Ident, QuillConstant)> {
+        self.consts
+            .iter()
+            .filter_map(|(name, managed)| {
+                managed.value.map(|value| (const_global_name(name), value))
+            })
+            .collect()
+    }
+}
+
+///
+/// Evaluates a constant at compile time, if it only depends on constants that have already been evaluated
+///
+/// Params:
+/// * `crab_const` - The constant to evaluate
+/// * `values` - The value of every constant that has been evaluated so far
+///
+/// Returns:
+/// The constant's value, or None if it can't be evaluated at compile time
+///
+fn eval_const(
+    crab_const: &CrabConst,
+    values: &HashMap<Ident, QuillConstant>,
+) -> Option<QuillConstant> {
+    let scalar_t = get_scalar_type(&crab_const.crab_type)?;
+    eval_expression(&crab_const.value, values).filter(|value| value.get_type() == scalar_t)
+}
+
+fn eval_expression(
+    expr: &Expression,
+    values: &HashMap<Ident, QuillConstant>,
+) -> Option<QuillConstant> {
+    let value = match &expr.this {
+        // Literals are parsed into the struct that wraps them
+        ExpressionType::STRUCT_INIT(si) if get_scalar_type(&si.id).is_some() => {
+            match (si.fields.as_slice(), &si.update) {
+                ([field], None) => eval_expression(&field.value, values)?,
+                _ => return None,
+            }
+        }
+        ExpressionType::PRIM(Primitive::UINT(value)) => QuillConstant::Int(64, *value),
+        ExpressionType::PRIM(Primitive::BOOL(value)) => QuillConstant::Bool(*value),
+        ExpressionType::VARIABLE(name) => *values.get(name)?,
+        ExpressionType::STATIC_FN_CALL(sfc) => *values.get(&sfc.as_assoc_const()?)?,
+        _ => return None,
+    };
+    eval_chain(value, expr.next.as_deref(), values)
+}
+
+///
+/// Evaluates the rest of an expression chain, which can only be operators
+///
+fn eval_chain(
+    value: QuillConstant,
+    next: Option<&Expression>,
+    values: &HashMap<Ident, QuillConstant>,
+) -> Option<QuillConstant> {
+    match next {
+        None => Some(value),
+        Some(Expression {
+            this: ExpressionType::FN_CALL(fc),
+            next,
+            ..
+        }) => {
+            let result = eval_operator(value, fc, values)?;
+            eval_chain(result, next.as_deref(), values)
+        }
+        Some(_) => None,
+    }
+}
+
+///
+/// Evaluates an operator the same way the builtin Int and Bool operators would at runtime
+/// Ints are compared as unsigned, and wrap around when they overflow
+///
+fn eval_operator(
+    lhs: QuillConstant,
+    fc: &FnCall,
+    values: &HashMap<Ident, QuillConstant>,
+) -> Option<QuillConstant> {
+    let rhs = match (fc.pos_args.as_slice(), fc.named_args.is_empty()) {
+        ([arg], true) => eval_expression(arg, values)?,
+        _ => return None,
+    };
+    let name = &fc.name;
+    Some(match (lhs, rhs) {
+        (QuillConstant::Int(bits, lhs), QuillConstant::Int(_, rhs)) => match name {
+            _ if name == &operator_add_name() => QuillConstant::Int(bits, lhs.wrapping_add(rhs)),
+            _ if name == &operator_sub_name() => QuillConstant::Int(bits, lhs.wrapping_sub(rhs)),
+            _ if name == &operator_mult_name() => QuillConstant::Int(bits, lhs.wrapping_mul(rhs)),
+            // Dividing by zero is left for the program to do at runtime
+            _ if name == &operator_div_name() && rhs != 0 => QuillConstant::Int(bits, lhs / rhs),
+            _ if name == &operator_lt_name() => QuillConstant::Bool(lhs < rhs),
+            _ if name == &operator_gt_name() => QuillConstant::Bool(lhs > rhs),
+            _ if name == &operator_lte_name() => QuillConstant::Bool(lhs <= rhs),
+            _ if name == &operator_gte_name() => QuillConstant::Bool(lhs >= rhs),
+            _ if name == &operator_eq_name() => QuillConstant::Bool(lhs == rhs),
+            _ => return None,
+        },
+        (QuillConstant::Bool(lhs), QuillConstant::Bool(rhs)) if name == &operator_eq_name() => {
+            QuillConstant::Bool(lhs == rhs)
+        }
+        _ => return None,
+    })
+}
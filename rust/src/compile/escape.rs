@@ -0,0 +1,145 @@
+use crate::compile::TypeManager;
+use crate::parse::ast::{
+    CodeBlock, Expression, ExpressionType, FnCall, Ident, Primitive, Statement,
+};
+use std::collections::HashSet;
+
+///
+/// Finds the local variables of a fn body whose values never escape the fn, so they can live on its stack
+/// A variable qualifies when every assignment to it is a struct init, and it is only ever used to read fields
+/// Anything else, such as passing it to a fn, returning it, or calling a method on it, may keep it alive past the fn
+/// Values that implement Drop never qualify, because their drop() gets self and could keep it alive too
+///
+/// Params:
+/// * `body` - The body of the fn
+/// * `types` - The TypeManager to check struct types with
+///
+/// Returns:
+/// The names of the variables that never escape
+///
+pub(super) fn find_stack_vars(body: &CodeBlock, types: &TypeManager) -> HashSet<Ident> {
+    let mut analysis = EscapeAnalysis {
+        types,
+        candidates: HashSet::new(),
+        escaped: HashSet::new(),
+    };
+    analysis.visit_codeblock(body);
+    analysis
+        .candidates
+        .difference(&analysis.escaped)
+        .cloned()
+        .collect()
+}
+
+struct EscapeAnalysis<'a> {
+    /// Used to check which struct inits build values that implement Drop
+    types: &'a TypeManager,
+
+    /// Every variable that is assigned a struct init
+    candidates: HashSet<Ident>,
+
+    /// Every variable that is assigned anything else, reassigned, or used as more than a source of fields
+    escaped: HashSet<Ident>,
+}
+
+impl EscapeAnalysis<'_> {
+    fn visit_codeblock(&mut self, codeblock: &CodeBlock) {
+        codeblock
+            .statements
+            .iter()
+            .for_each(|stmt| self.visit_statement(stmt));
+    }
+
+    fn visit_statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::IF_STATEMENT(is) => {
+                self.visit_expression(&is.expr);
+                self.visit_codeblock(&is.then);
+                if let Some(else_stmt) = &is.else_stmt {
+                    self.visit_codeblock(else_stmt);
+                }
+            }
+            Statement::WHILE_STATEMENT(ws) => {
+                self.visit_expression(&ws.expr);
+                self.visit_codeblock(&ws.then);
+            }
+            Statement::DO_WHILE_STATEMENT(dws) => {
+                self.visit_expression(&dws.expr);
+                self.visit_codeblock(&dws.then);
+            }
+            Statement::EXPRESSION(expr) => self.visit_expression(expr),
+            Statement::ASSIGNMENT(ass) => {
+                match (&ass.expr.this, &ass.expr.next) {
+                    (ExpressionType::STRUCT_INIT(si), None) if !self.types.is_droppable(&si.id) => {
+                        self.candidates.insert(ass.var_name.clone())
+                    }
+                    _ => self.escaped.insert(ass.var_name.clone()),
+                };
+                self.visit_expression(&ass.expr);
+            }
+            // The old value would be released, which a value on the stack can't be
+            Statement::REASSIGNMENT(reass) => {
+                self.escaped.insert(reass.var_name.clone());
+                self.visit_expression(&reass.expr);
+            }
+            Statement::RETURN(ret) => ret.iter().for_each(|expr| self.visit_expression(expr)),
+        }
+    }
+
+    fn visit_expression(&mut self, expr: &Expression) {
+        match &expr.this {
+            ExpressionType::VARIABLE(name) => {
+                // Reading a field only borrows the field, so the variable itself stays put
+                let reads_field = matches!(
+                    expr.next.as_deref(),
+                    Some(Expression {
+                        this: ExpressionType::VARIABLE(_),
+                        ..
+                    })
+                );
+                if !reads_field {
+                    self.escaped.insert(name.clone());
+                }
+            }
+            ExpressionType::PRIM(Primitive::LIST(exprs)) => exprs
+                .iter()
+                .for_each(|element| self.visit_expression(element)),
+            ExpressionType::PRIM(_) => {}
+            ExpressionType::STRUCT_INIT(si) => {
+                si.fields
+                    .iter()
+                    .for_each(|field| self.visit_expression(&field.value));
+                if let Some(update) = &si.update {
+                    self.visit_expression(update);
+                }
+            }
+            ExpressionType::FN_CALL(fc) => self.visit_fn_call(fc),
+            ExpressionType::STATIC_FN_CALL(sfc) => self.visit_fn_call(&sfc.call),
+        }
+        if let Some(next) = &expr.next {
+            self.visit_chained(next);
+        }
+    }
+
+    ///
+    /// Visits the rest of an expression chain
+    /// Variables in the rest of a chain are field names rather than local variables, so only args are visited
+    ///
+    fn visit_chained(&mut self, expr: &Expression) {
+        if let ExpressionType::FN_CALL(fc) = &expr.this {
+            self.visit_fn_call(fc);
+        }
+        if let Some(next) = &expr.next {
+            self.visit_chained(next);
+        }
+    }
+
+    fn visit_fn_call(&mut self, fc: &FnCall) {
+        fc.pos_args
+            .iter()
+            .for_each(|arg| self.visit_expression(arg));
+        fc.named_args
+            .iter()
+            .for_each(|arg| self.visit_expression(&arg.expr));
+    }
+}
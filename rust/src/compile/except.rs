@@ -1,4 +1,5 @@
 use crate::parse::ast::{CrabType, Ident, StructId};
+use crate::parse::ParseError;
 use crate::quill::QuillError;
 use thiserror::Error;
 
@@ -59,6 +60,9 @@ pub enum CompileError {
     #[error("Initialization of struct {0} expects field {1}, which has not been supplied")]
     StructInitFieldName(Ident, Ident),
 
+    #[error("Initialization of struct {0} copies its fields from a value of type {2}, but it must be {1}")]
+    StructUpdateType(Ident, CrabType, CrabType),
+
     #[error("Struct {0} does not contain a field with name {1}")]
     StructFieldName(CrabType, Ident),
 
@@ -68,6 +72,51 @@ pub enum CompileError {
     #[error("Function expected argument with name {0}, but none was supplied")]
     ArgumentNotSupplied(Ident),
 
+    #[error("Field {1} of struct {0} expects type {2}, instead got {3}")]
+    FieldType(Ident, Ident, CrabType, CrabType),
+
+    #[error("Function {0} should return type {1}, instead got {2}")]
+    ReturnType(Ident, CrabType, CrabType),
+
+    #[error("Variable {0} has type {1}, so it cannot be reassigned to a value of type {2}")]
+    ReassignmentType(Ident, CrabType, CrabType),
+
+    #[error("Constant {0} has type {1}, instead got {2}")]
+    ConstType(Ident, CrabType, CrabType),
+
+    #[error("Conditions must have type Bool, instead got {0}")]
+    ConditionType(CrabType),
+
+    #[error("Could not infer template {1} of function {0} from its arguments, so it must be supplied explicitly")]
+    AmbiguousTemplate(Ident, Ident),
+
+    #[error("Template {1} of function {0} was inferred as both {2} and {3}")]
+    ConflictingTemplate(Ident, Ident, CrabType, CrabType),
+
+    #[error("Template {0} must implement {1}, but {2} does not")]
+    UnsatisfiedBound(Ident, Ident, CrabType),
+
+    #[error("Template {0} is bounded by {1}, which is not an interface")]
+    BoundNotAnInterface(Ident, Ident),
+
+    #[error("No definition of function {0} accepts the given arguments")]
+    NoMatchingOverload(Ident),
+
+    #[error("Call of function {0} is ambiguous, because several of its definitions accept the given arguments and none is more specific than the others")]
+    AmbiguousOverload(Ident),
+
+    #[error("Function {0} is static, so it must be called on its type instead of on a value")]
+    StaticFnOnValue(Ident),
+
+    #[error("Function {0} is a method, so it must be called on a value instead of on its type")]
+    MethodOnType(Ident),
+
+    #[error("Compilation failed because {0} denied warnings were emitted")]
+    DeniedWarnings(usize),
+
     #[error(transparent)]
     QuillErr(#[from] QuillError),
+
+    #[error(transparent)]
+    ParseErr(#[from] ParseError),
 }
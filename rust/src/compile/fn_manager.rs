@@ -1,17 +1,24 @@
-use crate::compile::{CompileError, CrabValue, Result, TypeManager};
+use crate::compile::{
+    get_scalar_type, select_overload, CompileError, CrabValue, Result, TypeManager,
+};
 use crate::parse::ast::{
-    CrabType, FnCall, Func, FuncSignature, Ident, NamedParam, PosParam, StructId,
+    CrabType, FnBodyType, FnCall, Func, FuncSignature, Ident, NamedParam, PosParam, StructId,
+};
+use crate::util::{
+    drop_fn_name, magic_main_func_name, release_field_name, release_fn_name, unbox_fn_name,
+    vtable_name, ListFunctional, MapFunctional,
 };
-use crate::util::{magic_main_func_name, ListFunctional, MapFunctional};
 use std::cell::RefCell;
 use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::TryFrom;
 use std::default::Default;
 use std::rc::Rc;
 
 #[derive(Debug, Clone)]
 pub(super) struct FnManager {
-    /// All fns that have been defined outside of impl blocks
-    fn_sources: HashMap<Ident, Func>,
+    /// All fns that have been defined outside of impl blocks, indexed by name
+    /// Every overload of a fn shares the same name
+    fn_sources: HashMap<Ident, Vec<Func>>,
 
     /// All the fns that have been defined inside impl blocks
     impl_sources: HashMap<ImplFuncId, Func>,
@@ -22,6 +29,16 @@ pub(super) struct FnManager {
     /// All registered fns that have not been built yet
     fn_build_queue: Vec<Func>,
 
+    /// The signature of every source fn that has been called at least once
+    /// Signatures are used rather than names, so that an unused overload is still reported
+    called_sources: HashSet<FuncSignature>,
+
+    /// All of the vtables that are required to build interface objects, indexed by vtable name
+    vtables: HashMap<Ident, VTable>,
+
+    /// The mangled name of the drop fn of every Drop type a value has been built for, indexed by mangled type name
+    drop_fns: HashMap<Ident, Ident>,
+
     /// All of the registered types. Required for resolving params
     types: Rc<RefCell<TypeManager>>,
 }
@@ -34,6 +51,9 @@ impl FnManager {
             impl_sources: Default::default(),
             registered_fns: Default::default(),
             fn_build_queue: Default::default(),
+            called_sources: Default::default(),
+            vtables: Default::default(),
+            drop_fns: Default::default(),
         }
     }
 
@@ -49,7 +69,9 @@ impl FnManager {
         match &source.signature.caller_id {
             None => {
                 self.fn_sources
-                    .insert(source.signature.name.clone(), source);
+                    .entry(source.signature.name.clone())
+                    .or_default()
+                    .push(source);
             }
             Some(si) => {
                 self.impl_sources.insert(
@@ -70,20 +92,23 @@ impl FnManager {
         let main = self
             .fn_sources
             .get(&magic_main_func_name())
+            .and_then(|mains| mains.first())
             .ok_or(CompileError::NoMain)?;
         self.fn_build_queue.push(main.clone());
         self.registered_fns.insert(main.signature.clone());
+        self.called_sources.insert(main.signature.clone());
         Ok(())
     }
 
     ///
-    /// Removes the next function signature from the build queue and returns it
+    /// Removes the next function from the build queue and returns it
+    /// The returned function has not been mangled yet
     ///
     /// Returns:
-    /// The function signature removed from the queue
+    /// The function removed from the queue
     ///
     pub fn pop_build_queue(&mut self) -> Option<Func> {
-        self.fn_build_queue.pop().map(|func| func.mangled())
+        self.fn_build_queue.pop()
     }
 
     ///
@@ -98,9 +123,13 @@ impl FnManager {
 
     ///
     /// Retrieve a copy of a function's signature from the registered functions
+    /// If the function is overloaded, the args decide which signature is returned
     ///
     /// Params:
     /// * `name` - The name of the signature to get
+    /// * `caller_opt` - The caller of the function, if any
+    /// * `pos_values` - The positional args of the call, including self for methods
+    /// * `named_values` - The named args that were supplied in the call
     ///
     /// Returns:
     /// A copy of the requested signature
@@ -109,16 +138,24 @@ impl FnManager {
         &self,
         name: &Ident,
         caller_opt: Option<CrabType>,
+        pos_values: &[CrabValue],
+        named_values: &BTreeMap<Ident, CrabValue>,
     ) -> Result<FuncSignature> {
-        Ok(self.get_source(&name, caller_opt)?.signature)
+        Ok(self
+            .get_source(&name, caller_opt, pos_values, named_values)?
+            .signature)
     }
 
     ///
     /// Gets the FuncSignature required to build a given FnCall
     /// This function automagically resolves interface params to match the type in the call
+    /// Params that are only interfaces because of their tmpls are left alone, so they take interface objects
+    /// If the call does not supply any tmpls, they will be inferred from the argument types
+    /// Every tmpl must implement the interfaces it is bounded by
     /// If the returned signature has not been registered it will be added to the build queue
     /// The caller is always the value of the parents in the FnCall's ExpressionChain, or None if
     /// the FnCall's ExpressionChain does not have any parents
+    /// For static fns, the caller is the type the fn was called on
     ///
     /// Params:
     /// * `call` - The FnCall to get the FuncSignature of
@@ -131,28 +168,77 @@ impl FnManager {
         pos_values: &[CrabValue],
         named_values: &BTreeMap<Ident, CrabValue>,
     ) -> Result<FuncSignature> {
-        let source_fn = self
-            .get_source(&call.name, caller_opt.clone())?
-            .resolve(caller_opt.clone(), call.tmpls.clone())?;
+        let source_fn =
+            self.get_source(&call.name, caller_opt.clone(), pos_values, named_values)?;
+        self.called_sources.insert(source_fn.signature.clone());
+        // Static fns are called on a type, so they don't get a self param
+        let self_count = match &caller_opt {
+            Some(_) if !source_fn.signature.is_static => 1,
+            _ => 0,
+        };
+        let tmpls = match call.tmpls.is_empty() && !source_fn.signature.tmpls.is_empty() {
+            true => {
+                let pos_ts = pos_values
+                    .iter()
+                    .skip(self_count)
+                    .map(|value| value.crab_type.clone())
+                    .collect::<Vec<_>>();
+                let named_ts = named_values
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.crab_type.clone()))
+                    .collect::<Vec<_>>();
+                source_fn.signature.infer_call_tmpls(&pos_ts, &named_ts)?
+            }
+            false => call.tmpls.clone(),
+        };
+        self.types.borrow().check_bounds(
+            &source_fn.signature.bounds,
+            &source_fn.signature.tmpls,
+            &tmpls,
+        )?;
+        // Methods may bound the tmpls of their struct more tightly than the struct does
+        if let (Some(caller_id), Some(CrabType::TMPL(_, caller_tmpls))) =
+            (&source_fn.signature.caller_id, &caller_opt)
+        {
+            self.types.borrow().check_bounds(
+                &source_fn.signature.bounds,
+                &caller_id.tmpls,
+                caller_tmpls,
+            )?;
+        }
+        let declared_fn = source_fn.clone();
+        let source_fn = source_fn.resolve(caller_opt.clone(), tmpls)?;
 
         let pos_params = match &caller_opt {
-            None => vec![],
-            Some(caller) => {
+            Some(caller) if self_count == 1 => {
                 vec![PosParam {
                     name: String::from("self"),
                     crab_type: caller.clone(),
                 }]
             }
+            _ => vec![],
         };
-        let pos_params = pos_values
+        let (arg_values, collected) = source_fn
+            .signature
+            .split_variadic_args(&pos_values[self_count.min(pos_values.len())..]);
+        let pos_params = arg_values
             .iter()
-            .skip(pos_params.len())
             .zip(source_fn.signature.pos_params.iter())
-            .try_fold(pos_params, |pos_params, (value, param)| {
-                match self.types.borrow().is_a(&value.crab_type, &param.crab_type) {
+            .zip(declared_fn.signature.pos_params.iter())
+            .try_fold(
+                pos_params,
+                |pos_params, ((value, param), declared)| match self
+                    .types
+                    .borrow()
+                    .is_a(&value.crab_type, &param.crab_type)
+                {
                     true => Result::Ok(pos_params.fpush(PosParam {
                         name: param.name.clone(),
-                        crab_type: value.crab_type.clone(),
+                        crab_type: self.param_type(
+                            &declared.crab_type,
+                            &param.crab_type,
+                            &value.crab_type,
+                        ),
                     })),
                     false => Result::Err(CompileError::ArgumentType(
                         call.name.clone(),
@@ -160,8 +246,31 @@ impl FnManager {
                         param.crab_type.clone(),
                         value.crab_type.clone(),
                     )),
+                },
+            )?;
+        // The collected args are passed as a single list of the type the variadic param collects
+        let pos_params = match (
+            source_fn.signature.variadic_type(),
+            source_fn.signature.pos_params.last(),
+        ) {
+            (Some(variadic_t), Some(param)) => {
+                match collected
+                    .iter()
+                    .find(|value| !self.types.borrow().is_a(&value.crab_type, variadic_t))
+                {
+                    Some(value) => {
+                        return Err(CompileError::ArgumentType(
+                            call.name.clone(),
+                            param.name.clone(),
+                            variadic_t.clone(),
+                            value.crab_type.clone(),
+                        ))
+                    }
+                    None => pos_params.fpush(param.clone()),
                 }
-            })?;
+            }
+            _ => pos_params,
+        };
         let named_params = named_values
             .iter()
             .zip(source_fn.signature.named_params.iter())
@@ -176,7 +285,14 @@ impl FnManager {
                         param.name.clone(),
                         NamedParam {
                             name: param.name.clone(),
-                            crab_type: arg.crab_type.clone(),
+                            crab_type: match declared_fn.signature.named_params.get(&param.name) {
+                                Some(declared) => self.param_type(
+                                    &declared.crab_type,
+                                    &param.crab_type,
+                                    &arg.crab_type,
+                                ),
+                                None => arg.crab_type.clone(),
+                            },
                             expr: param.expr.clone(),
                         },
                     )),
@@ -196,30 +312,222 @@ impl FnManager {
             ..source_fn.signature.clone()
         };
 
-        // Always register, only add to build_queue if this func wasn't already registered
-        if self.registered_fns.insert(generated_signature.clone()) {
+        Ok(self.register(generated_signature, source_fn.body))
+    }
+
+    ///
+    /// Gets the name of the vtable that allows values of a concrete type to be used as interface objects
+    /// Every fn the interface declares is registered to be built for the concrete type
+    ///
+    /// Params:
+    /// * `ct` - The concrete type of the values
+    /// * `interface` - The name of the interface the values are used as
+    ///
+    /// Returns:
+    /// The name of the vtable
+    ///
+    pub fn get_vtable(&mut self, ct: &CrabType, interface: &Ident) -> Result<Ident> {
+        let concrete_name = StructId::try_from(ct.clone())?.mangle();
+        let name = vtable_name(interface, &concrete_name);
+        if !self.vtables.contains_key(&name) {
+            let interface_fns = self.types.borrow().get_interface_fns(interface)?;
+            // Boxed Ints and Bools go through a fn that unboxes self before calling the method
+            let is_boxed = get_scalar_type(ct).is_some();
+            let (fns, unboxed) = interface_fns.into_iter().try_fold(
+                (
+                    HashMap::from([(release_field_name(), release_fn_name(&concrete_name))]),
+                    vec![],
+                ),
+                |(fns, unboxed), interface_fn| {
+                    let signature = self.get_method_signature(&interface_fn.name, ct)?;
+                    Result::Ok(match is_boxed {
+                        true => (
+                            fns.finsert(interface_fn.name, unbox_fn_name(&signature.name)),
+                            unboxed.fpush(signature),
+                        ),
+                        false => (fns.finsert(interface_fn.name, signature.name), unboxed),
+                    })
+                },
+            )?;
+            self.vtables.insert(
+                name.clone(),
+                VTable {
+                    interface: interface.clone(),
+                    fns,
+                    unboxed,
+                },
+            );
+        }
+        Ok(name)
+    }
+
+    ///
+    /// Gets the name of the fn that drops values of a type that implements Drop
+    /// The drop fn is registered to be built the first time it is requested
+    ///
+    /// Params:
+    /// * `ct` - The type of the values
+    ///
+    /// Returns:
+    /// The mangled name of the drop fn
+    ///
+    pub fn get_drop_fn(&mut self, ct: &CrabType) -> Result<Ident> {
+        let struct_name = StructId::try_from(ct.clone())?.mangle();
+        if let Some(name) = self.drop_fns.get(&struct_name) {
+            return Ok(name.clone());
+        }
+        let signature = self.get_method_signature(&drop_fn_name(), ct)?;
+        self.drop_fns.insert(struct_name, signature.name.clone());
+        Ok(signature.name)
+    }
+
+    ///
+    /// Get every drop fn that has been requested with get_drop_fn, indexed by mangled type name
+    ///
+    pub fn get_drop_fns(&self) -> &HashMap<Ident, Ident> {
+        &self.drop_fns
+    }
+
+    ///
+    /// Get every vtable that has been requested with get_vtable, indexed by name
+    ///
+    pub fn get_vtables(&self) -> &HashMap<Ident, VTable> {
+        &self.vtables
+    }
+
+    ///
+    /// Get the names of every source function that has never been registered to be built
+    /// This is only meaningful once the build queue has been emptied
+    ///
+    /// Params:
+    /// * `ignored` - The display names of functions that should never be reported, such as library functions
+    ///
+    /// Returns:
+    /// The display names of all unused functions, in sorted order
+    ///
+    pub fn get_unused_fns(&self, ignored: &HashSet<Ident>) -> Vec<Ident> {
+        let mut unused: Vec<Ident> = self
+            .fn_sources
+            .values()
+            .flatten()
+            .chain(self.impl_sources.values())
+            .filter(|func| !self.called_sources.contains(&func.signature))
+            .map(|func| func.signature.display_name())
+            .filter(|name| !ignored.contains(name))
+            .collect();
+        unused.sort();
+        unused
+    }
+
+    ///
+    /// Gets the FuncSignature of a method, with its params exactly as they were declared
+    /// If the returned signature has not been registered it will be added to the build queue
+    ///
+    fn get_method_signature(&mut self, name: &Ident, caller: &CrabType) -> Result<FuncSignature> {
+        let source_fn = self.get_source(name, Some(caller.clone()), &[], &BTreeMap::new())?;
+        self.called_sources.insert(source_fn.signature.clone());
+        let source_fn = source_fn.resolve(Some(caller.clone()), vec![])?;
+        let pos_params = vec![PosParam {
+            name: String::from("self"),
+            crab_type: caller.clone(),
+        }]
+        .into_iter()
+        .chain(source_fn.signature.pos_params.clone())
+        .collect();
+        let generated_signature = FuncSignature {
+            pos_params,
+            ..source_fn.signature
+        };
+        Ok(self.register(generated_signature, source_fn.body))
+    }
+
+    ///
+    /// Always register the signature, but only add it to the build queue if it wasn't already registered
+    ///
+    /// Returns:
+    /// The mangled signature
+    ///
+    fn register(&mut self, signature: FuncSignature, body: FnBodyType) -> FuncSignature {
+        if self.registered_fns.insert(signature.clone()) {
             self.fn_build_queue.push(Func {
-                body: source_fn.body.clone(),
-                signature: generated_signature.clone(),
+                body,
+                signature: signature.clone(),
             });
         }
+        signature.mangled()
+    }
 
-        Ok(generated_signature.mangled())
+    ///
+    /// Params declared with an interface type are monomorphised to match the type of their argument
+    /// Params that only became interfaces when their tmpls were resolved take interface objects instead,
+    /// because the same fn must be able to accept values of any type that implements the interface
+    ///
+    fn param_type(&self, declared: &CrabType, resolved: &CrabType, arg: &CrabType) -> CrabType {
+        let types = self.types.borrow();
+        match !types.is_interface_type(declared) && types.is_interface_type(resolved) {
+            true => resolved.clone(),
+            false => arg.clone(),
+        }
     }
 
-    fn get_source(&self, name: &Ident, caller_opt: Option<CrabType>) -> Result<Func> {
-        let func_opt = match caller_opt {
+    ///
+    /// Get the source of the fn a call refers to
+    /// Fns outside of impl blocks may be overloaded, so the overload is chosen from the types of the args
+    ///
+    fn get_source(
+        &self,
+        name: &Ident,
+        caller_opt: Option<CrabType>,
+        pos_values: &[CrabValue],
+        named_values: &BTreeMap<Ident, CrabValue>,
+    ) -> Result<Func> {
+        match caller_opt {
             Some(caller) => self
                 .impl_sources
-                .get(&ImplFuncId::from_crabtype(name.clone(), &caller)?),
-            None => self.fn_sources.get(name),
-        };
-        func_opt
-            .ok_or(CompileError::CouldNotFindFunction(name.clone()))
-            .cloned()
+                .get(&ImplFuncId::from_crabtype(name.clone(), &caller)?)
+                .ok_or(CompileError::CouldNotFindFunction(name.clone()))
+                .cloned(),
+            None => {
+                let sources = self
+                    .fn_sources
+                    .get(name)
+                    .ok_or(CompileError::CouldNotFindFunction(name.clone()))?
+                    .iter()
+                    .collect::<Vec<_>>();
+                let pos_ts = pos_values
+                    .iter()
+                    .map(|value| Some(value.crab_type.clone()))
+                    .collect::<Vec<_>>();
+                let named_ts = named_values
+                    .iter()
+                    .map(|(name, value)| (name.clone(), Some(value.crab_type.clone())))
+                    .collect::<Vec<_>>();
+                let types = self.types.borrow();
+                let source = select_overload(name, &sources, &pos_ts, &named_ts, |lhs, rhs| {
+                    types.is_a(lhs, rhs)
+                })?;
+                Ok(source.clone())
+            }
+        }
     }
 }
 
+///
+/// The fns that let values of one concrete type be used as objects of an interface
+///
+#[derive(Debug, Clone)]
+pub(super) struct VTable {
+    /// The name of the interface this vtable implements
+    pub interface: Ident,
+
+    /// The mangled name of the concrete fn for each fn the interface declares, indexed by fn name
+    /// Also holds the fn that releases values of the concrete type, under `release_field_name()`
+    pub fns: HashMap<Ident, Ident>,
+
+    /// The methods that need an unbox fn, because the concrete type is an Int or Bool in a box
+    pub unboxed: Vec<FuncSignature>,
+}
+
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 struct ImplFuncId {
     func_name: Ident,
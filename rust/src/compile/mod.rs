@@ -17,6 +17,32 @@ mod builtins;
 #[allow(unused_imports)]
 pub(super) use builtins::*;
 
+mod overload;
+#[allow(unused_imports)]
+pub(super) use overload::*;
+
+mod refcount;
+#[allow(unused_imports)]
+pub(super) use refcount::*;
+
+mod returns;
+#[allow(unused_imports)]
+pub(super) use returns::*;
+
+mod escape;
+#[allow(unused_imports)]
+pub(super) use escape::*;
+
+mod consts;
+#[allow(unused_imports)]
+pub(super) use consts::*;
+
 mod fn_manager;
 #[allow(unused_imports)]
 pub(super) use fn_manager::*;
+
+mod warnings;
+pub use warnings::*;
+
+mod semantics;
+pub use semantics::*;
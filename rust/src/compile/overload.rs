@@ -0,0 +1,119 @@
+use crate::compile::{CompileError, Result};
+use crate::parse::ast::{CrabType, Func, FuncSignature, Ident, StructId};
+
+///
+/// Choose which definition of an overloaded fn a call refers to
+/// Every definition that accepts the call's args is a candidate, and the most specific candidate wins
+/// A candidate is more specific than another if every one of its params could be passed to the other
+/// When a fn has only one definition it is always chosen, so the usual argument errors are reported
+///
+/// Params:
+/// * `name` - The name of the fn being called
+/// * `sources` - Every definition of the fn
+/// * `pos_ts` - The type of each positional arg, or None if it depends on an unresolved template
+/// * `named_ts` - The name and type of each named arg, or None if it depends on an unresolved template
+/// * `is_a` - Returns whether a value of the first type may be passed as the second type
+///
+/// Returns:
+/// The definition the call refers to
+///
+pub(super) fn select_overload<'a, F>(
+    name: &Ident,
+    sources: &[&'a Func],
+    pos_ts: &[Option<CrabType>],
+    named_ts: &[(Ident, Option<CrabType>)],
+    is_a: F,
+) -> Result<&'a Func>
+where
+    F: Fn(&CrabType, &CrabType) -> bool,
+{
+    if let [source] = sources {
+        return Ok(*source);
+    }
+    let candidates = sources
+        .iter()
+        .copied()
+        .filter(|source| accepts(&source.signature, pos_ts, named_ts, &is_a))
+        .collect::<Vec<_>>();
+    let most_specific = candidates
+        .iter()
+        .copied()
+        .filter(|candidate| {
+            let param_ts = param_types(&candidate.signature);
+            candidates
+                .iter()
+                .all(|other| accepts(&other.signature, &param_ts, &[], &is_a))
+        })
+        .collect::<Vec<_>>();
+    // Ordinary fns are preferred over variadic fns that are just as specific
+    let not_variadic = most_specific
+        .iter()
+        .copied()
+        .filter(|source| !source.signature.variadic)
+        .collect::<Vec<_>>();
+    match (
+        candidates.as_slice(),
+        most_specific.as_slice(),
+        not_variadic.as_slice(),
+    ) {
+        ([], _, _) => Err(CompileError::NoMatchingOverload(name.clone())),
+        (_, [source], _) | (_, _, [source]) => Ok(*source),
+        _ => Err(CompileError::AmbiguousOverload(name.clone())),
+    }
+}
+
+///
+/// Get the types of args that a fn's pos params stand for
+/// A variadic param stands for a single arg of the type it collects
+///
+fn param_types(signature: &FuncSignature) -> Vec<Option<CrabType>> {
+    let (params, _) = signature.split_variadic_args(&signature.pos_params);
+    params
+        .iter()
+        .map(|param| Some(param.crab_type.clone()))
+        .chain(signature.variadic_type().cloned().map(Some))
+        .collect()
+}
+
+///
+/// Returns whether a fn can be called with args of the given types
+/// Params whose type is one of the fn's own tmpls accept any arg, because the tmpl is inferred from it
+/// A variadic param accepts any number of args, as long as each one has the type it collects
+///
+fn accepts<F>(
+    signature: &FuncSignature,
+    pos_ts: &[Option<CrabType>],
+    named_ts: &[(Ident, Option<CrabType>)],
+    is_a: &F,
+) -> bool
+where
+    F: Fn(&CrabType, &CrabType) -> bool,
+{
+    let accepts_arg = |arg_t: &Option<CrabType>, param_t: &CrabType| match (arg_t, param_t) {
+        (None, _) => true,
+        (Some(_), CrabType::SIMPLE(name))
+            if signature.tmpls.contains(&StructId::from_name(name.clone())) =>
+        {
+            true
+        }
+        (Some(arg_t), _) => is_a(arg_t, param_t),
+    };
+    let (params, _) = signature.split_variadic_args(&signature.pos_params);
+    let (args, collected) = signature.split_variadic_args(pos_ts);
+    let collected_accepted = match signature.variadic_type() {
+        Some(variadic_t) => collected.iter().all(|arg_t| accepts_arg(arg_t, variadic_t)),
+        None => collected.is_empty(),
+    };
+    args.len() == params.len()
+        && args
+            .iter()
+            .zip(params.iter())
+            .all(|(arg_t, param)| accepts_arg(arg_t, &param.crab_type))
+        && collected_accepted
+        && named_ts
+            .iter()
+            .all(|(name, arg_t)| match signature.named_params.get(name) {
+                Some(param) => accepts_arg(arg_t, &param.crab_type),
+                None => false,
+            })
+}
@@ -0,0 +1,265 @@
+use crate::compile::{get_element_type, get_scalar_type, Result};
+use crate::parse::ast::{CrabStruct, CrabType, Ident, StructBody, StructId};
+use crate::quill::{
+    ChildNib, FnNib, IntCmpType, Nib, Quill, QuillFnType, QuillIntType, QuillPointerType,
+    QuillStructType, QuillValue, QuillVoidType,
+};
+use crate::util::{
+    interface_data_field_name, interface_vtable_field_name, length_field_name, list_struct_name,
+    primitive_field_name, refcount_field_name, release_field_name, release_fn_name, self_var_name,
+    string_struct_name,
+};
+use std::convert::TryFrom;
+
+///
+/// Returns whether values of a type are reference counted
+/// Every struct and interface object is, but Ints, Bools and the primitives structs wrap are not
+///
+pub(super) fn is_refcounted(ct: &CrabType) -> bool {
+    matches!(ct, CrabType::SIMPLE(_) | CrabType::TMPL(_, _)) && get_scalar_type(ct).is_none()
+}
+
+///
+/// Adds a reference to a value
+/// Every reference counted struct is malloc'd with a single reference, which belongs to whoever malloc'd it
+///
+/// Params:
+/// * `value` - A pointer to the struct to add a reference to
+///
+pub(super) fn add_retain<N: Nib>(nib: &mut N, value: &QuillValue<QuillPointerType>) -> Result<()> {
+    let count = nib.get_value_from_struct(value, refcount_field_name(), QuillIntType::new(64))?;
+    let one = nib.const_int(64, 1);
+    let count = nib.int_add(&count, &one)?;
+    nib.set_value_in_struct(value, refcount_field_name(), &count)?;
+    Ok(())
+}
+
+///
+/// Removes a reference to a value
+/// Once the last reference is removed, every reference the value holds is removed too, and the value is freed
+///
+/// Params:
+/// * `value` - A pointer to the struct to remove a reference from
+/// * `struct_name` - The mangled name of the value's struct, or of its interface if it is an interface object
+///
+pub(super) fn add_release<N: Nib>(
+    nib: &mut N,
+    value: &QuillValue<QuillPointerType>,
+    struct_name: &str,
+) {
+    nib.add_fn_call(
+        release_fn_name(struct_name),
+        vec![value.clone().into()],
+        QuillVoidType::new(),
+    );
+}
+
+///
+/// Adds a loop over the elements of a list's buffer, from start up to, but not including, end
+///
+/// Params:
+/// * `t_star` - The list's buffer
+/// * `start` - The index of the first element to visit
+/// * `end` - The index after the last element to visit
+/// * `body` - Adds the instructions to run for each element to the loop's Nib
+///
+pub(super) fn add_element_loop<N, F>(
+    nib: &mut N,
+    t_star: &QuillValue<QuillPointerType>,
+    start: &QuillValue<QuillIntType>,
+    end: &QuillValue<QuillIntType>,
+    body: F,
+) -> Result<()>
+where
+    N: Nib,
+    F: FnOnce(&mut ChildNib, &QuillValue<QuillPointerType>) -> Result<()>,
+{
+    let element_t = QuillPointerType::try_from(t_star.get_type().get_inner_type())?;
+    let index_ptr = nib.add_alloca(QuillIntType::new(64));
+    nib.add_store(&index_ptr, start)?;
+
+    let mut loop_nib = nib.create_child();
+    let index = loop_nib.add_load(&index_ptr, QuillIntType::new(64))?;
+    let element = loop_nib.get_list_value(t_star, &index, element_t)?;
+    body(&mut loop_nib, &element)?;
+    let one = loop_nib.const_int(64, 1);
+    let next = loop_nib.int_add(&index, &one)?;
+    loop_nib.add_store(&index_ptr, &next)?;
+    let more = loop_nib.int_cmp(&next, end, IntCmpType::ULT)?;
+    loop_nib.add_cond_loop(&more);
+
+    let any = nib.int_cmp(start, end, IntCmpType::ULT)?;
+    nib.add_cond_branch(&any, loop_nib, None);
+    Ok(())
+}
+
+///
+/// The type of the fn every vtable holds for releasing the value inside an interface object
+///
+pub(super) fn vtable_release_fn_type() -> QuillFnType {
+    QuillFnType::new(
+        QuillFnType::void_return(),
+        vec![(self_var_name(), QuillPointerType::opaque().into())],
+    )
+}
+
+///
+/// Adds the fn that removes a reference to a value of a struct type to the quill
+/// Strings free their buffer, and lists release each of their elements in order before freeing theirs
+/// A struct that implements Drop is dropped first, and then its fields are released in the order they are declared
+///
+/// Params:
+/// * `strct` - The struct to add a release fn for, with all of its tmpls resolved
+/// * `drop_fn` - The mangled name of the struct's drop fn, if it has one
+///
+pub(super) fn add_struct_release_fn(
+    peter: &mut Quill,
+    strct: &CrabStruct,
+    drop_fn: Option<&Ident>,
+) -> Result<()> {
+    let (mut nib, value) = new_release_fn(&strct.id.mangle());
+    let mut free_nib = nib.create_child();
+    match &strct.body {
+        StructBody::FIELDS(fields) => fields
+            .iter()
+            .filter(|field| is_refcounted(&field.crab_type))
+            .try_for_each(|field| {
+                let field_struct = StructId::try_from(field.crab_type.clone())?.mangle();
+                let field_value = free_nib.get_value_from_struct(
+                    &value,
+                    field.name.clone(),
+                    QuillPointerType::new(QuillStructType::new(field_struct.clone())),
+                )?;
+                add_release(&mut free_nib, &field_value, &field_struct);
+                Result::Ok(())
+            })?,
+        StructBody::COMPILER_PROVIDED if strct.id.name == string_struct_name() => {
+            let char_star = free_nib.get_value_from_struct(
+                &value,
+                primitive_field_name(),
+                QuillPointerType::new(QuillIntType::new(8)),
+            )?;
+            free_nib.free(char_star);
+        }
+        StructBody::COMPILER_PROVIDED if strct.id.name == list_struct_name() => {
+            let element_id = &strct.id.tmpls[0];
+            let element_struct = element_id.mangle();
+            let t_star = free_nib.get_value_from_struct(
+                &value,
+                primitive_field_name(),
+                QuillPointerType::new(get_element_type(element_id)),
+            )?;
+            if is_refcounted(&element_id.clone().into()) {
+                let length = free_nib.get_value_from_struct(
+                    &value,
+                    length_field_name(),
+                    QuillIntType::new(64),
+                )?;
+                let zero = free_nib.const_int(64, 0);
+                add_element_loop(
+                    &mut free_nib,
+                    &t_star,
+                    &zero,
+                    &length,
+                    |loop_nib, element| {
+                        add_release(loop_nib, element, &element_struct);
+                        Ok(())
+                    },
+                )?;
+            }
+            free_nib.free(t_star);
+        }
+        // Every other compiler provided struct just wraps a primitive
+        StructBody::COMPILER_PROVIDED => {}
+    }
+    finish_release_fn(peter, nib, value, free_nib, drop_fn)
+}
+
+///
+/// Adds the fn that removes a reference to an interface object to the quill
+/// The value inside the object is released through the object's vtable, because its type isn't known
+///
+/// Params:
+/// * `interface` - The name of the interface
+/// * `vtable_t` - The struct type of the interface's vtables
+///
+pub(super) fn add_interface_release_fn(
+    peter: &mut Quill,
+    interface: &Ident,
+    vtable_t: QuillStructType,
+) -> Result<()> {
+    let (mut nib, object) = new_release_fn(&StructId::from_name(interface.clone()).mangle());
+    let mut free_nib = nib.create_child();
+    let data = free_nib.get_value_from_struct(
+        &object,
+        interface_data_field_name(),
+        QuillPointerType::opaque(),
+    )?;
+    let vtable = free_nib.get_value_from_struct(
+        &object,
+        interface_vtable_field_name(),
+        QuillPointerType::new(vtable_t),
+    )?;
+    let release = free_nib.get_value_from_struct(
+        &vtable,
+        release_field_name(),
+        QuillPointerType::new(vtable_release_fn_type()),
+    )?;
+    free_nib.add_fn_ptr_call(&release, vec![data.into()], QuillVoidType::new())?;
+    finish_release_fn(peter, nib, object, free_nib, None)
+}
+
+///
+/// Creates the Nib of a release fn, along with the value it releases
+///
+fn new_release_fn(struct_name: &str) -> (FnNib, QuillValue<QuillPointerType>) {
+    let value_t = QuillPointerType::new(QuillStructType::new(String::from(struct_name)));
+    let mut nib = FnNib::new(
+        release_fn_name(struct_name),
+        QuillFnType::new(
+            QuillFnType::void_return(),
+            vec![(self_var_name(), value_t.clone().into())],
+        ),
+    );
+    let value = nib.get_fn_param(self_var_name(), value_t);
+    (nib, value)
+}
+
+///
+/// Finishes a release fn by removing a reference from its value,
+/// and then running free_nib to release everything the value holds if that was the last reference
+/// A value with a drop fn is dropped while its last reference is still held,
+/// so that drop() can use self like any other method without freeing it again
+///
+fn finish_release_fn(
+    peter: &mut Quill,
+    mut nib: FnNib,
+    value: QuillValue<QuillPointerType>,
+    mut free_nib: ChildNib,
+    drop_fn: Option<&Ident>,
+) -> Result<()> {
+    free_nib.free(value.clone());
+    if let Some(drop_fn) = drop_fn {
+        let count =
+            nib.get_value_from_struct(&value, refcount_field_name(), QuillIntType::new(64))?;
+        let one = nib.const_int(64, 1);
+        let last = nib.int_cmp(&count, &one, IntCmpType::EQ)?;
+        let mut drop_nib = nib.create_child();
+        drop_nib.add_fn_call(
+            drop_fn.clone(),
+            vec![value.clone().into()],
+            QuillVoidType::new(),
+        );
+        nib.add_cond_branch(&last, drop_nib, None);
+    }
+    let count = nib.get_value_from_struct(&value, refcount_field_name(), QuillIntType::new(64))?;
+    let one = nib.const_int(64, 1);
+    let count = nib.int_sub(&count, &one)?;
+    nib.set_value_in_struct(&value, refcount_field_name(), &count)?;
+    let zero = nib.const_int(64, 0);
+    let unreferenced = nib.int_cmp(&count, &zero, IntCmpType::EQ)?;
+    nib.add_cond_branch(&unreferenced, free_nib, None);
+    nib.add_return(QuillFnType::void_return_value());
+    peter.add_fn(nib);
+    Ok(())
+}
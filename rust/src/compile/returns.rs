@@ -0,0 +1,40 @@
+use crate::parse::ast::{CodeBlock, Statement};
+
+///
+/// Returns whether a codeblock always returns, no matter which way its branches go
+/// Both semantic analysis and codegen use this, so that they always agree on which code is reachable
+///
+/// Params:
+/// * `codeblock` - The codeblock to check
+///
+/// Returns:
+/// True if every path through the codeblock ends in a return, or false otherwise
+///
+pub(super) fn always_returns(codeblock: &CodeBlock) -> bool {
+    codeblock.statements.iter().any(statement_always_returns)
+}
+
+///
+/// Returns whether a single statement always returns, no matter which way its branches go
+///
+/// Params:
+/// * `stmt` - The statement to check
+///
+/// Returns:
+/// True if every path through the statement ends in a return, or false otherwise
+///
+pub(super) fn statement_always_returns(stmt: &Statement) -> bool {
+    match stmt {
+        Statement::RETURN(_) => true,
+        // Without an else, a false condition skips straight past the if
+        Statement::IF_STATEMENT(is) => match &is.else_stmt {
+            None => false,
+            Some(else_stmt) => always_returns(&is.then) && always_returns(else_stmt),
+        },
+        // The body of a while loop may never run at all
+        Statement::WHILE_STATEMENT(_) => false,
+        // The body of a do-while loop always runs at least once
+        Statement::DO_WHILE_STATEMENT(dws) => always_returns(&dws.then),
+        Statement::EXPRESSION(_) | Statement::ASSIGNMENT(_) | Statement::REASSIGNMENT(_) => false,
+    }
+}
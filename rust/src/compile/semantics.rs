@@ -0,0 +1,1025 @@
+use crate::compile::{
+    select_overload, statement_always_returns, CompileError, Result, TypeManager,
+};
+use crate::parse::ast::{
+    Assignment, CodeBlock, CrabAst, CrabConst, CrabStruct, CrabType, DoWhileStmt, Expression,
+    ExpressionType, FnBodyType, FnCall, Func, FuncSignature, Ident, IfStmt, Primitive, Statement,
+    StaticFnCall, StructBody, StructId, StructImpl, StructInit, StructIntr, TmplBounds, WhileStmt,
+};
+use crate::util::{
+    bool_struct_name, const_fn_name, list_struct_name, operator_add_name, string_struct_name,
+    ListFunctional,
+};
+use log::trace;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryInto;
+
+///
+/// A CrabAst that has passed semantic analysis
+/// Every function and template body has been type checked, whether or not it is reachable from main
+/// Every expression in a function body or constant is annotated with the type the checker found for it
+/// This is the only form of the ast that codegen accepts
+///
+#[derive(Debug, Clone)]
+pub struct TypedCrabAst {
+    /// All of the registered structs, interfaces, and intrs
+    pub(super) types: TypeManager,
+
+    /// Every function in the program, including functions defined in impl blocks, with their bodies annotated
+    pub(super) functions: Vec<Func>,
+
+    /// The display names of every function that was defined in a library
+    pub(super) library_items: HashSet<Ident>,
+
+    /// Every constant in the program, including associated constants
+    pub(super) consts: Vec<CrabConst>,
+}
+
+///
+/// Type checks every function in the given CrabAst
+///
+/// Params:
+/// * `ast` - The CrabAst to analyse
+///
+/// Returns:
+/// A TypedCrabAst that is ready to be compiled, or the first type error that was found
+///
+pub fn analyse(ast: CrabAst) -> Result<TypedCrabAst> {
+    trace!("Called compile::analyse");
+    let mut types = TypeManager::new();
+    ast.structs
+        .iter()
+        .try_for_each(|crab_struct| types.register_struct(crab_struct.clone()))?;
+    ast.interfaces
+        .into_iter()
+        .try_for_each(|(_, crab_interface)| types.register_interface(crab_interface))?;
+    ast.intrs
+        .iter()
+        .try_for_each(|crab_intr| types.register_intr(crab_intr.clone()))?;
+    types.verify_struct_bounds()?;
+
+    // Structs get a copy of every default fn they don't define themselves
+    let default_fns = ast
+        .intrs
+        .iter()
+        .try_fold(vec![], |default_fns, crab_intr| {
+            Result::Ok(
+                default_fns
+                    .into_iter()
+                    .chain(get_default_fns(
+                        &types,
+                        crab_intr,
+                        ast.impls.get(&crab_intr.struct_id),
+                    )?)
+                    .collect::<Vec<_>>(),
+            )
+        })?;
+    // Associated constants are managed just like global ones, under a name qualified by their struct
+    let consts = ast
+        .consts
+        .into_iter()
+        .chain(
+            ast.impls
+                .values()
+                .flat_map(|simp| simp.consts.iter().cloned()),
+        )
+        .collect::<Vec<_>>();
+    // Constants that can't be evaluated at compile time are evaluated by a fn every time they are read
+    let const_fns = consts.iter().map(get_const_fn).collect::<Vec<_>>();
+    let library_items = ast
+        .library_items
+        .into_iter()
+        .chain(default_fns.iter().map(|func| func.signature.display_name()))
+        .chain(const_fns.iter().map(|func| func.signature.display_name()))
+        .collect();
+
+    let functions: Vec<Func> = ast
+        .functions
+        .into_iter()
+        .chain(
+            ast.impls
+                .into_values()
+                .flat_map(|simp| simp.fns.into_values()),
+        )
+        .chain(default_fns)
+        .chain(const_fns)
+        .collect();
+
+    let checker = TypeChecker::new(&types, &functions, &consts);
+    let consts = consts
+        .iter()
+        .map(|crab_const| checker.check_const(crab_const))
+        .collect::<Result<Vec<_>>>()?;
+    ast.structs
+        .iter()
+        .try_for_each(|crab_struct| checker.check_struct(crab_struct))?;
+    let functions = functions
+        .iter()
+        .map(|func| checker.check_func(func))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(TypedCrabAst {
+        types,
+        functions,
+        library_items,
+        consts,
+    })
+}
+
+///
+/// Get the fn that evaluates a constant, which takes no args and returns the constant's value
+///
+fn get_const_fn(crab_const: &CrabConst) -> Func {
+    Func {
+        signature: FuncSignature {
+            name: const_fn_name(&crab_const.name),
+            tmpls: vec![],
+            return_type: crab_const.crab_type.clone(),
+            pos_params: vec![],
+            named_params: Default::default(),
+            caller_id: None,
+            bounds: Default::default(),
+            is_static: false,
+            variadic: false,
+        },
+        body: FnBodyType::CODEBLOCK(CodeBlock {
+            statements: vec![Statement::RETURN(Some(crab_const.value.clone()))],
+        }),
+    }
+}
+
+///
+/// Get the default fns a struct inherits from the interfaces it implements
+/// Fns the struct defines itself are skipped, and `Self` is replaced with the struct's type
+///
+/// Params:
+/// * `types` - The TypeManager that every interface is registered with
+/// * `crab_intr` - The interfaces the struct implements
+/// * `simp_opt` - The struct's impl block, if it has one
+///
+/// Returns:
+/// A method for each default fn the struct needs
+///
+fn get_default_fns(
+    types: &TypeManager,
+    crab_intr: &StructIntr,
+    simp_opt: Option<&StructImpl>,
+) -> Result<Vec<Func>> {
+    let self_type = CrabType::from(crab_intr.struct_id.clone());
+    crab_intr
+        .inters
+        .iter()
+        .try_fold(vec![], |ancestry, inter| {
+            Result::Ok(
+                ancestry
+                    .into_iter()
+                    .chain(types.get_interface_ancestry(inter)?)
+                    .collect::<Vec<_>>(),
+            )
+        })?
+        .into_iter()
+        .flat_map(|interface| interface.default_fns.iter())
+        .try_fold(vec![], |default_fns: Vec<Func>, default_fn| {
+            let name = &default_fn.signature.name;
+            let defined = simp_opt.map_or(false, |simp| simp.fns.contains_key(name))
+                || default_fns
+                    .iter()
+                    .any(|existing| &existing.signature.name == name);
+            Result::Ok(match defined {
+                true => default_fns,
+                false => default_fns.fpush(
+                    default_fn
+                        .clone()
+                        .resolve_self(&self_type)?
+                        .method(crab_intr.struct_id.clone())?,
+                ),
+            })
+        })
+}
+
+///
+/// The type of an expression, as far as the type checker can tell
+/// None means the type depends on a template that has not been resolved yet,
+/// so any checks involving it must wait until the function is monomorphised
+/// Templates with bounds are known, because they may only be used through their bounds
+///
+type CheckedType = Option<CrabType>;
+
+///
+/// Everything the type checker knows about the function it is currently checking
+///
+#[derive(Debug, Clone)]
+struct Scope {
+    /// The display name of the function being checked
+    fn_name: Ident,
+    return_type: CrabType,
+
+    /// The names of every template this function can see, from both the function and its caller
+    tmpls: HashSet<Ident>,
+
+    /// The interfaces each bounded template must implement
+    bounds: TmplBounds,
+
+    vars: HashMap<Ident, CheckedType>,
+}
+impl Scope {
+    ///
+    /// Returns whether or not the given type refers to any unresolved template
+    ///
+    fn is_generic(&self, ct: &CrabType) -> bool {
+        match ct {
+            CrabType::SIMPLE(name) => self.tmpls.contains(name),
+            CrabType::TMPL(name, tmpls) => {
+                self.tmpls.contains(name) || tmpls.iter().any(|tmpl| self.is_generic(tmpl))
+            }
+            _ => false,
+        }
+    }
+
+    ///
+    /// Returns the interfaces a type is bounded by, or None if the type is not a bounded template
+    ///
+    fn get_bounds(&self, ct: &CrabType) -> Option<&Vec<Ident>> {
+        match ct {
+            CrabType::SIMPLE(name) => self.bounds.get(name),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Converts a CrabType into a CheckedType, hiding any type that is itself an unbounded template
+    /// Types that are only templated by a template, such as List<T>, are still known
+    ///
+    fn check_type(&self, ct: CrabType) -> CheckedType {
+        match &ct {
+            CrabType::SIMPLE(name) if self.bounds.contains_key(name) => Some(ct),
+            CrabType::SIMPLE(name) | CrabType::TMPL(name, _) if self.tmpls.contains(name) => None,
+            _ => Some(ct),
+        }
+    }
+}
+
+struct TypeChecker<'a> {
+    types: &'a TypeManager,
+
+    /// All fns that have been defined outside of impl blocks, indexed by name
+    /// Every overload of a fn shares the same name
+    fns: HashMap<Ident, Vec<&'a Func>>,
+
+    /// All fns that have been defined inside impl blocks, indexed by struct name and then fn name
+    impl_fns: HashMap<(Ident, Ident), &'a Func>,
+
+    /// The type of every constant, indexed by name
+    /// Associated constants are named after the struct they belong to
+    consts: HashMap<Ident, CrabType>,
+}
+
+impl<'a> TypeChecker<'a> {
+    fn new(types: &'a TypeManager, functions: &'a [Func], consts: &[CrabConst]) -> Self {
+        let (fns, impl_fns) = functions.iter().fold(
+            (HashMap::<Ident, Vec<&Func>>::new(), HashMap::new()),
+            |(mut fns, mut impl_fns), func| {
+                match &func.signature.caller_id {
+                    None => {
+                        fns.entry(func.signature.name.clone())
+                            .or_default()
+                            .push(func);
+                    }
+                    Some(caller_id) => {
+                        impl_fns
+                            .insert((caller_id.name.clone(), func.signature.name.clone()), func);
+                    }
+                }
+                (fns, impl_fns)
+            },
+        );
+        Self {
+            types,
+            fns,
+            impl_fns,
+            consts: consts
+                .iter()
+                .map(|crab_const| (crab_const.name.clone(), crab_const.crab_type.clone()))
+                .collect(),
+        }
+    }
+
+    ///
+    /// Type check the value of a constant
+    /// Constants can be read from anywhere, so their values can only see other constants
+    ///
+    /// Params:
+    /// * `crab_const` - The constant to check
+    ///
+    /// Returns:
+    /// The constant, with its value annotated
+    ///
+    fn check_const(&self, crab_const: &CrabConst) -> Result<CrabConst> {
+        trace!("TypeChecker::check_const");
+        let scope = Scope {
+            fn_name: crab_const.name.clone(),
+            return_type: crab_const.crab_type.clone(),
+            tmpls: HashSet::new(),
+            bounds: Default::default(),
+            vars: HashMap::new(),
+        };
+        self.check_type_exists(&scope, &crab_const.crab_type)?;
+        let mut crab_const = crab_const.clone();
+        let value_t = self.check_expression(&scope, &mut crab_const.value, None)?;
+        self.check_assignable(&scope, &value_t, &crab_const.crab_type, |expected, got| {
+            CompileError::ConstType(crab_const.name.clone(), expected, got)
+        })?;
+        Ok(crab_const)
+    }
+
+    ///
+    /// Type check the default values of a struct's fields
+    /// Like default argument values, they are built wherever the struct is initialized, so they can't see any variables
+    /// Their annotations are thrown away, because codegen reads them from the struct's declaration
+    ///
+    /// Params:
+    /// * `crab_struct` - The struct to check
+    ///
+    fn check_struct(&self, crab_struct: &CrabStruct) -> Result<()> {
+        trace!("TypeChecker::check_struct");
+        let fields = match &crab_struct.body {
+            StructBody::COMPILER_PROVIDED => return Ok(()),
+            StructBody::FIELDS(fields) => fields,
+        };
+        let scope = Scope {
+            fn_name: crab_struct.id.name.clone(),
+            return_type: CrabType::VOID,
+            tmpls: crab_struct
+                .id
+                .tmpls
+                .iter()
+                .map(|tmpl| tmpl.name.clone())
+                .collect(),
+            bounds: crab_struct.bounds.clone(),
+            vars: HashMap::new(),
+        };
+        fields.iter().try_for_each(|field| match &field.default {
+            None => Ok(()),
+            Some(default) => {
+                let default_t = self.check_expression(&scope, &mut default.clone(), None)?;
+                self.check_assignable(&scope, &default_t, &field.crab_type, |expected, got| {
+                    CompileError::FieldType(
+                        crab_struct.id.name.clone(),
+                        field.name.clone(),
+                        expected,
+                        got,
+                    )
+                })
+            }
+        })
+    }
+
+    ///
+    /// Type check a single function, including its params and default argument values
+    /// Default argument values are part of the signature, which is compared against interfaces, so they are left unannotated
+    ///
+    /// Params:
+    /// * `func` - The function to check
+    ///
+    /// Returns:
+    /// The function, with its body annotated
+    ///
+    fn check_func(&self, func: &Func) -> Result<Func> {
+        trace!("TypeChecker::check_func");
+        let signature = &func.signature;
+        let tmpls = signature
+            .tmpls
+            .iter()
+            .chain(
+                signature
+                    .caller_id
+                    .iter()
+                    .flat_map(|caller_id| caller_id.tmpls.iter()),
+            )
+            .map(|tmpl| tmpl.name.clone())
+            .collect();
+        self.types.verify_bounds(&signature.bounds)?;
+        // Methods may add bounds to the tmpls of their struct, on top of the ones the struct declares
+        let bounds = match &signature.caller_id {
+            None => signature.bounds.clone(),
+            Some(caller_id) => signature.bounds.iter().fold(
+                self.types.get_struct_bounds(caller_id)?,
+                |mut bounds, (tmpl, tmpl_bounds)| {
+                    bounds
+                        .entry(tmpl.clone())
+                        .or_default()
+                        .extend(tmpl_bounds.iter().cloned());
+                    bounds
+                },
+            ),
+        };
+        let mut scope = Scope {
+            fn_name: signature.display_name(),
+            return_type: signature.return_type.clone(),
+            tmpls,
+            bounds,
+            vars: HashMap::new(),
+        };
+
+        self.check_type_exists(&scope, &signature.return_type)?;
+        if let Some(caller_id) = &signature.caller_id {
+            if !signature.is_static {
+                let caller_t = scope.check_type(caller_id.clone().into());
+                scope.vars.insert(Ident::from("self"), caller_t);
+            }
+        }
+        signature.pos_params.iter().try_for_each(|param| {
+            self.check_type_exists(&scope, &param.crab_type)?;
+            let param_t = scope.check_type(param.crab_type.clone());
+            scope.vars.insert(param.name.clone(), param_t);
+            Result::Ok(())
+        })?;
+        signature.named_params.values().try_for_each(|param| {
+            self.check_type_exists(&scope, &param.crab_type)?;
+            // Default values are built by the caller, so they can't see any of our params
+            let default_scope = Scope {
+                vars: HashMap::new(),
+                ..scope.clone()
+            };
+            let default_t = self.check_expression(&default_scope, &mut param.expr.clone(), None)?;
+            self.check_assignable(&scope, &default_t, &param.crab_type, |expected, got| {
+                CompileError::ArgumentType(
+                    signature.name.clone(),
+                    param.name.clone(),
+                    expected,
+                    got,
+                )
+            })?;
+            let param_t = scope.check_type(param.crab_type.clone());
+            scope.vars.insert(param.name.clone(), param_t);
+            Result::Ok(())
+        })?;
+
+        let mut func = func.clone();
+        if let FnBodyType::CODEBLOCK(cb) = &mut func.body {
+            let returns = self.check_codeblock(&mut scope, cb)?;
+            if !returns && func.signature.return_type != CrabType::VOID {
+                return Err(CompileError::NoReturn(scope.fn_name));
+            }
+        }
+        Ok(func)
+    }
+
+    ///
+    /// Type check every statement in a codeblock
+    /// Variables declared inside the codeblock are only visible to the rest of the codeblock
+    ///
+    /// Returns:
+    /// True if the codeblock always returns, or false otherwise
+    ///
+    fn check_codeblock(&self, scope: &mut Scope, codeblock: &mut CodeBlock) -> Result<bool> {
+        codeblock
+            .statements
+            .iter_mut()
+            .try_fold(false, |returns, stmt| match returns {
+                true => Ok(true),
+                false => self.check_statement(scope, stmt),
+            })
+    }
+
+    ///
+    /// Type check a single statement
+    ///
+    /// Returns:
+    /// True if the statement always returns, or false otherwise
+    ///
+    fn check_statement(&self, scope: &mut Scope, stmt: &mut Statement) -> Result<bool> {
+        match stmt {
+            Statement::RETURN(ret) => self.check_return(scope, ret),
+            Statement::ASSIGNMENT(ass) => self.check_assignment(scope, ass),
+            Statement::REASSIGNMENT(reass) => self.check_reassignment(scope, reass),
+            Statement::EXPRESSION(expr) => self.check_expression(scope, expr, None).map(|_| ()),
+            Statement::IF_STATEMENT(is) => self.check_if_stmt(scope, is),
+            Statement::WHILE_STATEMENT(ws) => self.check_while_stmt(scope, ws),
+            Statement::DO_WHILE_STATEMENT(dws) => self.check_do_while_stmt(scope, dws),
+        }?;
+        Ok(statement_always_returns(stmt))
+    }
+
+    fn check_return(&self, scope: &Scope, ret: &mut Option<Expression>) -> Result<()> {
+        let ret_t = match ret {
+            None => Some(CrabType::VOID),
+            Some(expr) => self.check_expression(scope, expr, None)?,
+        };
+        let fn_name = scope.fn_name.clone();
+        self.check_assignable(scope, &ret_t, &scope.return_type, |expected, got| {
+            CompileError::ReturnType(fn_name, expected, got)
+        })
+    }
+
+    fn check_assignment(&self, scope: &mut Scope, ass: &mut Assignment) -> Result<()> {
+        let value_t = self.check_expression(scope, &mut ass.expr, None)?;
+        if value_t == Some(CrabType::VOID) {
+            return Err(CompileError::VoidType);
+        }
+        match scope.vars.contains_key(&ass.var_name) {
+            true => Err(CompileError::VarAlreadyExists(ass.var_name.clone())),
+            false => {
+                scope.vars.insert(ass.var_name.clone(), value_t);
+                Ok(())
+            }
+        }
+    }
+
+    fn check_reassignment(&self, scope: &mut Scope, reass: &mut Assignment) -> Result<()> {
+        let value_t = self.check_expression(scope, &mut reass.expr, None)?;
+        let var_t = scope
+            .vars
+            .get(&reass.var_name)
+            .ok_or(CompileError::VarDoesNotExist(reass.var_name.clone()))?
+            .clone();
+        if let Some(var_t) = var_t {
+            self.check_assignable(scope, &value_t, &var_t, |expected, got| {
+                CompileError::ReassignmentType(reass.var_name.clone(), expected, got)
+            })?;
+        }
+        Ok(())
+    }
+
+    fn check_if_stmt(&self, scope: &mut Scope, is: &mut IfStmt) -> Result<()> {
+        self.check_condition(scope, &mut is.expr)?;
+        self.check_codeblock(&mut scope.clone(), &mut is.then)?;
+        if let Some(cb) = &mut is.else_stmt {
+            self.check_codeblock(&mut scope.clone(), cb)?;
+        }
+        Ok(())
+    }
+
+    fn check_while_stmt(&self, scope: &mut Scope, ws: &mut WhileStmt) -> Result<()> {
+        self.check_condition(scope, &mut ws.expr)?;
+        self.check_codeblock(&mut scope.clone(), &mut ws.then)?;
+        Ok(())
+    }
+
+    fn check_do_while_stmt(&self, scope: &mut Scope, dws: &mut DoWhileStmt) -> Result<()> {
+        // The condition of a do-while loop can see the variables declared inside the loop
+        let mut do_while_scope = scope.clone();
+        self.check_codeblock(&mut do_while_scope, &mut dws.then)?;
+        self.check_condition(&do_while_scope, &mut dws.expr)
+    }
+
+    fn check_condition(&self, scope: &Scope, expr: &mut Expression) -> Result<()> {
+        match self.check_expression(scope, expr, None)? {
+            Some(ct) if !self.types.is_a(&ct, &CrabType::SIMPLE(bool_struct_name())) => {
+                Err(CompileError::ConditionType(ct))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    ///
+    /// Get the type of an expression, checking every part of it along the way
+    /// Every part of the expression chain is annotated with the type of the value it produces
+    ///
+    /// Params:
+    /// * `scope` - The variables visible to the expression
+    /// * `expr` - The expression to check
+    /// * `prev` - The type of the previous value in the expression chain, if there is one
+    ///
+    /// Returns:
+    /// The type that the expression evaluates to
+    ///
+    fn check_expression(
+        &self,
+        scope: &Scope,
+        expr: &mut Expression,
+        prev: Option<CheckedType>,
+    ) -> Result<CheckedType> {
+        let this_t = match &mut expr.this {
+            ExpressionType::PRIM(prim) => self.check_primitive(scope, prim)?,
+            ExpressionType::STRUCT_INIT(si) => self.check_struct_init(scope, si)?,
+            ExpressionType::FN_CALL(fc) => self.check_fn_call(scope, fc, prev, false)?,
+            ExpressionType::STATIC_FN_CALL(sfc) => self.check_static_fn_call(scope, sfc)?,
+            ExpressionType::VARIABLE(id) => match prev {
+                // Local variables hide any constant with the same name
+                None => match (scope.vars.get(&*id), self.consts.get(&*id)) {
+                    (Some(var_t), _) => var_t.clone(),
+                    (None, Some(const_t)) => Some(const_t.clone()),
+                    (None, None) => return Err(CompileError::VarDoesNotExist(id.clone())),
+                },
+                Some(None) => None,
+                // Interfaces don't have fields, so neither do templates bounded by them
+                Some(Some(prev_t)) if scope.get_bounds(&prev_t).is_some() => {
+                    return Err(CompileError::StructFieldName(prev_t, id.clone()))
+                }
+                Some(Some(prev_t)) => {
+                    let field_t = self
+                        .types
+                        .peek_field_types(&prev_t)?
+                        .and_then(|mut fields| fields.remove(&*id))
+                        .ok_or(CompileError::StructFieldName(prev_t, id.clone()))?;
+                    scope.check_type(field_t)
+                }
+            },
+        };
+
+        expr.crab_type = this_t.clone();
+        match &mut expr.next {
+            None => Ok(this_t),
+            Some(next) => self.check_expression(scope, next, Some(this_t)),
+        }
+    }
+
+    fn check_primitive(&self, scope: &Scope, prim: &mut Primitive) -> Result<CheckedType> {
+        Ok(match prim {
+            Primitive::STRING(_) => Some(CrabType::SIMPLE(string_struct_name())),
+            Primitive::BOOL(_) => Some(CrabType::PRIM_BOOL),
+            // Chars and bytes are ints underneath, they just have fewer bits
+            Primitive::UINT(_) | Primitive::CHAR(_) | Primitive::BYTE(_) => {
+                Some(CrabType::PRIM_INT)
+            }
+            Primitive::LIST(exprs) => {
+                let elem_ts = exprs.iter_mut().try_fold(vec![], |mut elem_ts, expr| {
+                    elem_ts.push(self.check_expression(scope, expr, None)?);
+                    Result::Ok(elem_ts)
+                })?;
+                let first_t = elem_ts.first().cloned().flatten();
+                if let Some(first_t) = &first_t {
+                    elem_ts.iter().try_for_each(|elem_t| {
+                        self.check_assignable(scope, elem_t, first_t, |expected, got| {
+                            CompileError::ArgumentType(
+                                operator_add_name(),
+                                Ident::from("element"),
+                                expected,
+                                got,
+                            )
+                        })
+                    })?;
+                }
+                first_t.map(|first_t| CrabType::TMPL(list_struct_name(), vec![first_t]))
+            }
+        })
+    }
+
+    fn check_struct_init(&self, scope: &Scope, si: &mut StructInit) -> Result<CheckedType> {
+        self.check_type_exists(scope, &si.id)?;
+        let struct_name = si.id.try_get_struct_name()?;
+        if self.types.is_interface(&struct_name) {
+            return Err(CompileError::NotAStruct(
+                si.id.clone().try_into()?,
+                String::from("TypeChecker::check_struct_init"),
+            ));
+        }
+        let field_values = si
+            .fields
+            .iter_mut()
+            .try_fold(HashMap::new(), |mut values, field| {
+                values.insert(
+                    field.name.clone(),
+                    self.check_expression(scope, &mut field.value, None)?,
+                );
+                Result::Ok(values)
+            })?;
+
+        // Compiler provided structs are initialized by the compiler, so their fields are always correct
+        if let Some(field_types) = self.types.peek_field_types(&si.id)? {
+            field_values.iter().try_for_each(|(name, value_t)| {
+                let field_t = field_types
+                    .get(name)
+                    .ok_or(CompileError::StructFieldName(si.id.clone(), name.clone()))?;
+                self.check_assignable(scope, value_t, field_t, |expected, got| {
+                    CompileError::FieldType(struct_name.clone(), name.clone(), expected, got)
+                })
+            })?;
+            // Missing fields are copied from the update value if there is one, or else use their defaults
+            let defaults = self.types.peek_field_defaults(&si.id)?;
+            field_types.keys().try_for_each(|name| {
+                match si.update.is_some()
+                    || field_values.contains_key(name)
+                    || defaults.contains_key(name)
+                {
+                    true => Ok(()),
+                    false => Err(CompileError::StructInitFieldName(
+                        struct_name.clone(),
+                        name.clone(),
+                    )),
+                }
+            })?;
+        }
+        if let Some(update) = &mut si.update {
+            let update_t = self.check_expression(scope, update, None)?;
+            self.check_assignable(scope, &update_t, &si.id, |expected, got| {
+                CompileError::StructUpdateType(struct_name.clone(), expected, got)
+            })?;
+        }
+        Ok(scope.check_type(si.id.clone()))
+    }
+
+    fn check_static_fn_call(&self, scope: &Scope, sfc: &mut StaticFnCall) -> Result<CheckedType> {
+        self.check_type_exists(scope, &sfc.caller)?;
+        if let Some(const_t) = sfc.as_assoc_const().and_then(|name| self.consts.get(&name)) {
+            return Ok(Some(const_t.clone()));
+        }
+        let caller_t = scope.check_type(sfc.caller.clone());
+        self.check_fn_call(scope, &mut sfc.call, Some(caller_t), true)
+    }
+
+    ///
+    /// Type check a call to a fn
+    /// Static fns are called with the type they belong to as their caller, every other method is called with a value
+    ///
+    fn check_fn_call(
+        &self,
+        scope: &Scope,
+        call: &mut FnCall,
+        caller_opt: Option<CheckedType>,
+        is_static: bool,
+    ) -> Result<CheckedType> {
+        call.tmpls
+            .iter()
+            .try_for_each(|tmpl| self.check_type_exists(scope, tmpl))?;
+        let pos_ts = call
+            .pos_args
+            .iter_mut()
+            .try_fold(vec![], |mut pos_ts, arg| {
+                pos_ts.push(self.check_expression(scope, arg, None)?);
+                Result::Ok(pos_ts)
+            })?;
+        let named_ts =
+            call.named_args
+                .iter_mut()
+                .try_fold(HashMap::new(), |mut named_ts, arg| {
+                    named_ts.insert(
+                        arg.name.clone(),
+                        self.check_expression(scope, &mut arg.expr, None)?,
+                    );
+                    Result::Ok(named_ts)
+                })?;
+
+        let signature =
+            match self.get_signature(scope, call, caller_opt, is_static, &pos_ts, &named_ts)? {
+                None => return Ok(None),
+                Some(signature) => signature,
+            };
+
+        let (params, _) = signature.split_variadic_args(&signature.pos_params);
+        let (args, collected) = signature.split_variadic_args(&pos_ts);
+        if args.len() != params.len() {
+            return Err(CompileError::PositionalArgumentCount(
+                call.name.clone(),
+                params.len(),
+                args.len(),
+            ));
+        }
+        args.iter()
+            .zip(params.iter())
+            .try_for_each(|(arg_t, param)| {
+                self.check_assignable(scope, arg_t, &param.crab_type, |expected, got| {
+                    CompileError::ArgumentType(call.name.clone(), param.name.clone(), expected, got)
+                })
+            })?;
+        if let (Some(variadic_t), Some(param)) =
+            (signature.variadic_type(), signature.pos_params.last())
+        {
+            collected.iter().try_for_each(|arg_t| {
+                self.check_assignable(scope, arg_t, variadic_t, |expected, got| {
+                    CompileError::ArgumentType(call.name.clone(), param.name.clone(), expected, got)
+                })
+            })?;
+        }
+        named_ts.iter().try_for_each(|(name, arg_t)| {
+            let param = signature
+                .named_params
+                .get(name)
+                .ok_or(CompileError::ArgumentNotSupplied(name.clone()))?;
+            self.check_assignable(scope, arg_t, &param.crab_type, |expected, got| {
+                CompileError::ArgumentType(call.name.clone(), name.clone(), expected, got)
+            })
+        })?;
+
+        Ok(scope.check_type(signature.return_type))
+    }
+
+    ///
+    /// Get the signature of the function that a call refers to, with all known templates resolved
+    /// If the call does not supply any tmpls, they are inferred from the argument types
+    /// Methods called on a bounded template are looked up in the interfaces it is bounded by
+    ///
+    /// Returns:
+    /// The signature of the function, or None if the signature depends on a template
+    ///
+    fn get_signature(
+        &self,
+        scope: &Scope,
+        call: &FnCall,
+        caller_opt: Option<CheckedType>,
+        is_static: bool,
+        pos_ts: &[CheckedType],
+        named_ts: &HashMap<Ident, CheckedType>,
+    ) -> Result<Option<FuncSignature>> {
+        let caller = match caller_opt {
+            None => None,
+            Some(None) => return Ok(None),
+            Some(Some(caller)) => Some(caller),
+        };
+        match caller {
+            None => {
+                let sources = self
+                    .fns
+                    .get(&call.name)
+                    .ok_or(CompileError::CouldNotFindFunction(call.name.clone()))?;
+                // Overloads can't be told apart until every argument's type is known
+                if sources.len() > 1 && pos_ts.iter().chain(named_ts.values()).any(|t| t.is_none())
+                {
+                    return Ok(None);
+                }
+                let named_ts_list = named_ts
+                    .iter()
+                    .map(|(name, arg_t)| (name.clone(), arg_t.clone()))
+                    .collect::<Vec<_>>();
+                let func =
+                    select_overload(&call.name, sources, pos_ts, &named_ts_list, |lhs, rhs| {
+                        self.is_a(scope, lhs, rhs)
+                    })?;
+                let tmpls = match call.tmpls.is_empty() && !func.signature.tmpls.is_empty() {
+                    true => {
+                        // Tmpls can't be inferred until every argument's type is known
+                        if pos_ts.iter().chain(named_ts.values()).any(|t| t.is_none()) {
+                            return Ok(None);
+                        }
+                        let pos_ts = pos_ts.iter().flatten().cloned().collect::<Vec<_>>();
+                        let named_ts = named_ts
+                            .iter()
+                            .filter_map(|(name, arg_t)| Some((name.clone(), arg_t.clone()?)))
+                            .collect::<Vec<_>>();
+                        func.signature.infer_call_tmpls(&pos_ts, &named_ts)?
+                    }
+                    false => call.tmpls.clone(),
+                };
+                if func.signature.tmpls.len() != tmpls.len() {
+                    return Err(CompileError::WrongTemplateTypeCount(
+                        func.signature.tmpls.len(),
+                        tmpls.len(),
+                    ));
+                }
+                self.check_bounds(scope, &func.signature.bounds, &func.signature.tmpls, &tmpls)?;
+                Ok(Some((*func).clone().resolve(None, tmpls)?.signature))
+            }
+            Some(caller) => {
+                // Interfaces only declare methods
+                let is_interface = scope.get_bounds(&caller).is_some()
+                    || self.types.is_interface(&caller.try_get_struct_name()?);
+                if is_static && is_interface {
+                    return Err(CompileError::MethodOnType(call.name.clone()));
+                }
+                if let Some(bounds) = scope.get_bounds(&caller) {
+                    return bounds
+                        .iter()
+                        .find_map(|bound| self.types.get_interface_fn(bound, &call.name))
+                        .map(|signature| Some(signature.resolve_self(&caller)))
+                        .ok_or(CompileError::CouldNotFindFunction(call.name.clone()));
+                }
+                let caller_name = caller.try_get_struct_name()?;
+                if self.types.is_interface(&caller_name) {
+                    return self
+                        .types
+                        .get_interface_fn(&caller_name, &call.name)
+                        .map(|signature| Some(signature.resolve_self(&caller)))
+                        .ok_or(CompileError::CouldNotFindFunction(call.name.clone()));
+                }
+                let func = self
+                    .impl_fns
+                    .get(&(caller_name, call.name.clone()))
+                    .ok_or(CompileError::CouldNotFindFunction(call.name.clone()))?;
+                match (func.signature.is_static, is_static) {
+                    (true, false) => {
+                        Err(CompileError::StaticFnOnValue(func.signature.display_name()))
+                    }
+                    (false, true) => Err(CompileError::MethodOnType(func.signature.display_name())),
+                    _ => Ok(()),
+                }?;
+                // The method may bound the tmpls of its struct more tightly than the struct does
+                if let (Some(caller_id), CrabType::TMPL(_, caller_tmpls)) =
+                    (&func.signature.caller_id, &caller)
+                {
+                    self.check_bounds(
+                        scope,
+                        &func.signature.bounds,
+                        &caller_id.tmpls,
+                        caller_tmpls,
+                    )?;
+                }
+                Ok(Some(
+                    (*func)
+                        .clone()
+                        .resolve(Some(caller), call.tmpls.clone())?
+                        .signature,
+                ))
+            }
+        }
+    }
+
+    ///
+    /// Checks that the tmpls a function is called with implement the interfaces they are bounded by
+    /// Tmpls that depend on an unbounded template can't be checked until the call is monomorphised
+    ///
+    /// Params:
+    /// * `scope` - The scope the call is made in
+    /// * `bounds` - The bounds of the function being called
+    /// * `tmpl_ids` - The names of the tmpls, in the order they were declared
+    /// * `tmpls` - The types the tmpls are called with, in the same order
+    ///
+    fn check_bounds(
+        &self,
+        scope: &Scope,
+        bounds: &TmplBounds,
+        tmpl_ids: &[StructId],
+        tmpls: &[CrabType],
+    ) -> Result<()> {
+        tmpl_ids
+            .iter()
+            .zip(tmpls.iter())
+            .try_for_each(|(tmpl_id, ct)| {
+                let known = scope.get_bounds(ct).is_some() || !scope.is_generic(ct);
+                match bounds.get(&tmpl_id.name) {
+                    Some(tmpl_bounds) if known => {
+                        tmpl_bounds.iter().try_for_each(|bound| {
+                            match self.is_a(scope, ct, &CrabType::SIMPLE(bound.clone())) {
+                                true => Ok(()),
+                                false => Err(CompileError::UnsatisfiedBound(
+                                    tmpl_id.name.clone(),
+                                    bound.clone(),
+                                    ct.clone(),
+                                )),
+                            }
+                        })
+                    }
+                    _ => Ok(()),
+                }
+            })
+    }
+
+    ///
+    /// Returns whether lhs has an is-a relationship with rhs
+    /// A bounded template is-a each of the interfaces it is bounded by, and everything they extend
+    ///
+    fn is_a(&self, scope: &Scope, lhs: &CrabType, rhs: &CrabType) -> bool {
+        match (scope.get_bounds(lhs), rhs) {
+            (Some(bounds), CrabType::SIMPLE(rhs_name)) => {
+                lhs == rhs
+                    || bounds
+                        .iter()
+                        .any(|bound| self.types.extends(bound, rhs_name))
+            }
+            (Some(_), _) => lhs == rhs,
+            (None, _) => self.types.is_a(lhs, rhs),
+        }
+    }
+
+    ///
+    /// Checks that a value of type got may be used where a value of type expected is required
+    /// A template could be resolved to any type that satisfies its bounds, so only a value of that exact template may be used as one
+    /// The check is skipped if got depends on an unbounded template, because its type isn't known yet
+    ///
+    /// Params:
+    /// * `scope` - The scope the check is performed in
+    /// * `got` - The type of the value
+    /// * `expected` - The type the value must have
+    /// * `err` - Builds the error to return if the check fails, from the expected and actual types
+    ///
+    fn check_assignable<F>(
+        &self,
+        scope: &Scope,
+        got: &CheckedType,
+        expected: &CrabType,
+        err: F,
+    ) -> Result<()>
+    where
+        F: FnOnce(CrabType, CrabType) -> CompileError,
+    {
+        let assignable = |got: &CrabType| match scope.is_generic(expected) {
+            true => got == expected,
+            false => self.is_a(scope, got, expected),
+        };
+        match got {
+            Some(got) if !assignable(got) => Err(err(expected.clone(), got.clone())),
+            _ => Ok(()),
+        }
+    }
+
+    ///
+    /// Checks that the given type, and all of its tmpls, refer to registered types or templates
+    ///
+    fn check_type_exists(&self, scope: &Scope, ct: &CrabType) -> Result<()> {
+        match ct {
+            CrabType::SIMPLE(name) => {
+                match scope.tmpls.contains(name) || self.types.type_exists(name) {
+                    true => Ok(()),
+                    false => Err(CompileError::TypeDoesNotExist(name.clone())),
+                }
+            }
+            CrabType::TMPL(name, tmpls) => {
+                self.check_type_exists(scope, &CrabType::SIMPLE(name.clone()))?;
+                tmpls
+                    .iter()
+                    .try_for_each(|tmpl| self.check_type_exists(scope, tmpl))
+            }
+            _ => Ok(()),
+        }
+    }
+}
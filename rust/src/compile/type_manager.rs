@@ -1,12 +1,35 @@
 use crate::compile::builtins::get_builtin_strct_definition;
-use crate::compile::{CompileError, Result};
+use crate::compile::{vtable_release_fn_type, CompileError, Result};
 use crate::parse::ast::{
-    CrabInterface, CrabStruct, CrabType, FuncSignature, Ident, StructBody, StructId, StructIntr,
+    CrabInterface, CrabStruct, CrabType, Expression, FuncSignature, Ident, StructBody, StructId,
+    StructIntr, TmplBounds,
+};
+use crate::quill::{
+    PolyQuillType, QuillBoolType, QuillFnType, QuillIntType, QuillPointerType, QuillStructType,
+    QuillVoidType,
+};
+use crate::util::{
+    bool_struct_name, drop_interface_name, int_struct_name, interface_data_field_name,
+    interface_vtable_field_name, release_field_name, vtable_struct_name, ListFunctional,
+    MapFunctional,
 };
-use crate::quill::{PolyQuillType, QuillFnType, QuillPointerType, QuillStructType, QuillVoidType};
-use crate::util::{ListFunctional, MapFunctional};
 use std::collections::{HashMap, HashSet};
 
+///
+/// Get the quill type of values of a crab type that are passed around as plain values, rather than as pointers
+/// Ints and Bools are only put inside their structs when they are boxed into interface objects
+///
+/// Returns:
+/// The type of the plain value, or None if values of the crab type are pointers to structs
+///
+pub(super) fn get_scalar_type(ct: &CrabType) -> Option<PolyQuillType> {
+    match ct {
+        CrabType::SIMPLE(name) if name == &int_struct_name() => Some(QuillIntType::new(64).into()),
+        CrabType::SIMPLE(name) if name == &bool_struct_name() => Some(QuillBoolType::new().into()),
+        _ => None,
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(super) enum ManagedType {
     STRUCT(CrabStruct),
@@ -41,6 +64,10 @@ pub(super) struct TypeManager {
     /// Any StructId tmpls must be resolved to concrete types
     included_types: HashSet<CrabStruct>,
 
+    /// All of the interfaces that have been used as types, and must therefore have their
+    /// interface object and vtable types added to the quill
+    included_interfaces: HashSet<Ident>,
+
     /// All of interfaces each struct implements
     intrs: HashMap<Ident, Vec<Ident>>,
 }
@@ -100,7 +127,18 @@ impl TypeManager {
                 return Err(CompileError::NotAnInterface);
             }
         }
-        self.intrs.insert(intr.struct_id.name, intr.inters);
+        // Implementing an interface implies implementing everything it extends
+        let inters = intr.inters.iter().try_fold(vec![], |inters, intfc| {
+            self.get_interface_ancestry(intfc)?
+                .into_iter()
+                .try_fold(inters, |inters, ancestor| {
+                    Result::Ok(match inters.contains(&ancestor.name) {
+                        true => inters,
+                        false => inters.fpush(ancestor.name.clone()),
+                    })
+                })
+        })?;
+        self.intrs.insert(intr.struct_id.name, inters);
 
         Ok(())
     }
@@ -129,6 +167,7 @@ impl TypeManager {
     /// Try to get a type by name
     /// If a struct type is returned from this function, it will be added to the `included_types` map
     /// this will register that struct to be included in the Quill
+    /// Likewise, interfaces will be added to the `included_interfaces` set
     ///
     /// Params:
     /// * `name` - The name of the type to get
@@ -159,17 +198,105 @@ impl TypeManager {
                 // Also add any types included in the CrabType to the list of registered types
                 ct_tmpls
                     .iter()
-                    .try_for_each(|ct| self.get_type(ct)?.as_struct().map(|_| ()))?;
+                    .try_for_each(|ct| self.get_type(ct).map(|_| ()))?;
+                self.check_bounds(&strct.bounds, &strct.id.tmpls, ct_tmpls.as_slice())?;
 
                 let resolved_struct = strct.clone().resolve(ct_tmpls.as_slice())?;
                 self.included_types.insert(resolved_struct.clone());
                 ManagedType::STRUCT(resolved_struct)
             }
-            ManagedType::INTERFACE(_) => mt.clone(),
+            ManagedType::INTERFACE(intfc) => {
+                self.included_interfaces.insert(intfc.name.clone());
+                mt.clone()
+            }
         };
         Ok(mt)
     }
 
+    ///
+    /// Checks that every tmpl implements all of the interfaces it is bounded by
+    ///
+    /// Params:
+    /// * `bounds` - The bounds of the struct or function being instantiated
+    /// * `tmpl_ids` - The names of the tmpls, in the order they were declared
+    /// * `tmpls` - The types the tmpls are being instantiated with, in the same order
+    ///
+    pub fn check_bounds(
+        &self,
+        bounds: &TmplBounds,
+        tmpl_ids: &[StructId],
+        tmpls: &[CrabType],
+    ) -> Result<()> {
+        tmpl_ids
+            .iter()
+            .zip(tmpls.iter())
+            .try_for_each(|(tmpl_id, ct)| match bounds.get(&tmpl_id.name) {
+                None => Ok(()),
+                Some(tmpl_bounds) => tmpl_bounds.iter().try_for_each(|bound| {
+                    match self.is_a(ct, &CrabType::SIMPLE(bound.clone())) {
+                        true => Ok(()),
+                        false => Err(CompileError::UnsatisfiedBound(
+                            tmpl_id.name.clone(),
+                            bound.clone(),
+                            ct.clone(),
+                        )),
+                    }
+                }),
+            })
+    }
+
+    ///
+    /// Checks that every bound refers to a registered interface
+    ///
+    pub fn verify_bounds(&self, bounds: &TmplBounds) -> Result<()> {
+        bounds.iter().try_for_each(|(tmpl, tmpl_bounds)| {
+            tmpl_bounds
+                .iter()
+                .try_for_each(|bound| match self.is_interface(bound) {
+                    true => Ok(()),
+                    false => Err(CompileError::BoundNotAnInterface(
+                        tmpl.clone(),
+                        bound.clone(),
+                    )),
+                })
+        })
+    }
+
+    ///
+    /// Checks that the bounds of every registered struct refer to registered interfaces
+    ///
+    pub fn verify_struct_bounds(&self) -> Result<()> {
+        self.registered_types.values().try_for_each(|mt| match mt {
+            ManagedType::STRUCT(strct) => self.verify_bounds(&strct.bounds),
+            ManagedType::INTERFACE(_) => Ok(()),
+        })
+    }
+
+    ///
+    /// Get the bounds of a registered struct, keyed by the tmpl names used in the given StructId
+    /// This allows impl blocks to name their tmpls differently than the struct definition does
+    ///
+    /// Params:
+    /// * `id` - The StructId of the struct, as written by an impl block
+    ///
+    /// Returns:
+    /// The bounds of each of the struct's tmpls
+    ///
+    pub fn get_struct_bounds(&self, id: &StructId) -> Result<TmplBounds> {
+        let strct = self
+            .registered_types
+            .get(&id.name)
+            .ok_or(CompileError::StructDoesNotExist(id.clone()))?
+            .as_struct()?;
+        Ok(strct.id.tmpls.iter().zip(id.tmpls.iter()).fold(
+            TmplBounds::new(),
+            |bounds, (strct_tmpl, id_tmpl)| match strct.bounds.get(&strct_tmpl.name) {
+                Some(tmpl_bounds) => bounds.finsert(id_tmpl.name.clone(), tmpl_bounds.clone()),
+                None => bounds,
+            },
+        ))
+    }
+
     ///
     /// Get the given crab type as a quill type
     /// If a struct type is returned from this function, it will be added to the `included_types` map
@@ -182,10 +309,17 @@ impl TypeManager {
     /// A PolyQuillType that is equivalent to the given CrabType
     ///
     pub fn get_quill_type(&mut self, ct: &CrabType) -> Result<PolyQuillType> {
+        if let Some(scalar_t) = get_scalar_type(ct) {
+            return Ok(scalar_t);
+        }
         Ok(match ct {
             CrabType::VOID => QuillVoidType::new().into(),
+            // Interface values are pointers to interface objects, which are named like structs
             CrabType::SIMPLE(_) | CrabType::TMPL(_, _) => {
-                let name = self.get_type(ct)?.as_struct()?.id.mangle();
+                let name = match self.get_type(ct)? {
+                    ManagedType::STRUCT(strct) => strct.id.mangle(),
+                    ManagedType::INTERFACE(intfc) => StructId::from_name(intfc.name).mangle(),
+                };
                 QuillPointerType::new(QuillStructType::new(name)).into()
             }
             _ => unreachable!(),
@@ -209,6 +343,94 @@ impl TypeManager {
         ))
     }
 
+    ///
+    /// Get the struct type of an interface's objects
+    /// An interface object is a pointer to a value of any type that implements the interface,
+    /// paired with a pointer to the vtable for that type
+    ///
+    /// Params:
+    /// * `name` - The name of the interface
+    ///
+    /// Returns:
+    /// The QuillStructType of the interface's objects
+    ///
+    pub fn get_quill_interface(&mut self, name: &Ident) -> Result<QuillStructType> {
+        match self.get_type(&CrabType::SIMPLE(name.clone()))? {
+            ManagedType::INTERFACE(_) => Ok(QuillStructType::new(
+                StructId::from_name(name.clone()).mangle(),
+            )),
+            ManagedType::STRUCT(_) => Err(CompileError::NotAnInterface),
+        }
+    }
+
+    ///
+    /// Get the struct type of an interface's vtables
+    /// Every vtable has a field for each fn the interface declares
+    ///
+    /// Params:
+    /// * `name` - The name of the interface
+    ///
+    /// Returns:
+    /// The QuillStructType of the interface's vtables
+    ///
+    pub fn get_quill_vtable(&mut self, name: &Ident) -> Result<QuillStructType> {
+        self.get_quill_interface(name)?;
+        Ok(QuillStructType::new(vtable_struct_name(name)))
+    }
+
+    ///
+    /// Get the type of a fn stored in a vtable
+    /// This matches the fn's type, except that self may point to a value of any type
+    ///
+    /// Params:
+    /// * `fs` - The signature of the fn, as declared by the interface
+    ///
+    /// Returns:
+    /// A QuillFnType that can be called through a vtable
+    ///
+    pub fn get_vtable_fn_type(&mut self, fs: FuncSignature) -> Result<QuillFnType> {
+        let fn_t = self.get_quill_fn_type(fs)?;
+        let params = vec![(String::from("self"), QuillPointerType::opaque().into())]
+            .into_iter()
+            .chain(fn_t.get_params().iter().cloned())
+            .collect();
+        Ok(QuillFnType::new(fn_t.get_ret_type().clone(), params))
+    }
+
+    ///
+    /// Returns the fields of an interface's objects
+    ///
+    pub fn get_interface_fields(&mut self, name: &Ident) -> Result<HashMap<String, PolyQuillType>> {
+        let vtable_t = self.get_quill_vtable(name)?;
+        Ok(HashMap::from([
+            (
+                interface_data_field_name(),
+                QuillPointerType::opaque().into(),
+            ),
+            (
+                interface_vtable_field_name(),
+                QuillPointerType::new(vtable_t).into(),
+            ),
+        ]))
+    }
+
+    ///
+    /// Returns the fields of an interface's vtables, which are pointers to each fn the interface declares
+    /// Every vtable also points to the fn that releases the value an interface object holds
+    ///
+    pub fn get_vtable_fields(&mut self, name: &Ident) -> Result<HashMap<String, PolyQuillType>> {
+        let release_t: PolyQuillType = QuillPointerType::new(vtable_release_fn_type()).into();
+        self.get_interface_fns(name)?.into_iter().try_fold(
+            HashMap::from([(release_field_name(), release_t)]),
+            |fields, signature| {
+                Result::Ok(fields.finsert(
+                    signature.name.clone(),
+                    QuillPointerType::new(self.get_vtable_fn_type(signature)?).into(),
+                ))
+            },
+        )
+    }
+
     ///
     /// Get the given crab type as a quill function type type
     /// If a struct type is returned from this function, it will be added to the `included_types` map
@@ -324,4 +546,174 @@ impl TypeManager {
     pub fn get_included_type_names(&self) -> &HashSet<CrabStruct> {
         &self.included_types
     }
+
+    pub fn get_included_interfaces(&self) -> &HashSet<Ident> {
+        &self.included_interfaces
+    }
+
+    ///
+    /// Returns whether or not a struct or interface with the given name has been registered
+    /// Unlike get_type, this will never add a type to the `included_types` map
+    ///
+    pub fn type_exists(&self, name: &Ident) -> bool {
+        self.registered_types.contains_key(name)
+    }
+
+    ///
+    /// Returns whether or not the given name belongs to a registered interface
+    ///
+    pub fn is_interface(&self, name: &Ident) -> bool {
+        matches!(
+            self.registered_types.get(name),
+            Some(ManagedType::INTERFACE(_))
+        )
+    }
+
+    ///
+    /// Returns whether or not the given type is a registered interface
+    ///
+    pub fn is_interface_type(&self, ct: &CrabType) -> bool {
+        ct.try_get_struct_name()
+            .map_or(false, |name| self.is_interface(&name))
+    }
+
+    ///
+    /// Returns whether values of the given type must be dropped at the end of their lifetime
+    ///
+    pub fn is_droppable(&self, ct: &CrabType) -> bool {
+        !self.is_interface_type(ct) && self.is_a(ct, &CrabType::SIMPLE(drop_interface_name()))
+    }
+
+    ///
+    /// Get the signatures of every function declared by an interface, or by any interface it extends
+    ///
+    pub fn get_interface_fns(&self, interface: &Ident) -> Result<Vec<FuncSignature>> {
+        Ok(self
+            .get_interface_ancestry(interface)?
+            .into_iter()
+            .flat_map(|intfc| intfc.fns.iter())
+            .fold(vec![], |fns: Vec<FuncSignature>, signature| {
+                match fns.iter().any(|existing| existing.name == signature.name) {
+                    true => fns,
+                    false => fns.fpush(signature.clone()),
+                }
+            }))
+    }
+
+    ///
+    /// Get an interface, followed by every interface it extends, directly or indirectly
+    /// Each interface appears only once, even if it is extended more than once
+    ///
+    /// Params:
+    /// * `interface` - The name of the interface to get the ancestry of
+    ///
+    /// Returns:
+    /// The interface and all of its ancestors
+    ///
+    pub fn get_interface_ancestry(&self, interface: &Ident) -> Result<Vec<&CrabInterface>> {
+        let mut ancestry = vec![self.get_interface(interface)?];
+        let mut i = 0;
+        while i < ancestry.len() {
+            let current = ancestry[i];
+            for parent in &current.parents {
+                if !ancestry.iter().any(|intfc| &intfc.name == parent) {
+                    ancestry.push(self.get_interface(parent)?);
+                }
+            }
+            i += 1;
+        }
+        Ok(ancestry)
+    }
+
+    ///
+    /// Returns whether or not an interface is the same as, or extends, another interface
+    ///
+    pub fn extends(&self, interface: &Ident, ancestor: &Ident) -> bool {
+        self.get_interface_ancestry(interface)
+            .map_or(false, |ancestry| {
+                ancestry.iter().any(|intfc| &intfc.name == ancestor)
+            })
+    }
+
+    fn get_interface(&self, interface: &Ident) -> Result<&CrabInterface> {
+        match self.registered_types.get(interface) {
+            Some(ManagedType::INTERFACE(intfc)) => Ok(intfc),
+            Some(ManagedType::STRUCT(_)) => Err(CompileError::NotAnInterface),
+            None => Err(CompileError::TypeDoesNotExist(interface.clone())),
+        }
+    }
+
+    ///
+    /// Get the signature of a function declared by an interface
+    ///
+    /// Params:
+    /// * `interface` - The name of the interface that declares the function
+    /// * `fn_name` - The name of the function to get
+    ///
+    /// Returns:
+    /// The signature of the function, or None if the interface does not declare it
+    ///
+    pub fn get_interface_fn(&self, interface: &Ident, fn_name: &Ident) -> Option<FuncSignature> {
+        self.get_interface_fns(interface)
+            .ok()?
+            .into_iter()
+            .find(|signature| &signature.name == fn_name)
+    }
+
+    ///
+    /// Returns the CrabTypes of the fields of a given struct type, without including the struct in the Quill
+    /// The struct's tmpls are substituted, but not validated, so they may refer to unresolved templates
+    ///
+    /// Returns:
+    /// The types of each field, or None if the struct's fields are compiler provided
+    ///
+    pub fn peek_field_types(&self, ct: &CrabType) -> Result<Option<HashMap<Ident, CrabType>>> {
+        Ok(match self.peek_struct(ct)?.body {
+            StructBody::COMPILER_PROVIDED => None,
+            StructBody::FIELDS(fields) => {
+                Some(fields.into_iter().fold(HashMap::new(), |fields, field| {
+                    fields.finsert(field.name, field.crab_type)
+                }))
+            }
+        })
+    }
+
+    ///
+    /// Returns the default values of the fields of a given struct type, without including the struct in the Quill
+    ///
+    /// Returns:
+    /// The default value of every field that has one
+    ///
+    pub fn peek_field_defaults(&self, ct: &CrabType) -> Result<HashMap<Ident, Expression>> {
+        Ok(match self.peek_struct(ct)?.body {
+            StructBody::COMPILER_PROVIDED => HashMap::new(),
+            StructBody::FIELDS(fields) => {
+                fields
+                    .into_iter()
+                    .fold(HashMap::new(), |defaults, field| match field.default {
+                        Some(default) => defaults.finsert(field.name, default),
+                        None => defaults,
+                    })
+            }
+        })
+    }
+
+    fn peek_struct(&self, ct: &CrabType) -> Result<CrabStruct> {
+        let (name, tmpls) = match ct {
+            CrabType::SIMPLE(name) => (name, vec![]),
+            CrabType::TMPL(name, tmpls) => (name, tmpls.clone()),
+            _ => {
+                return Err(CompileError::NotAStruct(
+                    StructId::from_name(format!("{}", ct)),
+                    String::from("TypeManager::peek_struct"),
+                ))
+            }
+        };
+        self.registered_types
+            .get(name)
+            .ok_or(CompileError::TypeDoesNotExist(name.clone()))?
+            .as_struct()?
+            .clone()
+            .resolve(&tmpls)
+    }
 }
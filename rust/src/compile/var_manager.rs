@@ -1,13 +1,34 @@
 use crate::compile::{CompileError, CrabValue, Result};
 use crate::parse::ast::Ident;
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::rc::Rc;
 
 #[derive(Debug, Clone)]
-pub(super) struct VarManager(HashMap<Ident, CrabValue>);
+pub(super) struct VarManager {
+    vars: HashMap<Ident, (CrabValue, usize)>,
+
+    /// Every variable that has ever been assigned in this function, indexed by the usize in `vars`
+    /// Shared by every VarManager cloned from this one, so reads inside child codeblocks are counted
+    usages: Rc<RefCell<Vec<VarUsage>>>,
+}
+
+///
+/// Tracks whether or not a variable has been read since it was assigned
+///
+#[derive(Debug, Clone)]
+pub(super) struct VarUsage {
+    pub name: Ident,
+    pub is_param: bool,
+    pub read: bool,
+}
 
 impl VarManager {
     pub(super) fn new() -> Self {
-        Self(HashMap::new())
+        Self {
+            vars: HashMap::new(),
+            usages: Rc::new(RefCell::new(vec![])),
+        }
     }
 
     ///
@@ -19,10 +40,33 @@ impl VarManager {
     /// * `value` - The value of the variable to assign
     ///
     pub(super) fn assign(&mut self, name: Ident, value: CrabValue) -> Result<()> {
-        match self.0.insert(name.clone(), value) {
-            Some(_) => Err(CompileError::VarAlreadyExists(name)),
-            None => Ok(()),
+        self.add_var(name, value, false)
+    }
+
+    ///
+    /// Assigns a new value with a given name and value, which came from a function parameter
+    /// Returns an error if a variable already exists with the given name
+    ///
+    /// Params:
+    /// * `name` - The name of the param to assign
+    /// * `value` - The value of the param to assign
+    ///
+    pub(super) fn assign_param(&mut self, name: Ident, value: CrabValue) -> Result<()> {
+        self.add_var(name, value, true)
+    }
+
+    fn add_var(&mut self, name: Ident, value: CrabValue, is_param: bool) -> Result<()> {
+        if self.vars.contains_key(&name) {
+            return Err(CompileError::VarAlreadyExists(name));
         }
+        let mut usages = self.usages.borrow_mut();
+        usages.push(VarUsage {
+            name: name.clone(),
+            is_param,
+            read: false,
+        });
+        self.vars.insert(name, (value, usages.len() - 1));
+        Ok(())
     }
 
     ///
@@ -34,14 +78,18 @@ impl VarManager {
     /// * `value` - The value of the variable to assign
     ///
     pub(super) fn reassign(&mut self, name: Ident, value: CrabValue) -> Result<()> {
-        match self.0.insert(name.clone(), value) {
-            Some(_) => Ok(()),
+        match self.vars.get_mut(&name) {
+            Some((old_value, _)) => {
+                *old_value = value;
+                Ok(())
+            }
             None => Err(CompileError::VarDoesNotExist(name)),
         }
     }
 
     ///
     /// Retrieve a value from the var manager by name
+    /// The variable will be marked as read
     ///
     /// Params:
     /// * `name` - The name of the variable to retrieve
@@ -50,9 +98,53 @@ impl VarManager {
     /// The QuillValue with the given name
     ///
     pub(super) fn get(&mut self, name: &Ident) -> Result<&CrabValue> {
-        match self.0.get(name) {
+        match self.vars.get(name) {
             None => Err(CompileError::VarDoesNotExist(name.clone())),
-            Some(val) => Ok(val),
+            Some((val, index)) => {
+                self.usages.borrow_mut()[*index].read = true;
+                Ok(val)
+            }
         }
     }
+
+    ///
+    /// Retrieve a value from the var manager by name, without marking the variable as read
+    /// Useful when a variable is about to be written to
+    ///
+    /// Params:
+    /// * `name` - The name of the variable to retrieve
+    ///
+    /// Returns:
+    /// The QuillValue with the given name
+    ///
+    pub(super) fn peek(&self, name: &Ident) -> Result<&CrabValue> {
+        self.vars
+            .get(name)
+            .map(|(val, _)| val)
+            .ok_or(CompileError::VarDoesNotExist(name.clone()))
+    }
+
+    ///
+    /// Returns every variable in scope that isn't a param, without marking any of them as read
+    ///
+    pub(super) fn get_locals(&self) -> Vec<Ident> {
+        let usages = self.usages.borrow();
+        self.vars
+            .iter()
+            .filter(|(_, (_, index))| !usages[*index].is_param)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    ///
+    /// Returns every variable and param that has been assigned but never read
+    ///
+    pub(super) fn get_unread(&self) -> Vec<VarUsage> {
+        self.usages
+            .borrow()
+            .iter()
+            .filter(|usage| !usage.read)
+            .cloned()
+            .collect()
+    }
 }
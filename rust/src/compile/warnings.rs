@@ -0,0 +1,184 @@
+use crate::compile::{CompileError, Result};
+use crate::parse::ast::Ident;
+use log::{error, warn};
+use std::collections::HashMap;
+use std::str::FromStr;
+use thiserror::Error;
+
+///
+/// All of the warnings the compiler knows how to emit
+///
+#[derive(Error, Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Warning {
+    #[error("Variable {1} in function {0} is assigned but never read")]
+    UnusedVariable(Ident, Ident),
+
+    #[error("Parameter {1} of function {0} is never read")]
+    UnusedParam(Ident, Ident),
+
+    #[error("Function {0} contains code after a return statement, which will never be run")]
+    UnreachableCode(Ident),
+
+    #[error("Function {0} is never called from main")]
+    UnusedFunction(Ident),
+}
+impl Warning {
+    pub fn get_type(&self) -> WarningType {
+        match self {
+            Warning::UnusedVariable(_, _) => WarningType::UNUSED_VARIABLE,
+            Warning::UnusedParam(_, _) => WarningType::UNUSED_PARAM,
+            Warning::UnreachableCode(_) => WarningType::UNREACHABLE_CODE,
+            Warning::UnusedFunction(_) => WarningType::UNUSED_FUNCTION,
+        }
+    }
+}
+
+///
+/// The categories of warning that can be controlled from the command line
+///
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum WarningType {
+    UNUSED_VARIABLE,
+    UNUSED_PARAM,
+    UNREACHABLE_CODE,
+    UNUSED_FUNCTION,
+}
+impl WarningType {
+    pub fn all() -> Vec<WarningType> {
+        vec![
+            WarningType::UNUSED_VARIABLE,
+            WarningType::UNUSED_PARAM,
+            WarningType::UNREACHABLE_CODE,
+            WarningType::UNUSED_FUNCTION,
+        ]
+    }
+}
+
+///
+/// What the compiler should do when it encounters a warning
+///
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum WarningLevel {
+    /// Don't report the warning at all
+    ALLOW,
+    /// Report the warning, but keep compiling
+    WARN,
+    /// Report the warning, and fail the compilation once codegen is complete
+    DENY,
+}
+
+///
+/// The name of a warning, or group of warnings, as given on the command line
+///
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct WarningName(Vec<WarningType>);
+impl FromStr for WarningName {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().replace('_', "-").as_str() {
+            "all" | "warnings" => Ok(Self(WarningType::all())),
+            "unused" => Ok(Self(vec![
+                WarningType::UNUSED_VARIABLE,
+                WarningType::UNUSED_PARAM,
+                WarningType::UNUSED_FUNCTION,
+            ])),
+            "unused-variable" | "unused-variables" => Ok(Self(vec![WarningType::UNUSED_VARIABLE])),
+            "unused-param" | "unused-params" => Ok(Self(vec![WarningType::UNUSED_PARAM])),
+            "unreachable-code" | "unreachable" => Ok(Self(vec![WarningType::UNREACHABLE_CODE])),
+            "unused-function" | "unused-functions" => Ok(Self(vec![WarningType::UNUSED_FUNCTION])),
+            _ => Err(format!("Could not parse {} as a warning name", s)),
+        }
+    }
+}
+
+///
+/// The level of every warning type
+/// Every warning is set to WARN unless otherwise configured
+///
+#[derive(Debug, Clone)]
+pub struct WarningConfig(HashMap<WarningType, WarningLevel>);
+impl Default for WarningConfig {
+    fn default() -> Self {
+        Self(
+            WarningType::all()
+                .into_iter()
+                .map(|wt| (wt, WarningLevel::WARN))
+                .collect(),
+        )
+    }
+}
+impl WarningConfig {
+    ///
+    /// Consumes self, returning a WarningConfig with the named warnings set to the given level
+    ///
+    /// Params:
+    /// * `names` - The warnings to set the level of
+    /// * `level` - The level to set them to
+    ///
+    pub fn with_level(self, names: &[WarningName], level: WarningLevel) -> Self {
+        let mut levels = self.0;
+        names.iter().flat_map(|name| name.0.iter()).for_each(|wt| {
+            levels.insert(*wt, level);
+        });
+        Self(levels)
+    }
+
+    pub fn get_level(&self, wt: WarningType) -> WarningLevel {
+        *self.0.get(&wt).unwrap_or(&WarningLevel::WARN)
+    }
+}
+
+///
+/// Collects all of the warnings emitted while compiling
+/// Each distinct warning is only reported once, even if a generic function is built several times
+///
+#[derive(Debug, Clone, Default)]
+pub(super) struct WarningManager {
+    config: WarningConfig,
+    emitted: Vec<Warning>,
+}
+
+impl WarningManager {
+    pub fn new(config: WarningConfig) -> Self {
+        Self {
+            config,
+            emitted: vec![],
+        }
+    }
+
+    ///
+    /// Report a warning
+    /// The warning is logged immediately according to its configured level
+    ///
+    /// Params:
+    /// * `warning` - The warning to report
+    ///
+    pub fn emit(&mut self, warning: Warning) {
+        if self.emitted.contains(&warning) {
+            return;
+        }
+        match self.config.get_level(warning.get_type()) {
+            WarningLevel::ALLOW => return,
+            WarningLevel::WARN => warn!("{}", warning),
+            WarningLevel::DENY => error!("{}", warning),
+        }
+        self.emitted.push(warning);
+    }
+
+    ///
+    /// Returns an error if any warning that has been configured as DENY was emitted
+    ///
+    pub fn check(&self) -> Result<()> {
+        let denied = self
+            .emitted
+            .iter()
+            .filter(|warning| self.config.get_level(warning.get_type()) == WarningLevel::DENY)
+            .count();
+        match denied {
+            0 => Ok(()),
+            _ => Err(CompileError::DeniedWarnings(denied)),
+        }
+    }
+}
@@ -1,33 +1,43 @@
 use crate::parse::ast::{
-    AstNode, CrabInterface, CrabStruct, Func, Ident, StructId, StructImpl, StructIntr,
+    AstNode, CrabConst, CrabInterface, CrabStruct, Func, Ident, StructId, StructImpl, StructIntr,
 };
 use crate::parse::{ParseError, Result, Rule};
 use crate::try_from_pair;
-use crate::util::main_func_name;
+use crate::util::{assoc_const_name, main_func_name, SetFunctional};
 use pest::iterators::Pair;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 
 #[derive(Debug, Clone, Eq, PartialEq, Default)]
 pub struct CrabAst {
-    pub functions: HashMap<Ident, Func>,
+    /// Every fn defined outside of an impl block. Overloads of a fn share its name
+    pub functions: Vec<Func>,
     pub structs: Vec<CrabStruct>,
     pub interfaces: HashMap<Ident, CrabInterface>,
     pub main: Option<Func>,
     pub intrs: Vec<StructIntr>,
     pub impls: HashMap<StructId, StructImpl>,
+
+    /// Every constant declared outside of an impl block
+    pub consts: Vec<CrabConst>,
+
+    /// The display names of every function that was defined in a library, rather than the program being compiled
+    pub library_items: HashSet<Ident>,
 }
 
 try_from_pair!(CrabAst, Rule::program);
 impl AstNode for CrabAst {
     fn from_pair(pair: Pair<Rule>) -> Result<Self> {
         let inner = pair.into_inner();
-        let mut functions = HashMap::new();
+        let mut functions = vec![];
         let mut structs = vec![];
         let mut impls = HashMap::new();
         let mut interfaces = HashMap::new();
         let mut intrs = vec![];
+        let mut consts = vec![];
         let mut main = None;
+        let mut derived = vec![];
+        let mut library_items = HashSet::new();
 
         for in_pair in inner {
             match in_pair.clone().as_rule() {
@@ -36,41 +46,75 @@ impl AstNode for CrabAst {
                     if func.signature.name == main_func_name() {
                         main = Some(func.clone());
                     }
-                    functions.insert(func.signature.name.clone(), func);
+                    functions.push(func);
+                }
+                Rule::crab_struct => {
+                    let crab_struct = CrabStruct::try_from(in_pair)?;
+                    if let Some(derivation) = crab_struct.derive()? {
+                        derived.push(derivation);
+                    }
+                    structs.push(crab_struct);
                 }
-                Rule::crab_struct => structs.push(CrabStruct::try_from(in_pair)?),
                 Rule::impl_block => {
-                    let struct_impl = StructImpl::try_from(in_pair)?;
-                    impls.insert(struct_impl.struct_id.clone(), struct_impl);
+                    insert_impl(&mut impls, StructImpl::try_from(in_pair)?)?;
                 }
                 Rule::interface => {
                     let interface = CrabInterface::try_from(in_pair)?;
                     interfaces.insert(interface.name.clone(), interface);
                 }
                 Rule::intr_block => intrs.push(StructIntr::try_from(in_pair)?),
+                Rule::crab_const => consts.push(CrabConst::try_from(in_pair)?),
                 Rule::EOI => break, // Nothing should ever show up after EOI
                 _ => return Err(ParseError::NoMatch(String::from("CrabAst::from_pair"))),
             }
         }
 
+        // Derived fns are merged in last, so that an impl or intr block of the same struct is kept
+        // They are generated rather than written, so they are treated like library items
+        for (derived_impl, derived_intr) in derived {
+            library_items.extend(
+                derived_impl
+                    .fns
+                    .values()
+                    .map(|func| func.signature.display_name()),
+            );
+            let struct_impl = match impls.remove(&derived_impl.struct_id) {
+                Some(struct_impl) => derived_impl.merge(struct_impl),
+                None => derived_impl,
+            };
+            impls.insert(struct_impl.struct_id.clone(), struct_impl);
+
+            match intrs
+                .iter_mut()
+                .find(|intr| intr.struct_id == derived_intr.struct_id)
+            {
+                Some(intr) => intr.inters.extend(derived_intr.inters),
+                None => intrs.push(derived_intr),
+            }
+        }
+
         Ok(Self {
             functions,
             structs,
             interfaces,
             intrs,
             impls,
+            consts,
             main,
+            library_items,
         })
     }
 }
 impl CrabAst {
-    pub fn join(self, other: Self) -> Self {
-        Self {
-            impls: self
+    pub fn join(self, other: Self) -> Result<Self> {
+        Ok(Self {
+            impls: other
                 .impls
-                .into_iter()
-                .chain(other.impls.into_iter())
-                .collect(),
+                .into_values()
+                .try_fold(self.impls, |mut impls, struct_impl| {
+                    insert_impl(&mut impls, struct_impl)?;
+                    Result::Ok(impls)
+                })?,
             functions: self
                 .functions
                 .into_iter()
@@ -91,13 +135,99 @@ impl CrabAst {
                 .into_iter()
                 .chain(other.intrs.into_iter())
                 .collect(),
+            consts: self
+                .consts
+                .into_iter()
+                .chain(other.consts.into_iter())
+                .collect(),
             main: self.main.or(other.main),
+            library_items: self
+                .library_items
+                .into_iter()
+                .chain(other.library_items.into_iter())
+                .collect(),
+        })
+    }
+
+    ///
+    /// Consumes self, returning a CrabAst with every function marked as coming from a library
+    ///
+    pub fn as_library(self) -> Self {
+        let library_items = self
+            .functions
+            .iter()
+            .chain(self.impls.values().flat_map(|simp| simp.fns.values()))
+            .map(|func| func.signature.display_name())
+            .chain(self.library_items.into_iter())
+            .collect();
+        Self {
+            library_items,
+            ..self
         }
     }
     pub fn verify(&self) -> Result<()> {
+        self.verify_functions()?;
+        self.verify_consts()?;
+        self.verify_interfaces()?;
         self.verify_intrs()
     }
 
+    ///
+    /// Verify that no two overloads of a fn have the same positional param types,
+    /// because calls could never tell them apart
+    ///
+    fn verify_functions(&self) -> Result<()> {
+        self.functions
+            .iter()
+            .try_fold(HashSet::new(), |overloads, func| {
+                let param_types = func
+                    .signature
+                    .pos_params
+                    .iter()
+                    .map(|param| param.crab_type.clone())
+                    .collect::<Vec<_>>();
+                let key = (&func.signature.name, param_types, func.signature.variadic);
+                match overloads.contains(&key) {
+                    true => Err(ParseError::FnRedefinition(func.signature.name.clone())),
+                    false => Ok(overloads.finsert(key)),
+                }
+            })
+            .map(|_| ())
+    }
+
+    ///
+    /// Verify that no two constants have the same name,
+    /// and that no associated constant has the same name as a fn of its struct
+    ///
+    fn verify_consts(&self) -> Result<()> {
+        self.impls.values().try_for_each(|simp| {
+            match simp.consts.iter().find(|crab_const| {
+                simp.fns
+                    .keys()
+                    .any(|name| assoc_const_name(&simp.struct_id.name, name) == crab_const.name)
+            }) {
+                Some(crab_const) => Err(ParseError::ConstRedefinition(crab_const.name.clone())),
+                None => Ok(()),
+            }
+        })?;
+        self.consts
+            .iter()
+            .chain(self.impls.values().flat_map(|simp| simp.consts.iter()))
+            .try_fold(HashSet::new(), |names, crab_const| {
+                match names.contains(&crab_const.name) {
+                    true => Err(ParseError::ConstRedefinition(crab_const.name.clone())),
+                    false => Ok(names.finsert(crab_const.name.clone())),
+                }
+            })
+            .map(|_| ())
+    }
+
+    fn verify_interfaces(&self) -> Result<()> {
+        self.interfaces
+            .values()
+            .try_for_each(|interface| interface.get_ancestry(&self.interfaces).map(|_| ()))
+    }
+
     fn verify_intrs(&self) -> Result<()> {
         for intr in &self.intrs {
             for (sid, simp) in &self.impls {
@@ -107,6 +237,7 @@ impl CrabAst {
                             self.interfaces
                                 .get(inter)
                                 .ok_or(ParseError::InterfaceNotFound(inter.clone()))?,
+                            &self.interfaces,
                         )?;
                     }
                     break;
@@ -118,3 +249,19 @@ impl CrabAst {
         Ok(())
     }
 }
+
+///
+/// Add an impl to the map of impls, joining its fns with any impl of the same struct already there
+///
+/// Params:
+/// * `impls` - Every impl found so far, indexed by struct
+/// * `struct_impl` - The impl to add
+///
+fn insert_impl(impls: &mut HashMap<StructId, StructImpl>, struct_impl: StructImpl) -> Result<()> {
+    let struct_impl = match impls.remove(&struct_impl.struct_id) {
+        Some(existing) => existing.join(struct_impl)?,
+        None => struct_impl,
+    };
+    impls.insert(struct_impl.struct_id.clone(), struct_impl);
+    Ok(())
+}
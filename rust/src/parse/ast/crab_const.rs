@@ -0,0 +1,47 @@
+use crate::parse::ast::{AstNode, CrabType, Expression, Ident, StructId};
+use crate::parse::{ParseError, Result, Rule};
+use crate::try_from_pair;
+use crate::util::assoc_const_name;
+use pest::iterators::Pair;
+use std::convert::TryFrom;
+
+///
+/// A constant declared outside of any struct, such as `const Int MAX_RETRIES = 5`
+/// It can be read from anywhere, as long as no local variable has the same name
+///
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CrabConst {
+    pub name: Ident,
+    pub crab_type: CrabType,
+    pub value: Expression,
+}
+try_from_pair!(CrabConst, Rule::crab_const);
+impl AstNode for CrabConst {
+    fn from_pair(pair: Pair<Rule>) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut inner = pair.into_inner();
+        let crab_type = CrabType::try_from(inner.next().ok_or(ParseError::ExpectedInner)?)?;
+        let name = Ident::from(inner.next().ok_or(ParseError::ExpectedInner)?.as_str());
+        let value = Expression::try_from(inner.next().ok_or(ParseError::ExpectedInner)?)?;
+
+        Ok(Self {
+            name,
+            crab_type,
+            value,
+        })
+    }
+}
+impl CrabConst {
+    ///
+    /// Convert this constant to one that belongs to a struct, such as `GymGoer::STARTING_FITNESS`
+    /// Its name is qualified by the struct, so it can't clash with a global constant
+    ///
+    pub(super) fn associated(self, struct_id: &StructId) -> Self {
+        Self {
+            name: assoc_const_name(&struct_id.name, &self.name),
+            ..self
+        }
+    }
+}
@@ -1,13 +1,24 @@
-use crate::parse::ast::{AstNode, FuncSignature, Ident};
+use crate::parse::ast::{AstNode, Func, FuncSignature, Ident};
 use crate::parse::{ParseError, Result, Rule};
 use crate::try_from_pair;
+use crate::util::ListFunctional;
 use pest::iterators::Pair;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct CrabInterface {
     pub name: Ident,
+
+    /// The interfaces this interface extends
+    /// Anything that implements this interface must implement all of them as well
+    pub parents: Vec<Ident>,
+
+    /// Every fn this interface declares, including the ones with a default body
     pub fns: Vec<FuncSignature>,
+
+    /// The fns with a default body, which are used by any implementor that does not define them itself
+    pub default_fns: Vec<Func>,
 }
 
 try_from_pair!(CrabInterface, Rule::interface);
@@ -25,12 +36,83 @@ impl AstNode for CrabInterface {
                 )))?
                 .as_str(),
         );
+        let mut parents = vec![];
         let mut fns = vec![];
+        let mut default_fns = vec![];
 
         for in_pair in inner {
-            fns.push(FuncSignature::try_from(in_pair)?);
+            match in_pair.as_rule() {
+                Rule::interface_parents => {
+                    parents = in_pair.into_inner().fold(parents, |parents, parent| {
+                        parents.fpush(Ident::from(parent.as_str()))
+                    });
+                }
+                Rule::fn_signature => fns.push(FuncSignature::try_from(in_pair)?),
+                Rule::function => {
+                    let func = Func::try_from(in_pair)?;
+                    fns.push(func.signature.clone());
+                    default_fns.push(func);
+                }
+                rule => {
+                    return Err(ParseError::IncorrectRule(
+                        String::from("CrabInterface"),
+                        String::from("interface_parents, fn_signature or function"),
+                        format!("{:#?}", rule),
+                    ))
+                }
+            }
+        }
+
+        Ok(Self {
+            name,
+            parents,
+            fns,
+            default_fns,
+        })
+    }
+}
+impl CrabInterface {
+    ///
+    /// Get this interface, followed by every interface it extends, directly or indirectly
+    /// Each interface appears only once, even if it is extended more than once
+    ///
+    /// Params:
+    /// * `interfaces` - Every interface in the program, indexed by name
+    ///
+    /// Returns:
+    /// This interface and all of its ancestors, or an error if one of them is missing or this interface extends itself
+    ///
+    pub fn get_ancestry<'a>(
+        &'a self,
+        interfaces: &'a HashMap<Ident, CrabInterface>,
+    ) -> Result<Vec<&'a CrabInterface>> {
+        let mut ancestry = vec![self];
+        let mut i = 0;
+        while i < ancestry.len() {
+            let current = ancestry[i];
+            for parent in &current.parents {
+                if parent == &self.name {
+                    return Err(ParseError::InterfaceExtendsItself(self.name.clone()));
+                }
+                if !ancestry.iter().any(|intfc| &intfc.name == parent) {
+                    ancestry.push(
+                        interfaces
+                            .get(parent)
+                            .ok_or(ParseError::InterfaceNotFound(parent.clone()))?,
+                    );
+                }
+            }
+            i += 1;
         }
+        Ok(ancestry)
+    }
 
-        Ok(Self { name, fns })
+    ///
+    /// Get the default body of a fn declared by this interface, if it has one
+    ///
+    pub fn get_default_fn(&self, name: &Ident) -> Option<&Func> {
+        self.default_fns
+            .iter()
+            .find(|func| &func.signature.name == name)
     }
 }
@@ -1,4 +1,6 @@
-use crate::parse::ast::{AstNode, CrabType, Ident, StructId};
+use crate::parse::ast::{
+    AstNode, CrabType, Expression, Ident, StructId, StructIdBounds, TmplBounds,
+};
 use crate::parse::{ParseError, Result, Rule};
 use crate::util::MapFunctional;
 use crate::{compile, try_from_pair, util};
@@ -11,6 +13,9 @@ use util::ListFunctional;
 pub struct CrabStruct {
     pub id: StructId,
     pub body: StructBody,
+    pub bounds: TmplBounds,
+    /// The interfaces listed in the struct's derive attribute, which get generated impls
+    pub derives: Vec<Ident>,
 }
 
 try_from_pair!(CrabStruct, Rule::crab_struct);
@@ -20,18 +25,29 @@ impl AstNode for CrabStruct {
         Self: Sized,
     {
         let mut inner = pair.into_inner();
-        let name = StructId::try_from(
-            inner
-                .next()
-                .ok_or(ParseError::NoMatch(String::from("Struct::from_pair")))?,
-        )?;
+        let derives = match inner.peek() {
+            Some(derive_pair) if derive_pair.as_rule() == Rule::derive_attr => {
+                DeriveAttr::try_from(inner.next().ok_or(ParseError::ExpectedInner)?)?.0
+            }
+            _ => vec![],
+        };
+        let id_pair = inner
+            .next()
+            .ok_or(ParseError::NoMatch(String::from("Struct::from_pair")))?;
+        let bounds = StructIdBounds::try_from(id_pair.clone())?.0;
+        let name = StructId::try_from(id_pair)?;
         let body = StructBody::try_from(
             inner
                 .next()
                 .ok_or(ParseError::NoMatch(String::from("Struct::from_pair")))?,
         )?;
 
-        Ok(Self { id: name, body })
+        Ok(Self {
+            id: name,
+            body,
+            bounds,
+            derives,
+        })
     }
 }
 impl CrabStruct {
@@ -51,6 +67,7 @@ impl CrabStruct {
         Ok(Self {
             id: resolved,
             body: resolved_body,
+            ..self
         })
     }
 }
@@ -107,6 +124,8 @@ impl StructBody {
 pub struct StructField {
     pub name: Ident,
     pub crab_type: CrabType,
+    /// The value the field takes when a struct init does not supply one
+    pub default: Option<Expression>,
 }
 try_from_pair!(StructField, Rule::struct_field);
 impl AstNode for StructField {
@@ -117,7 +136,15 @@ impl AstNode for StructField {
         let mut inner = pair.into_inner();
         let crab_type = CrabType::try_from(inner.next().ok_or(ParseError::ExpectedInner)?)?;
         let name = Ident::from(inner.next().ok_or(ParseError::ExpectedInner)?.as_str());
-        Ok(Self { name, crab_type })
+        let default = match inner.next() {
+            None => None,
+            Some(default_pair) => Some(Expression::try_from(default_pair)?),
+        };
+        Ok(Self {
+            name,
+            crab_type,
+            default,
+        })
     }
 }
 
@@ -135,3 +162,16 @@ impl AstNode for StructFields {
         ))
     }
 }
+
+struct DeriveAttr(Vec<Ident>);
+try_from_pair!(DeriveAttr, Rule::derive_attr);
+impl AstNode for DeriveAttr {
+    fn from_pair(pair: Pair<Rule>) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self(pair.into_inner().fold(vec![], |derives, derive| {
+            derives.fpush(Ident::from(derive.as_str()))
+        })))
+    }
+}
@@ -1,4 +1,4 @@
-use crate::parse::ast::{AstNode, CrabInterface, Func, Ident, StructId};
+use crate::parse::ast::{AstNode, CrabConst, CrabInterface, CrabType, Func, Ident, StructId};
 use crate::parse::{ParseError, Result, Rule};
 use crate::try_from_pair;
 use crate::util::{ListFunctional, MapFunctional};
@@ -11,6 +11,9 @@ pub struct StructImpl {
     pub struct_id: StructId,
     pub interface_name: Option<Ident>,
     pub fns: HashMap<Ident, Func>,
+
+    /// Every constant declared in the impl block, named after the struct it belongs to
+    pub consts: Vec<CrabConst>,
 }
 try_from_pair!(StructImpl, Rule::impl_block);
 impl AstNode for StructImpl {
@@ -25,7 +28,7 @@ impl AstNode for StructImpl {
         let interface_name = match next_opt {
             None => None,
             Some(next_pair) => match next_pair.clone().as_rule() {
-                Rule::function => None,
+                Rule::function | Rule::static_function | Rule::crab_const => None,
                 Rule::ident => {
                     inner.next();
                     Some(Ident::from(next_pair.as_str()))
@@ -33,47 +36,125 @@ impl AstNode for StructImpl {
                 rule => {
                     return Err(ParseError::IncorrectRule(
                         String::from("StructImpl"),
-                        String::from("function or ident"),
+                        String::from("function, static_function, crab_const or ident"),
                         format!("{:#?}", rule),
                     ))
                 }
             },
         };
 
-        let fns = inner.try_fold(HashMap::new(), |fns, func| {
-            let f = Func::try_from(func)?.method(struct_id.clone());
-            Result::Ok(fns.finsert(f.signature.name.clone(), f))
+        let (fns, consts) = inner.try_fold((HashMap::new(), vec![]), |(fns, consts), item| {
+            let f = match item.as_rule() {
+                Rule::static_function => StaticFunction::try_from(item)?
+                    .0
+                    .static_method(struct_id.clone())?,
+                Rule::crab_const => {
+                    let crab_const = CrabConst::try_from(item)?.associated(&struct_id);
+                    return Result::Ok((fns, consts.fpush(crab_const)));
+                }
+                _ => Func::try_from(item)?.method(struct_id.clone())?,
+            };
+            Result::Ok((fns.finsert(f.signature.name.clone(), f), consts))
         })?;
 
         Ok(Self {
             struct_id,
             interface_name,
             fns,
+            consts,
         })
     }
 }
 impl StructImpl {
-    pub fn verify_implements(&self, intr: &CrabInterface) -> Result<()> {
-        for ifunc in &intr.fns {
-            let mut match_found = false;
-            for (_, func) in &self.fns {
-                if func.signature.implements(ifunc) {
-                    match_found = true;
-                    break;
+    ///
+    /// Consumes self and another written impl of the same struct, returning one impl with the fns of both
+    /// A fn may only be defined by one of them
+    ///
+    pub fn join(self, other: StructImpl) -> Result<Self> {
+        if let Some(name) = other.fns.keys().find(|name| self.fns.contains_key(*name)) {
+            return Err(ParseError::MethodRedefinition(
+                self.struct_id.name,
+                name.clone(),
+            ));
+        }
+        Ok(self.merge(other))
+    }
+
+    ///
+    /// Consumes self and another impl of the same struct, returning one impl with the fns of both
+    /// If both define a fn with the same name, the one from `other` is kept
+    ///
+    pub fn merge(self, other: StructImpl) -> Self {
+        Self {
+            struct_id: self.struct_id,
+            interface_name: self.interface_name.or(other.interface_name),
+            fns: self.fns.into_iter().chain(other.fns.into_iter()).collect(),
+            consts: self
+                .consts
+                .into_iter()
+                .chain(other.consts.into_iter())
+                .collect(),
+        }
+    }
+
+    ///
+    /// Verify that this impl defines every fn required by an interface
+    /// The fns of every interface it extends are required as well,
+    /// unless the interface or one of its ancestors provides a default body for them
+    ///
+    /// Params:
+    /// * `intr` - The interface to verify
+    /// * `interfaces` - Every interface in the program, indexed by name
+    ///
+    pub fn verify_implements(
+        &self,
+        intr: &CrabInterface,
+        interfaces: &HashMap<Ident, CrabInterface>,
+    ) -> Result<()> {
+        let self_type = CrabType::from(self.struct_id.clone());
+        let ancestry = intr.get_ancestry(interfaces)?;
+        for interface in &ancestry {
+            for ifunc in &interface.fns {
+                let has_default = ancestry
+                    .iter()
+                    .any(|ancestor| ancestor.get_default_fn(&ifunc.name).is_some());
+                let ifunc = ifunc.clone().resolve_self(&self_type);
+                let mut match_found = has_default;
+                for (_, func) in &self.fns {
+                    if func.signature.implements(&ifunc) {
+                        match_found = true;
+                        break;
+                    }
+                }
+                if !match_found {
+                    return Err(ParseError::DoesNotImplement(
+                        self.struct_id.clone(),
+                        ifunc.name.clone(),
+                        interface.name.clone(),
+                    ));
                 }
-            }
-            if !match_found {
-                return Err(ParseError::DoesNotImplement(
-                    self.struct_id.clone(),
-                    ifunc.name.clone(),
-                    intr.name.clone(),
-                ));
             }
         }
         Ok(())
     }
 }
 
+///
+/// A fn in an impl block that is marked static, so it does not take self
+///
+struct StaticFunction(Func);
+try_from_pair!(StaticFunction, Rule::static_function);
+impl AstNode for StaticFunction {
+    fn from_pair(pair: Pair<Rule>) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self(Func::try_from(
+            pair.into_inner().next().ok_or(ParseError::ExpectedInner)?,
+        )?))
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct StructIntr {
     pub struct_id: StructId,
@@ -0,0 +1,334 @@
+use crate::parse::ast::{
+    Assignment, CodeBlock, CrabStruct, CrabType, Expression, ExpressionType, FnBodyType, FnCall,
+    Func, FuncSignature, Ident, IfStmt, PosParam, Primitive, Statement, StructBody, StructField,
+    StructFieldInit, StructImpl, StructInit, StructIntr,
+};
+use crate::parse::{ParseError, Result};
+use crate::util::{
+    bool_struct_name, comparable_interface_name, equable_interface_name, hash_fn_name,
+    hashable_interface_name, int_struct_name, operator_add_name, operator_eq_name,
+    operator_lt_name, operator_mult_name, primitive_field_name, printable_interface_name,
+    self_var_name, string_struct_name, to_string_name, ListFunctional, MapFunctional,
+};
+use std::collections::HashMap;
+
+impl CrabStruct {
+    ///
+    /// Generate the impl and intr bodies for every interface in this struct's derive attribute
+    /// The generated fns are plain AST, so they are checked and built like hand-written fns
+    ///
+    /// Returns:
+    /// The generated impl and intr, or None if the struct derives nothing
+    ///
+    pub fn derive(&self) -> Result<Option<(StructImpl, StructIntr)>> {
+        if self.derives.is_empty() {
+            return Ok(None);
+        }
+        let fields = match &self.body {
+            StructBody::FIELDS(fields) => fields,
+            StructBody::COMPILER_PROVIDED => {
+                return Err(ParseError::CannotDerive(
+                    self.id.clone(),
+                    self.derives[0].clone(),
+                ))
+            }
+        };
+
+        let fns = self
+            .derives
+            .iter()
+            .try_fold(HashMap::new(), |fns, derive| {
+                let func = match derive {
+                    d if *d == printable_interface_name() => self.derive_to_string(fields),
+                    d if *d == equable_interface_name() => self.derive_eq(fields),
+                    d if *d == comparable_interface_name() => self.derive_lt(fields),
+                    d if *d == hashable_interface_name() => self.derive_hash(fields),
+                    _ => return Err(ParseError::CannotDerive(self.id.clone(), derive.clone())),
+                }
+                .method(self.id.clone())?;
+                Result::Ok(fns.finsert(func.signature.name.clone(), func))
+            })?;
+
+        Ok(Some((
+            StructImpl {
+                struct_id: self.id.clone(),
+                interface_name: None,
+                fns,
+                consts: vec![],
+            },
+            StructIntr {
+                struct_id: self.id.clone(),
+                inters: self.derives.clone(),
+            },
+        )))
+    }
+
+    ///
+    /// Generate a toString that prints every field by name, such as `GymGoer { name: Phillip }`
+    /// The string is built one piece at a time, so String's operatorAdd is always the one called
+    ///
+    fn derive_to_string(&self, fields: &[StructField]) -> Func {
+        let result_name = Ident::from("result");
+        let statements = match fields.split_first() {
+            None => vec![Statement::RETURN(Some(string_expr(format!(
+                "{} {{}}",
+                self.id.name
+            ))))],
+            Some((first, rest)) => {
+                let start = Statement::ASSIGNMENT(Assignment {
+                    var_name: result_name.clone(),
+                    expr: string_expr(format!("{} {{ {}: ", self.id.name, first.name)),
+                });
+                let append = |piece: Expression| {
+                    Statement::REASSIGNMENT(Assignment {
+                        var_name: result_name.clone(),
+                        expr: chain(
+                            ExpressionType::VARIABLE(result_name.clone()),
+                            vec![operator_call(operator_add_name(), piece)],
+                        ),
+                    })
+                };
+                rest.iter()
+                    .fold(
+                        vec![start, append(field_expr(self_var_name(), &first.name))],
+                        |statements, field| {
+                            statements
+                                .fpush(append(string_expr(format!(", {}: ", field.name))))
+                                .fpush(append(field_expr(self_var_name(), &field.name)))
+                        },
+                    )
+                    .fpush(append(string_expr(String::from(" }"))))
+                    .fpush(Statement::RETURN(Some(chain(
+                        ExpressionType::VARIABLE(result_name.clone()),
+                        vec![],
+                    ))))
+            }
+        };
+
+        derived_fn(
+            to_string_name(),
+            vec![],
+            CrabType::SIMPLE(string_struct_name()),
+            statements,
+        )
+    }
+
+    ///
+    /// Generate an operatorEq that is true when every field of self equals the same field of other
+    ///
+    fn derive_eq(&self, fields: &[StructField]) -> Func {
+        let all_equal = fields.iter().rev().fold(
+            vec![Statement::RETURN(Some(bool_expr(true)))],
+            |then, field| {
+                vec![Statement::IF_STATEMENT(IfStmt {
+                    expr: compare_fields(operator_eq_name(), self_var_name(), other_name(), field),
+                    then: CodeBlock { statements: then },
+                    else_stmt: None,
+                })]
+            },
+        );
+
+        derived_fn(
+            operator_eq_name(),
+            vec![self.other_param()],
+            CrabType::SIMPLE(bool_struct_name()),
+            all_equal.fpush(Statement::RETURN(Some(bool_expr(false)))),
+        )
+    }
+
+    ///
+    /// Generate an operatorLt that compares fields lexicographically, in declaration order
+    /// The first field that differs decides the result, so equal structs are never less
+    ///
+    fn derive_lt(&self, fields: &[StructField]) -> Func {
+        let return_if = |expr: Expression, value: bool| {
+            Statement::IF_STATEMENT(IfStmt {
+                expr,
+                then: CodeBlock {
+                    statements: vec![Statement::RETURN(Some(bool_expr(value)))],
+                },
+                else_stmt: None,
+            })
+        };
+        let statements = fields
+            .iter()
+            .fold(vec![], |statements, field| {
+                statements
+                    .fpush(return_if(
+                        compare_fields(operator_lt_name(), self_var_name(), other_name(), field),
+                        true,
+                    ))
+                    .fpush(return_if(
+                        compare_fields(operator_lt_name(), other_name(), self_var_name(), field),
+                        false,
+                    ))
+            })
+            .fpush(Statement::RETURN(Some(bool_expr(false))));
+
+        derived_fn(
+            operator_lt_name(),
+            vec![self.other_param()],
+            CrabType::SIMPLE(bool_struct_name()),
+            statements,
+        )
+    }
+
+    ///
+    /// Generate a hash that combines the hash of every field, so equal structs hash the same
+    /// Hashable extends Equable, so a struct that derives Hashable needs an operatorEq as well
+    ///
+    fn derive_hash(&self, fields: &[StructField]) -> Func {
+        let result_name = Ident::from("result");
+        let reassign = |expr: ExpressionType| {
+            Statement::REASSIGNMENT(Assignment {
+                var_name: result_name.clone(),
+                expr: chain(ExpressionType::VARIABLE(result_name.clone()), vec![expr]),
+            })
+        };
+        let statements = fields
+            .iter()
+            .fold(
+                vec![Statement::ASSIGNMENT(Assignment {
+                    var_name: result_name.clone(),
+                    expr: int_expr(0),
+                })],
+                |statements, field| {
+                    let field_hash = chain(
+                        ExpressionType::VARIABLE(self_var_name()),
+                        vec![
+                            ExpressionType::VARIABLE(field.name.clone()),
+                            ExpressionType::FN_CALL(FnCall {
+                                name: hash_fn_name(),
+                                tmpls: vec![],
+                                pos_args: vec![],
+                                named_args: vec![],
+                            }),
+                        ],
+                    );
+                    statements
+                        .fpush(reassign(operator_call(operator_mult_name(), int_expr(31))))
+                        .fpush(reassign(operator_call(operator_add_name(), field_hash)))
+                },
+            )
+            .fpush(Statement::RETURN(Some(chain(
+                ExpressionType::VARIABLE(result_name.clone()),
+                vec![],
+            ))));
+
+        derived_fn(
+            hash_fn_name(),
+            vec![],
+            CrabType::SIMPLE(int_struct_name()),
+            statements,
+        )
+    }
+
+    fn other_param(&self) -> PosParam {
+        PosParam {
+            name: other_name(),
+            crab_type: CrabType::from(self.id.clone()),
+        }
+    }
+}
+
+fn other_name() -> Ident {
+    Ident::from("other")
+}
+
+fn derived_fn(
+    name: Ident,
+    pos_params: Vec<PosParam>,
+    return_type: CrabType,
+    statements: Vec<Statement>,
+) -> Func {
+    Func {
+        signature: FuncSignature {
+            name,
+            tmpls: vec![],
+            return_type,
+            pos_params,
+            named_params: Default::default(),
+            caller_id: None,
+            bounds: Default::default(),
+            is_static: false,
+            variadic: false,
+        },
+        body: FnBodyType::CODEBLOCK(CodeBlock { statements }),
+    }
+}
+
+///
+/// Build an expression out of a chain of expression types, such as `self.name.toString()`
+///
+fn chain(this: ExpressionType, rest: Vec<ExpressionType>) -> Expression {
+    let next = rest.into_iter().rev().fold(None, |next, this| {
+        Some(Box::new(Expression {
+            this,
+            next,
+            crab_type: None,
+        }))
+    });
+    Expression {
+        this,
+        next,
+        crab_type: None,
+    }
+}
+
+fn operator_call(name: Ident, arg: Expression) -> ExpressionType {
+    ExpressionType::FN_CALL(FnCall {
+        name,
+        tmpls: vec![],
+        pos_args: vec![arg],
+        named_args: vec![],
+    })
+}
+
+fn field_expr(var_name: Ident, field_name: &Ident) -> Expression {
+    chain(
+        ExpressionType::VARIABLE(var_name),
+        vec![ExpressionType::VARIABLE(field_name.clone())],
+    )
+}
+
+///
+/// Build `lhs.field <operator> rhs.field`
+///
+fn compare_fields(operator: Ident, lhs: Ident, rhs: Ident, field: &StructField) -> Expression {
+    chain(
+        ExpressionType::VARIABLE(lhs),
+        vec![
+            ExpressionType::VARIABLE(field.name.clone()),
+            operator_call(operator, field_expr(rhs, &field.name)),
+        ],
+    )
+}
+
+fn string_expr(value: String) -> Expression {
+    chain(ExpressionType::PRIM(Primitive::STRING(value)), vec![])
+}
+
+///
+/// Bools and Ints are structs, so like the parser, wrap the primitive in a struct init
+///
+fn prim_expr(struct_name: Ident, prim: Primitive) -> Expression {
+    chain(
+        ExpressionType::STRUCT_INIT(StructInit {
+            id: CrabType::SIMPLE(struct_name),
+            fields: vec![StructFieldInit {
+                name: primitive_field_name(),
+                value: chain(ExpressionType::PRIM(prim), vec![]),
+            }],
+            update: None,
+        }),
+        vec![],
+    )
+}
+
+fn bool_expr(value: bool) -> Expression {
+    prim_expr(bool_struct_name(), Primitive::BOOL(value))
+}
+
+fn int_expr(value: u64) -> Expression {
+    prim_expr(int_struct_name(), Primitive::UINT(value))
+}
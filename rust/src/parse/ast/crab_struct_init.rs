@@ -9,6 +9,8 @@ use std::convert::TryFrom;
 pub struct StructInit {
     pub id: CrabType,
     pub fields: Vec<StructFieldInit>,
+    /// An existing value that every field not in fields is copied from, as in `Foo { x = 1, ..other }`
+    pub update: Option<Box<Expression>>,
 }
 try_from_pair!(StructInit, Rule::struct_init);
 impl AstNode for StructInit {
@@ -22,10 +24,22 @@ impl AstNode for StructInit {
                 .next()
                 .ok_or(ParseError::NoMatch(String::from("Struct::from_pair")))?,
         )?;
-        let fields = inner.try_fold(vec![], |fields, field| {
-            Result::Ok(fields.fpush(StructFieldInit::try_from(field)?))
+        let (fields, update) = inner.try_fold((vec![], None), |(fields, update), pair| {
+            Result::Ok(match pair.as_rule() {
+                Rule::struct_update => (
+                    fields,
+                    Some(Box::new(Expression::try_from(
+                        pair.into_inner().next().ok_or(ParseError::ExpectedInner)?,
+                    )?)),
+                ),
+                _ => (fields.fpush(StructFieldInit::try_from(pair)?), update),
+            })
         })?;
-        Ok(Self { id: name, fields })
+        Ok(Self {
+            id: name,
+            fields,
+            update,
+        })
     }
 }
 impl StructInit {
@@ -36,6 +50,10 @@ impl StructInit {
                 fields: self.fields.into_iter().try_fold(vec![], |fields, field| {
                     compile::Result::Ok(fields.fpush(field.resolve(caller.clone(), caller_id)?))
                 })?,
+                update: match self.update {
+                    None => None,
+                    Some(update) => Some(Box::new(update.resolve(caller.clone(), caller_id)?)),
+                },
             },
             _ => self,
         })
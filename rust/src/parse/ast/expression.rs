@@ -1,9 +1,12 @@
 use crate::parse::ast::{
-    AstNode, CrabType, FnCall, Ident, Primitive, StructFieldInit, StructId, StructInit,
+    AstNode, CrabType, FnCall, Ident, InterpolatedString, MapLiteral, Primitive, StaticFnCall,
+    StructFieldInit, StructId, StructInit,
 };
 use crate::parse::ParseError::ExpectedInner;
 use crate::parse::{ParseError, Result, Rule};
-use crate::util::{bool_struct_name, int_struct_name, primitive_field_name};
+use crate::util::{
+    bool_struct_name, byte_struct_name, char_struct_name, int_struct_name, primitive_field_name,
+};
 use crate::util::{
     operator_add_name, operator_div_name, operator_eq_name, operator_gt_name, operator_gte_name,
     operator_lsh_name, operator_lt_name, operator_lte_name, operator_mult_name, operator_rsh_name,
@@ -17,6 +20,10 @@ use std::convert::TryFrom;
 pub struct Expression {
     pub this: ExpressionType,
     pub next: Option<Box<Expression>>,
+
+    /// The type of the value at this point in the expression chain, which is filled in by the type checker
+    /// None until the expression has been checked, or if its type depends on an unresolved template
+    pub crab_type: Option<CrabType>,
 }
 impl Expression {
     pub(super) fn resolve(self, caller: CrabType, caller_id: &StructId) -> compile::Result<Self> {
@@ -24,7 +31,11 @@ impl Expression {
             this: self.this.resolve(caller.clone(), caller_id)?,
             next: match self.next {
                 None => None,
-                Some(bexpr) => Some(Box::new(bexpr.resolve(caller, caller_id)?)),
+                Some(bexpr) => Some(Box::new(bexpr.resolve(caller.clone(), caller_id)?)),
+            },
+            crab_type: match (&caller, self.crab_type) {
+                (CrabType::TMPL(_, tmpls), Some(ct)) => Some(ct.resolve(caller_id, tmpls)?),
+                (_, crab_type) => crab_type,
             },
         })
     }
@@ -36,6 +47,7 @@ pub enum ExpressionType {
     PRIM(Primitive),
     STRUCT_INIT(StructInit),
     FN_CALL(FnCall),
+    STATIC_FN_CALL(StaticFnCall),
     VARIABLE(Ident),
 }
 impl ExpressionType {
@@ -45,6 +57,9 @@ impl ExpressionType {
                 ExpressionType::STRUCT_INIT(si.resolve(caller, caller_id)?)
             }
             ExpressionType::FN_CALL(fc) => ExpressionType::FN_CALL(fc.resolve(caller, caller_id)?),
+            ExpressionType::STATIC_FN_CALL(sfc) => {
+                ExpressionType::STATIC_FN_CALL(sfc.resolve(caller, caller_id)?)
+            }
             _ => self,
         })
     }
@@ -63,6 +78,7 @@ impl AstNode for Expression {
         let mut expr = Expression {
             this: ExpressionType::try_from(first_pair)?,
             next: None,
+            crab_type: None,
         };
 
         // This will continue appending tokens to the expression until either:
@@ -108,12 +124,13 @@ impl Expression {
     /// Params:
     /// - `addition`: The ExpressionType to add to this expression
     ///
-    fn append(&mut self, addition: ExpressionType) {
+    pub(super) fn append(&mut self, addition: ExpressionType) {
         match &mut self.next {
             None => {
                 self.next = Some(Box::new(Expression {
                     this: addition,
                     next: None,
+                    crab_type: None,
                 }))
             }
             Some(expr) => expr.append(addition),
@@ -140,32 +157,33 @@ impl TryFrom<Pair<'_, Rule>> for ExpressionType {
             // Primitives are *special*. They need to be converted to StructInits that contain a Primitive argument
             Rule::primitive => {
                 let prim = Primitive::try_from(pair)?;
-                match &prim {
-                    Primitive::UINT(_) => Ok(Self::STRUCT_INIT(StructInit {
-                        id: CrabType::SIMPLE(int_struct_name()),
-                        fields: vec![StructFieldInit {
-                            name: primitive_field_name(),
-                            value: Expression {
-                                this: ExpressionType::PRIM(prim),
-                                next: None,
-                            },
-                        }],
-                    })),
-                    Primitive::BOOL(_) => Ok(Self::STRUCT_INIT(StructInit {
-                        id: CrabType::SIMPLE(bool_struct_name()),
-                        fields: vec![StructFieldInit {
-                            name: primitive_field_name(),
-                            value: Expression {
-                                this: ExpressionType::PRIM(prim),
-                                next: None,
-                            },
-                        }],
-                    })),
-                    _ => Ok(ExpressionType::PRIM(prim)),
-                }
+                let struct_name = match &prim {
+                    Primitive::UINT(_) => int_struct_name(),
+                    Primitive::BOOL(_) => bool_struct_name(),
+                    Primitive::CHAR(_) => char_struct_name(),
+                    Primitive::BYTE(_) => byte_struct_name(),
+                    _ => return Ok(ExpressionType::PRIM(prim)),
+                };
+                Ok(Self::STRUCT_INIT(StructInit {
+                    id: CrabType::SIMPLE(struct_name),
+                    fields: vec![StructFieldInit {
+                        name: primitive_field_name(),
+                        value: Expression {
+                            this: ExpressionType::PRIM(prim),
+                            next: None,
+                            crab_type: None,
+                        },
+                    }],
+                    update: None,
+                }))
             }
+            // Interpolated strings are sugar for a call that joins their pieces together
+            Rule::interpolated_string => Ok(Self::FN_CALL(InterpolatedString::try_from(pair)?.0)),
+            // So are map literals, which build the map from a list of keys and a list of values
+            Rule::map_literal => Ok(Self::FN_CALL(MapLiteral::try_from(pair)?.0)),
             Rule::struct_init => Ok(Self::STRUCT_INIT(StructInit::try_from(pair)?)),
             Rule::fn_call => Ok(Self::FN_CALL(FnCall::try_from(pair)?)),
+            Rule::static_fn_call => Ok(Self::STATIC_FN_CALL(StaticFnCall::try_from(pair)?)),
             Rule::ident => Ok(Self::VARIABLE(Ident::from(pair.as_str()))),
             _ => Err(ParseError::NoMatch(String::from(
                 "ExpressionType::try_from<Pair>",
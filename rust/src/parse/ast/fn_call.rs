@@ -1,6 +1,6 @@
 use crate::parse::ast::{AstNode, CrabType, Expression, Ident, StructId};
 use crate::parse::{ParseError, Result, Rule};
-use crate::util::ListFunctional;
+use crate::util::{assoc_const_name, ListFunctional};
 use crate::{compile, try_from_pair};
 use pest::iterators::Pair;
 use std::convert::TryFrom;
@@ -66,6 +66,77 @@ impl FnCall {
     }
 }
 
+///
+/// A call to a static fn, such as `GymGoer::new()`
+/// Associated constants are read the same way, without any parens
+///
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct StaticFnCall {
+    pub caller: CrabType,
+    pub call: FnCall,
+}
+try_from_pair!(StaticFnCall, Rule::static_fn_call);
+impl AstNode for StaticFnCall {
+    fn from_pair(pair: Pair<Rule>) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut inner = pair.into_inner();
+        let caller = CrabType::try_from(inner.next().ok_or(ParseError::ExpectedInner)?)?;
+        let call_pair = inner.next().ok_or(ParseError::ExpectedInner)?;
+        let call = match call_pair.as_rule() {
+            Rule::fn_call => FnCall::try_from(call_pair)?,
+            Rule::ident => FnCall {
+                name: Ident::from(call_pair.as_str()),
+                tmpls: vec![],
+                pos_args: vec![],
+                named_args: vec![],
+            },
+            _ => {
+                return Err(ParseError::IncorrectRule(
+                    String::from(stringify!(StaticFnCall)),
+                    format!("{:?} or {:?}", Rule::fn_call, Rule::ident),
+                    format!("{:?}", call_pair.as_rule()),
+                ))
+            }
+        };
+
+        Ok(Self { caller, call })
+    }
+}
+impl StaticFnCall {
+    ///
+    /// Get the name of the associated constant this could be reading, if it has no args
+    /// Whether that constant exists is up to the caller to check
+    ///
+    pub fn as_assoc_const(&self) -> Option<Ident> {
+        match (&self.call, self.caller.try_get_struct_name()) {
+            (
+                FnCall {
+                    name,
+                    tmpls,
+                    pos_args,
+                    named_args,
+                },
+                Ok(struct_name),
+            ) if tmpls.is_empty() && pos_args.is_empty() && named_args.is_empty() => {
+                Some(assoc_const_name(&struct_name, name))
+            }
+            _ => None,
+        }
+    }
+
+    pub(super) fn resolve(self, caller: CrabType, caller_id: &StructId) -> compile::Result<Self> {
+        Ok(Self {
+            caller: match &caller {
+                CrabType::TMPL(_, tmpls) => self.caller.resolve(caller_id, &tmpls)?,
+                _ => self.caller,
+            },
+            call: self.call.resolve(caller, caller_id)?,
+        })
+    }
+}
+
 struct PosArgs(Vec<Expression>);
 try_from_pair!(PosArgs, Rule::pos_args);
 impl AstNode for PosArgs {
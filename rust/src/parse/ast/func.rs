@@ -1,9 +1,15 @@
 use crate::compile::CompileError;
 use crate::parse::ast::FnBodyType::{CODEBLOCK, COMPILER_PROVIDED};
-use crate::parse::ast::{AstNode, CodeBlock, CrabType, Expression, Ident, Statement, StructId};
+use crate::parse::ast::{
+    AstNode, CodeBlock, CrabType, Expression, Ident, Statement, StructId, StructIdBounds,
+    TmplBounds,
+};
 use crate::parse::{ParseError, Result, Rule};
 use crate::util::MapFunctional;
-use crate::util::{int_struct_name, magic_main_func_name, main_func_name, ListFunctional};
+use crate::util::{
+    int_struct_name, list_struct_name, magic_main_func_name, main_func_name, self_type_name,
+    ListFunctional,
+};
 use crate::{compile, try_from_pair};
 use pest::iterators::Pair;
 use std::collections::BTreeMap;
@@ -46,11 +52,21 @@ impl Func {
     ///
     /// Convert this function to a method
     ///
-    pub fn method(self, struct_id: StructId) -> Self {
-        Self {
+    pub fn method(self, struct_id: StructId) -> Result<Self> {
+        Ok(Self {
             body: self.body,
-            signature: self.signature.method(struct_id),
-        }
+            signature: self.signature.method(struct_id)?,
+        })
+    }
+
+    ///
+    /// Convert this function to a static function, which belongs to a struct but has no self
+    ///
+    pub fn static_method(self, struct_id: StructId) -> Result<Self> {
+        Ok(Self {
+            body: self.body,
+            signature: self.signature.static_method(struct_id)?,
+        })
     }
 
     pub fn mangled(self) -> Self {
@@ -60,6 +76,23 @@ impl Func {
         }
     }
 
+    ///
+    /// Replace every use of the `Self` type in this function with the given type
+    ///
+    pub fn resolve_self(self, self_type: &CrabType) -> compile::Result<Self> {
+        let self_id = StructId {
+            name: Ident::new(),
+            tmpls: vec![StructId::from_name(self_type_name())],
+        };
+        Ok(Self {
+            body: self.body.resolve(
+                CrabType::TMPL(Ident::new(), vec![self_type.clone()]),
+                &self_id,
+            )?,
+            signature: self.signature.resolve_self(self_type),
+        })
+    }
+
     pub fn resolve(
         self,
         caller_opt: Option<CrabType>,
@@ -119,21 +152,29 @@ pub struct FuncSignature {
     pub pos_params: Vec<PosParam>,
     pub named_params: BTreeMap<Ident, NamedParam>,
     pub caller_id: Option<StructId>,
+    pub bounds: TmplBounds,
+    /// Static fns belong to their caller_id's struct, but are called on the type instead of on a value
+    pub is_static: bool,
+    /// Variadic fns collect every positional arg past their other pos params into a list,
+    /// which is passed as their last pos param
+    pub variadic: bool,
 }
 
 try_from_pair!(FuncSignature, Rule::fn_signature);
 impl AstNode for FuncSignature {
     fn from_pair(pair: Pair<Rule>) -> Result<Self> {
         let mut inner = pair.into_inner();
-        let id = StructId::try_from(inner.next().ok_or(ParseError::ExpectedInner)?)?;
+        let id_pair = inner.next().ok_or(ParseError::ExpectedInner)?;
+        let bounds = StructIdBounds::try_from(id_pair.clone())?.0;
+        let id = StructId::try_from(id_pair)?;
         let name = id.name;
         let tmpls = id.tmpls;
 
         let (pos_params, named_params, return_type) = inner.try_fold(
-            (vec![], BTreeMap::new(), CrabType::VOID),
+            (PosParams(vec![], false), BTreeMap::new(), CrabType::VOID),
             |(pos_params, named_params, return_type), pair| {
                 Result::Ok(match pair.as_rule() {
-                    Rule::pos_params => (PosParams::try_from(pair)?.0, named_params, return_type),
+                    Rule::pos_params => (PosParams::try_from(pair)?, named_params, return_type),
                     Rule::named_params => (pos_params, NamedParams::try_from(pair)?.0, return_type),
                     Rule::return_type => (pos_params, named_params, ReturnType::try_from(pair)?.0),
                     _ => {
@@ -156,9 +197,12 @@ impl AstNode for FuncSignature {
             name,
             tmpls,
             return_type,
-            pos_params,
+            pos_params: pos_params.0,
             named_params,
             caller_id: None,
+            bounds,
+            is_static: false,
+            variadic: pos_params.1,
         };
 
         let new_fn = if new_fn.verify_main_fn()? {
@@ -176,19 +220,48 @@ impl AstNode for FuncSignature {
 impl FuncSignature {
     ///
     /// Convert this function signature to a method
+    /// Methods can't have tmpls of their own, but they can add bounds to the tmpls of their struct
+    /// Tmpls that belong to the caller are removed, leaving only the bounds the method adds to them
     ///
-    pub(super) fn method(self, caller_id: StructId) -> Self {
-        if self.tmpls.len() > 0 {
-            unimplemented!();
+    pub(super) fn method(self, caller_id: StructId) -> Result<Self> {
+        let own_tmpl = self.tmpls.iter().find(|tmpl| {
+            !caller_id
+                .tmpls
+                .iter()
+                .any(|caller_tmpl| caller_tmpl.name == tmpl.name)
+        });
+        if let Some(tmpl) = own_tmpl {
+            return Err(ParseError::TemplatedMethod(
+                caller_id.name,
+                self.name,
+                tmpl.name.clone(),
+            ));
         }
-        Self {
+        Ok(Self {
+            tmpls: vec![],
             caller_id: Some(caller_id),
             ..self
+        })
+    }
+
+    ///
+    /// Convert this function signature to a static function
+    ///
+    pub(super) fn static_method(self, caller_id: StructId) -> Result<Self> {
+        if !self.tmpls.is_empty() {
+            return Err(ParseError::TemplatedStaticFn(caller_id.name, self.name));
         }
+        Ok(Self {
+            caller_id: Some(caller_id),
+            is_static: true,
+            ..self
+        })
     }
 
     pub(super) fn implements(&self, other: &FuncSignature) -> bool {
         self.name == other.name
+            && self.is_static == other.is_static
+            && self.variadic == other.variadic
             && self.return_type == other.return_type
             && self.pos_params == other.pos_params
             && self.named_params == other.named_params
@@ -216,6 +289,195 @@ impl FuncSignature {
         }
     }
 
+    ///
+    /// Replace every use of the `Self` type in this function's params and return type with the given type
+    ///
+    pub fn resolve_self(self, self_type: &CrabType) -> Self {
+        Self {
+            return_type: self.return_type.resolve_self(self_type),
+            pos_params: self
+                .pos_params
+                .into_iter()
+                .map(|param| PosParam {
+                    crab_type: param.crab_type.resolve_self(self_type),
+                    ..param
+                })
+                .collect(),
+            named_params: self
+                .named_params
+                .into_iter()
+                .map(|(name, param)| {
+                    (
+                        name,
+                        NamedParam {
+                            crab_type: param.crab_type.resolve_self(self_type),
+                            ..param
+                        },
+                    )
+                })
+                .collect(),
+            ..self
+        }
+    }
+
+    ///
+    /// Infer the tmpls of this function from the types of the arguments it was called with
+    /// Every tmpl must appear in the type of at least one param that was supplied an argument
+    ///
+    /// Params:
+    /// * `args` - The name of each param that was supplied an argument, and the type of that argument
+    ///
+    /// Returns:
+    /// The inferred tmpls, in the same order they were declared
+    ///
+    pub fn infer_tmpls(&self, args: &[(Ident, CrabType)]) -> compile::Result<Vec<CrabType>> {
+        let param_types = self
+            .pos_params
+            .iter()
+            .map(|param| (&param.name, &param.crab_type))
+            .chain(
+                self.named_params
+                    .values()
+                    .map(|param| (&param.name, &param.crab_type)),
+            )
+            .collect::<BTreeMap<_, _>>();
+        let inferred =
+            args.iter().try_fold(
+                BTreeMap::new(),
+                |inferred, (name, arg_type)| match param_types.get(name) {
+                    Some(param_type) => self.unify_tmpls(inferred, param_type, arg_type),
+                    None => Ok(inferred),
+                },
+            )?;
+        self.tmpls
+            .iter()
+            .try_fold(vec![], |tmpls, tmpl| match inferred.get(&tmpl.name) {
+                Some(ct) => Ok(tmpls.fpush(ct.clone())),
+                None => Err(CompileError::AmbiguousTemplate(
+                    self.name.clone(),
+                    tmpl.name.clone(),
+                )),
+            })
+    }
+
+    ///
+    /// Infer the tmpls of this function from the args of a call to it
+    /// The args collected by a variadic param are assumed to all have the type of the first one
+    ///
+    /// Params:
+    /// * `pos_ts` - The type of each positional arg, not including self
+    /// * `named_ts` - The name and type of each named arg
+    ///
+    /// Returns:
+    /// The inferred tmpls, in the same order they were declared
+    ///
+    pub fn infer_call_tmpls(
+        &self,
+        pos_ts: &[CrabType],
+        named_ts: &[(Ident, CrabType)],
+    ) -> compile::Result<Vec<CrabType>> {
+        let (args, collected) = self.split_variadic_args(pos_ts);
+        let collected_arg = match (self.variadic, self.pos_params.last(), collected.first()) {
+            (true, Some(param), Some(arg_t)) => Some((
+                param.name.clone(),
+                CrabType::TMPL(list_struct_name(), vec![arg_t.clone()]),
+            )),
+            _ => None,
+        };
+        let args = self
+            .pos_params
+            .iter()
+            .zip(args.iter())
+            .map(|(param, arg_t)| (param.name.clone(), arg_t.clone()))
+            .chain(collected_arg)
+            .chain(named_ts.iter().cloned())
+            .collect::<Vec<_>>();
+        self.infer_tmpls(&args)
+    }
+
+    ///
+    /// Match a param type against an argument type, recording the type of every tmpl found along the way
+    ///
+    fn unify_tmpls(
+        &self,
+        inferred: BTreeMap<Ident, CrabType>,
+        param_type: &CrabType,
+        arg_type: &CrabType,
+    ) -> compile::Result<BTreeMap<Ident, CrabType>> {
+        match (param_type, arg_type) {
+            (CrabType::SIMPLE(name), _)
+                if self.tmpls.contains(&StructId::from_name(name.clone())) =>
+            {
+                match inferred.get(name) {
+                    Some(existing) if existing != arg_type => {
+                        Err(CompileError::ConflictingTemplate(
+                            self.name.clone(),
+                            name.clone(),
+                            existing.clone(),
+                            arg_type.clone(),
+                        ))
+                    }
+                    _ => Ok(inferred.finsert(name.clone(), arg_type.clone())),
+                }
+            }
+            (CrabType::TMPL(param_name, param_tmpls), CrabType::TMPL(arg_name, arg_tmpls))
+                if param_name == arg_name && param_tmpls.len() == arg_tmpls.len() =>
+            {
+                param_tmpls.iter().zip(arg_tmpls.iter()).try_fold(
+                    inferred,
+                    |inferred, (param_tmpl, arg_tmpl)| {
+                        self.unify_tmpls(inferred, param_tmpl, arg_tmpl)
+                    },
+                )
+            }
+            _ => Ok(inferred),
+        }
+    }
+
+    ///
+    /// Get the type of the args collected by this fn's variadic param
+    ///
+    /// Returns:
+    /// The type of each collected arg, or None if this fn is not variadic
+    ///
+    pub fn variadic_type(&self) -> Option<&CrabType> {
+        match (self.variadic, self.pos_params.last()) {
+            (
+                true,
+                Some(PosParam {
+                    crab_type: CrabType::TMPL(_, tmpls),
+                    ..
+                }),
+            ) => tmpls.first(),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Split the positional args of a call to this fn into the args for its ordinary pos params,
+    /// and the args that are collected by its variadic param
+    /// If this fn is not variadic, every arg is an ordinary arg
+    ///
+    pub fn split_variadic_args<'a, T>(&self, args: &'a [T]) -> (&'a [T], &'a [T]) {
+        match self.variadic {
+            true => args.split_at(std::cmp::min(args.len(), self.pos_params.len() - 1)),
+            false => (args, &[]),
+        }
+    }
+
+    ///
+    /// Get the name of this function as a user would write it, i.e. `Struct.fn` for methods
+    ///
+    pub fn display_name(&self) -> Ident {
+        match &self.caller_id {
+            // Resolving the tmpls of a free function gives it a caller with no name
+            Some(caller_id) if !caller_id.name.is_empty() => {
+                format!("{}.{}", caller_id.name, self.name)
+            }
+            _ => self.name.clone(),
+        }
+    }
+
     pub fn resolve(self, caller: CrabType, caller_id: &StructId) -> compile::Result<Self> {
         match &caller {
             CrabType::TMPL(_, tmpls) => {
@@ -265,14 +527,19 @@ impl FuncSignature {
 }
 impl Display for FuncSignature {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let fn_or_md = match &self.caller_id {
-            None => "FN",
-            Some(_) => "MD",
+        match &self.caller_id {
+            None => write!(f, "-FN-{}", self.name)?,
+            // Static fns have no self param to tell apart the fns of different structs
+            Some(caller_id) if self.is_static => write!(f, "-SF-{}-{}", caller_id, self.name)?,
+            Some(_) => write!(f, "-MD-{}", self.name)?,
         };
-        write!(f, "-{}-{}", fn_or_md, self.name)?;
         self.pos_params
             .iter()
             .try_for_each(|param| write!(f, "-{}", param.crab_type))?;
+        // A variadic param can't be mistaken for an ordinary list param
+        if self.variadic {
+            write!(f, "-...")?;
+        }
         self.named_params
             .iter()
             .try_for_each(|(_, param)| write!(f, "-{}", param.crab_type))?;
@@ -296,18 +563,47 @@ impl AstNode for ReturnType {
     }
 }
 
-struct PosParams(Vec<PosParam>);
+///
+/// The pos params of a fn, and whether or not the last of them is variadic
+///
+struct PosParams(Vec<PosParam>, bool);
 try_from_pair!(PosParams, Rule::pos_params);
 impl AstNode for PosParams {
     fn from_pair(pair: Pair<Rule>) -> Result<Self>
     where
         Self: Sized,
     {
-        Ok(Self(
-            pair.into_inner().try_fold(vec![], |params, param| {
-                Result::Ok(params.fpush(PosParam::try_from(param)?))
-            })?,
-        ))
+        pair.into_inner()
+            .try_fold(Self(vec![], false), |params, param| {
+                Result::Ok(match param.as_rule() {
+                    Rule::variadic_param => {
+                        Self(params.0.fpush(VariadicParam::try_from(param)?.0), true)
+                    }
+                    _ => Self(params.0.fpush(PosParam::try_from(param)?), params.1),
+                })
+            })
+    }
+}
+
+///
+/// A variadic param, such as `Printable ...items`
+/// The fn sees it as a pos param that is a list of the declared type
+///
+struct VariadicParam(PosParam);
+try_from_pair!(VariadicParam, Rule::variadic_param);
+impl AstNode for VariadicParam {
+    fn from_pair(pair: Pair<Rule>) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let mut inner = pair.into_inner();
+        let crab_type = CrabType::try_from(inner.next().ok_or(ParseError::ExpectedInner)?)?;
+        let name = Ident::from(inner.next().ok_or(ParseError::ExpectedInner)?.as_str());
+
+        Ok(Self(PosParam {
+            name,
+            crab_type: CrabType::TMPL(list_struct_name(), vec![crab_type]),
+        }))
     }
 }
 
@@ -1,10 +1,10 @@
 use crate::compile::CompileError;
 use crate::parse::ast::AstNode;
 use crate::parse::{ParseError, Rule};
-use crate::util::{list_struct_name, ListFunctional, MapFunctional};
+use crate::util::{list_struct_name, self_type_name, ListFunctional, MapFunctional};
 use crate::{compile, parse, try_from_pair};
 use pest::iterators::Pair;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
 use std::fmt::{Display, Formatter};
 
@@ -53,6 +53,24 @@ impl CrabType {
         }
     }
 
+    ///
+    /// Replace every use of the `Self` type with the given type
+    /// Inside an interface, `Self` refers to whichever type implements the interface
+    ///
+    pub fn resolve_self(&self, self_type: &CrabType) -> Self {
+        match self {
+            CrabType::SIMPLE(name) if name == &self_type_name() => self_type.clone(),
+            CrabType::TMPL(name, tmpls) => CrabType::TMPL(
+                name.clone(),
+                tmpls
+                    .iter()
+                    .map(|tmpl| tmpl.resolve_self(self_type))
+                    .collect(),
+            ),
+            _ => self.clone(),
+        }
+    }
+
     pub(super) fn resolve(self, caller_id: &StructId, tmpls: &[CrabType]) -> compile::Result<Self> {
         let resolution_map = caller_id.tmpls.iter().zip(tmpls.iter()).fold(
             HashMap::new(),
@@ -173,12 +191,50 @@ impl AstNode for StructId {
     {
         let mut inner = pair.into_inner();
         let name = Ident::from(inner.next().ok_or(ParseError::ExpectedInner)?.as_str());
-        let tmpls = inner.fold(vec![], |tmpls, tmpl| {
-            tmpls.fpush(StructId::from_name(Ident::from(tmpl.as_str())))
-        });
+        let tmpls = inner.try_fold(vec![], |tmpls, tmpl| {
+            let tmpl_name = tmpl
+                .into_inner()
+                .next()
+                .ok_or(ParseError::ExpectedInner)?
+                .as_str();
+            parse::Result::Ok(tmpls.fpush(StructId::from_name(Ident::from(tmpl_name))))
+        })?;
         Ok(Self { name, tmpls })
     }
 }
+
+///
+/// The interfaces that each tmpl of a struct or function must implement, indexed by tmpl name
+/// Tmpls without any bounds are not included
+///
+pub type TmplBounds = BTreeMap<Ident, Vec<Ident>>;
+
+///
+/// Parses the bounds out of a struct_id, for example `T: Printable + Comparable`
+///
+pub(super) struct StructIdBounds(pub TmplBounds);
+try_from_pair!(StructIdBounds, Rule::struct_id);
+impl AstNode for StructIdBounds {
+    fn from_pair(pair: Pair<Rule>) -> parse::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self(pair.into_inner().skip(1).fold(
+            TmplBounds::new(),
+            |bounds, tmpl| {
+                let mut tmpl_inner = tmpl.into_inner();
+                let tmpl_name = tmpl_inner.next().map(|name| Ident::from(name.as_str()));
+                let tmpl_bounds: Vec<Ident> = tmpl_inner
+                    .map(|bound| Ident::from(bound.as_str()))
+                    .collect();
+                match (tmpl_name, tmpl_bounds.is_empty()) {
+                    (Some(tmpl_name), false) => bounds.finsert(tmpl_name, tmpl_bounds),
+                    _ => bounds,
+                }
+            },
+        )))
+    }
+}
 impl StructId {
     /// Returns the mangled name for this StructId
     /// NOTE: This mangling algorithm is quick, dirty, and terrible. It can and will cause name collisions
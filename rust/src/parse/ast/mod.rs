@@ -13,6 +13,8 @@ pub use ident::*;
 mod crab_struct;
 pub use crab_struct::*;
 
+mod crab_struct_derive;
+
 mod code_block;
 pub use code_block::*;
 
@@ -36,3 +38,6 @@ pub use fn_call::*;
 
 mod crab_interface;
 pub use crab_interface::*;
+
+mod crab_const;
+pub use crab_const::*;
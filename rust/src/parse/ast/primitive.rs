@@ -1,7 +1,7 @@
-use crate::parse::ast::{AstNode, Expression};
-use crate::parse::{parse_string, ParseError, Result, Rule};
+use crate::parse::ast::{AstNode, Expression, ExpressionType, FnCall};
+use crate::parse::{parse_byte, parse_char, parse_string, ParseError, Result, Rule};
 use crate::try_from_pair;
-use crate::util::ListFunctional;
+use crate::util::{concat_fn_name, map_of_fn_name, to_string_name, ListFunctional};
 use pest::iterators::Pair;
 use std::convert::TryFrom;
 
@@ -9,6 +9,8 @@ use std::convert::TryFrom;
 pub enum Primitive {
     UINT(u64),
     STRING(String),
+    CHAR(char),
+    BYTE(u8),
     BOOL(bool),
     LIST(Vec<Expression>),
 }
@@ -34,7 +36,21 @@ impl AstNode for Primitive {
                     .next()
                     .ok_or(ParseError::ExpectedInner)?
                     .as_str(),
-            ))),
+            )?)),
+            Rule::char_primitive => Ok(Primitive::CHAR(parse_char(
+                prim_type
+                    .into_inner()
+                    .next()
+                    .ok_or(ParseError::ExpectedInner)?
+                    .as_str(),
+            )?)),
+            Rule::byte_primitive => Ok(Primitive::BYTE(parse_byte(
+                prim_type
+                    .into_inner()
+                    .next()
+                    .ok_or(ParseError::ExpectedInner)?
+                    .as_str(),
+            )?)),
             Rule::bool_primitive => Ok(Primitive::BOOL(prim_type.as_str() == "true")),
             Rule::list_primitive => Ok(Primitive::LIST(ListPrimitive::try_from(prim_type)?.0)),
             _ => Err(ParseError::NoMatch(String::from("Primitive::from_pair"))),
@@ -56,3 +72,90 @@ impl AstNode for ListPrimitive {
         ))
     }
 }
+
+///
+/// A string literal with expressions interpolated into it, such as `"x={x}"`
+/// It is desugared into a call to `__concat__` with the toString of every expression,
+/// so the resulting string is only allocated once
+///
+pub(super) struct InterpolatedString(pub(super) FnCall);
+try_from_pair!(InterpolatedString, Rule::interpolated_string);
+impl AstNode for InterpolatedString {
+    fn from_pair(pair: Pair<Rule>) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let parts = pair.into_inner().try_fold(vec![], |parts, part| {
+            Result::Ok(match part.as_rule() {
+                Rule::string_inner if part.as_str().is_empty() => parts,
+                Rule::string_inner => parts.fpush(Expression {
+                    this: ExpressionType::PRIM(Primitive::STRING(parse_string(part.as_str())?)),
+                    next: None,
+                    crab_type: None,
+                }),
+                Rule::interpolation => {
+                    let mut expr = Expression::try_from(
+                        part.into_inner().next().ok_or(ParseError::ExpectedInner)?,
+                    )?;
+                    expr.append(ExpressionType::FN_CALL(FnCall {
+                        name: to_string_name(),
+                        tmpls: vec![],
+                        pos_args: vec![],
+                        named_args: vec![],
+                    }));
+                    parts.fpush(expr)
+                }
+                _ => {
+                    return Err(ParseError::NoMatch(String::from(
+                        "InterpolatedString::from_pair",
+                    )))
+                }
+            })
+        })?;
+
+        Ok(Self(FnCall {
+            name: concat_fn_name(),
+            tmpls: vec![],
+            pos_args: parts,
+            named_args: vec![],
+        }))
+    }
+}
+
+///
+/// A map literal, such as `{"one": 1, "two": 2}`
+/// It is desugared into a call to `__map_of__` with a list of its keys and a list of its values,
+/// so the types of the map are inferred like they are for list literals
+///
+pub(super) struct MapLiteral(pub(super) FnCall);
+try_from_pair!(MapLiteral, Rule::map_literal);
+impl AstNode for MapLiteral {
+    fn from_pair(pair: Pair<Rule>) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        let (keys, values) =
+            pair.into_inner()
+                .try_fold((vec![], vec![]), |(keys, values), entry| {
+                    let mut inner = entry.into_inner();
+                    let mut next = || -> Result<Expression> {
+                        Expression::try_from(inner.next().ok_or(ParseError::ExpectedInner)?)
+                    };
+                    let key = next()?;
+                    let value = next()?;
+                    Result::Ok((keys.fpush(key), values.fpush(value)))
+                })?;
+        let list = |exprs: Vec<Expression>| Expression {
+            this: ExpressionType::PRIM(Primitive::LIST(exprs)),
+            next: None,
+            crab_type: None,
+        };
+
+        Ok(Self(FnCall {
+            name: map_of_fn_name(),
+            tmpls: vec![],
+            pos_args: vec![list(keys), list(values)],
+            named_args: vec![],
+        }))
+    }
+}
@@ -36,9 +36,38 @@ pub enum ParseError {
     #[error("The struct {0} does not implement {1}, which is required by interface {2}")]
     DoesNotImplement(StructId, Ident, Ident),
 
+    #[error("Function {0} is defined more than once with the same positional param types")]
+    FnRedefinition(Ident),
+
+    #[error("Function {1} of struct {0} is defined in more than one impl block")]
+    MethodRedefinition(Ident, Ident),
+
+    #[error("Constant {0} is declared more than once")]
+    ConstRedefinition(Ident),
+
     #[error("The interface {0} does not exist")]
     InterfaceNotFound(Ident),
 
+    #[error("The interface {0} extends itself")]
+    InterfaceExtendsItself(Ident),
+
+    #[error("Static function {1} of struct {0} cannot have templates")]
+    TemplatedStaticFn(Ident, Ident),
+
+    #[error(
+        "Method {1} of struct {0} declares template {2}, which is not a template of the struct"
+    )]
+    TemplatedMethod(Ident, Ident, Ident),
+
+    #[error("The struct {0} cannot derive {1}")]
+    CannotDerive(StructId, Ident),
+
+    #[error("Invalid escape sequence \\{0} in a string literal")]
+    InvalidEscape(String),
+
+    #[error("The byte literal b'{0}' is not ASCII")]
+    InvalidByteLiteral(char),
+
     #[error("A match had too many inners")]
     TooManyInners,
 
@@ -11,11 +11,28 @@ use std::path::{Path, PathBuf};
 struct CrabParser;
 
 pub fn parse(sources: &[PathBuf]) -> Result<CrabAst> {
+    parse_with_libraries(sources, &[])
+}
+
+///
+/// Parse the given sources and libraries into a single CrabAst
+/// Libraries are parsed exactly like sources, but their functions are marked as library items
+///
+/// Params:
+/// * `sources` - The crabfiles of the program being compiled
+/// * `libraries` - The crabfiles of any libraries the program depends on
+///
+pub fn parse_with_libraries(sources: &[PathBuf], libraries: &[PathBuf]) -> Result<CrabAst> {
     let mut crab_ast = CrabAst::default();
     for source in sources {
         debug!("Parsing crabfile {:#?}", source);
         let ca = parse_file(source)?;
-        crab_ast = crab_ast.join(ca);
+        crab_ast = crab_ast.join(ca)?;
+    }
+    for library in libraries {
+        debug!("Parsing library crabfile {:#?}", library);
+        let ca = parse_file(library)?.as_library();
+        crab_ast = crab_ast.join(ca)?;
     }
     crab_ast.verify()?;
     Ok(crab_ast)
@@ -1,20 +1,103 @@
-pub fn parse_string(input: &str) -> String {
+use crate::parse::{ParseError, Result};
+use std::str::Chars;
+
+///
+/// Replace the escape sequences in a string literal with the characters they stand for
+/// Crab strings are UTF-8, so a `\u{XXXX}` escape may be any Unicode scalar value,
+/// but like in Rust, a `\xXX` escape is limited to ASCII
+///
+/// Params:
+/// * `input` - The contents of the string literal, without its quotes
+///
+/// Returns:
+/// The string the literal stands for, or an error if it contains an invalid escape sequence
+///
+pub fn parse_string(input: &str) -> Result<String> {
     let mut out = String::with_capacity(input.len());
-    let mut escaped = false;
-    for c in input.chars() {
-        if !escaped {
-            match c {
-                '\\' => escaped = true,
-                _ => out += &c.to_string(),
-            };
-        } else {
-            escaped = false;
-            match c {
-                '\\' => out += "\\",
-                'n' => out += "\n",
-                _ => unimplemented!(),
-            };
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.push(parse_escape(&mut chars)?),
+            _ => out.push(c),
         }
     }
-    out
+    Ok(out)
+}
+
+///
+/// Parse the contents of a char literal, such as `a` or `\u{1F980}`, into the character it stands for
+///
+pub fn parse_char(input: &str) -> Result<char> {
+    let mut chars = input.chars();
+    match chars.next() {
+        Some('\\') => parse_escape(&mut chars),
+        Some(c) => Ok(c),
+        None => Err(ParseError::ExpectedInner),
+    }
+}
+
+///
+/// Parse the contents of a byte literal, such as `a` or `\xFF`, into the byte it stands for
+/// Unlike in strings, a `\xXX` escape may be any byte, but any other character must be ASCII
+///
+pub fn parse_byte(input: &str) -> Result<u8> {
+    if let Some(digits) = input.strip_prefix("\\x") {
+        return parse_hex(digits)
+            .filter(|_| digits.len() == 2)
+            .map(|code| code as u8)
+            .ok_or(ParseError::InvalidEscape(format!("x{}", digits)));
+    }
+    let c = parse_char(input)?;
+    match c.is_ascii() {
+        true => Ok(c as u8),
+        false => Err(ParseError::InvalidByteLiteral(c)),
+    }
+}
+
+///
+/// Parse the escape sequence that follows a backslash, consuming it from the given chars
+///
+fn parse_escape(chars: &mut Chars) -> Result<char> {
+    let c = chars
+        .next()
+        .ok_or(ParseError::InvalidEscape(String::new()))?;
+    match c {
+        'n' => Ok('\n'),
+        't' => Ok('\t'),
+        'r' => Ok('\r'),
+        '0' => Ok('\0'),
+        '\\' | '"' | '\'' | '{' | '}' => Ok(c),
+        'x' => {
+            let digits = chars.by_ref().take(2).collect::<String>();
+            match parse_hex(&digits) {
+                Some(code) if digits.len() == 2 && code <= 0x7F => Ok(code as u8 as char),
+                _ => Err(ParseError::InvalidEscape(format!("x{}", digits))),
+            }
+        }
+        'u' => {
+            let rest = chars.as_str();
+            let digits = rest
+                .strip_prefix('{')
+                .and_then(|rest| rest.find('}').map(|end| &rest[..end]))
+                .unwrap_or_default();
+            let escape = format!("u{{{}}}", digits);
+            match (1..=6).contains(&digits.len()) {
+                true => {
+                    *chars = rest[digits.len() + 2..].chars();
+                    parse_hex(digits)
+                        .and_then(char::from_u32)
+                        .ok_or(ParseError::InvalidEscape(escape))
+                }
+                false => Err(ParseError::InvalidEscape(escape)),
+            }
+        }
+        _ => Err(ParseError::InvalidEscape(c.to_string())),
+    }
+}
+
+fn parse_hex(digits: &str) -> Option<u32> {
+    match digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        true => u32::from_str_radix(digits, 16).ok(),
+        false => None,
+    }
 }
@@ -38,6 +38,9 @@ pub enum QuillError {
     #[error("No struct exists with name {0}")]
     NoStruct(String),
 
+    #[error("No global exists with name {0}")]
+    NoGlobal(String),
+
     #[error("Failed to build a GEP instruction")]
     Gep,
 
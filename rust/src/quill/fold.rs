@@ -0,0 +1,781 @@
+use crate::quill::quill_types::QuillListSize;
+use crate::quill::{ChildNib, FnNib, Instruction, IntCmpType, PolyQuillType, QuillConstant};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+///
+/// Folds every instruction of a fn that only depends on values known at compile time into a constant,
+/// and then removes every instruction whose result is never used
+///
+/// Values stored to an alloca are carried over to the loads that read them, as long as the alloca
+/// is only ever stored to and loaded from. Reads of global constants are folded the same way.
+///
+/// Strings are only folded where their bytes are compared. Concatenating or measuring a string is
+/// always a call to another fn, so it can't be seen from here and is left for runtime.
+///
+/// Params:
+/// * `nib` - The fn to fold
+/// * `globals` - The value of every global constant, by name
+///
+pub(super) fn fold_fn(nib: &mut FnNib, globals: &HashMap<String, QuillConstant>) {
+    let slots = find_slots(&nib.inner);
+    fold_nib(&mut nib.inner, Known::default(), &slots, globals);
+    remove_dead(&mut nib.inner, &slots);
+}
+
+///
+/// Everything known about the values of a nib at some point in it
+///
+#[derive(Debug, Clone, Default)]
+struct Known {
+    /// Every value that is a constant, by id
+    values: HashMap<usize, QuillConstant>,
+
+    /// Every constant string, by id
+    strings: HashMap<usize, String>,
+
+    /// The constant that each pointer points to, by the pointer's id
+    memory: HashMap<usize, QuillConstant>,
+}
+
+impl Known {
+    ///
+    /// Forgets everything about a value, because an instruction is giving its id a new value
+    ///
+    fn forget(&mut self, id: usize) {
+        self.values.remove(&id);
+        self.strings.remove(&id);
+        self.memory.remove(&id);
+    }
+
+    ///
+    /// Gets a value as an int, where Bools are ints with a single bit
+    ///
+    fn get_int(&self, id: usize) -> Option<(u32, u64)> {
+        match self.values.get(&id)? {
+            QuillConstant::Int(bits, value) => Some((*bits, *value)),
+            QuillConstant::Bool(value) => Some((1, *value as u64)),
+        }
+    }
+}
+
+///
+/// Folds the instructions of a nib, and then of its children
+///
+/// Params:
+/// * `known` - Everything known when the nib starts
+/// * `slots` - The allocas whose values can be carried from stores to loads
+/// * `globals` - The value of every global constant, by name
+///
+fn fold_nib(
+    nib: &mut ChildNib,
+    mut known: Known,
+    slots: &HashSet<usize>,
+    globals: &HashMap<String, QuillConstant>,
+) {
+    // A loop runs its nib again after it stores to its slots, so values stored before it can't be trusted
+    if is_loop(nib) {
+        stored_slots(nib)
+            .iter()
+            .for_each(|slot| known.forget(*slot));
+    }
+
+    let instructions = std::mem::take(&mut nib.instructions);
+    nib.instructions = instructions
+        .into_iter()
+        .map(|instruction| fold_instruction(instruction, &mut known, slots, globals))
+        .collect();
+}
+
+///
+/// Folds a single instruction, replacing it with a constant if its value is known
+///
+fn fold_instruction(
+    instruction: Instruction,
+    known: &mut Known,
+    slots: &HashSet<usize>,
+    globals: &HashMap<String, QuillConstant>,
+) -> Instruction {
+    if let Some(id) = get_defined(&instruction) {
+        known.forget(id);
+    }
+    let folded = match &instruction {
+        Instruction::ConstInt(id, bits, value) => Some((*id, QuillConstant::Int(*bits, *value))),
+        Instruction::ConstBool(id, value) => Some((*id, QuillConstant::Bool(*value))),
+        Instruction::ConstString(id, value) => {
+            known.strings.insert(*id, value.clone());
+            None
+        }
+        Instruction::IntAdd(id, lhs, rhs) => fold_int_op(known, *id, *lhs, *rhs, |lhs, rhs| {
+            Some(lhs.wrapping_add(rhs))
+        }),
+        Instruction::IntSub(id, lhs, rhs) => fold_int_op(known, *id, *lhs, *rhs, |lhs, rhs| {
+            Some(lhs.wrapping_sub(rhs))
+        }),
+        Instruction::IntMul(id, lhs, rhs) => fold_int_op(known, *id, *lhs, *rhs, |lhs, rhs| {
+            Some(lhs.wrapping_mul(rhs))
+        }),
+        // Dividing by zero is left for the program to do at runtime
+        Instruction::IntDiv(id, lhs, rhs) => {
+            fold_int_op(known, *id, *lhs, *rhs, |lhs, rhs| lhs.checked_div(rhs))
+        }
+        Instruction::IntResize(source, id, bits) => known
+            .get_int(*source)
+            .map(|(_, value)| (*id, QuillConstant::Int(*bits, truncate(value, *bits)))),
+        Instruction::IntCmp(lhs, rhs, id, cmp_type) => {
+            match (known.get_int(*lhs), known.get_int(*rhs)) {
+                (Some((bits, lhs)), Some((_, rhs))) => {
+                    Some((*id, QuillConstant::Bool(compare(lhs, rhs, bits, *cmp_type))))
+                }
+                _ => None,
+            }
+        }
+        Instruction::MemCmp(lhs, rhs, len, id) => {
+            match (
+                known.strings.get(lhs),
+                known.strings.get(rhs),
+                known.get_int(*len),
+            ) {
+                (Some(lhs), Some(rhs), Some((_, len))) => {
+                    mem_cmp(lhs.as_bytes(), rhs.as_bytes(), len)
+                        .map(|result| (*id, QuillConstant::Int(64, result as u64)))
+                }
+                _ => None,
+            }
+        }
+        Instruction::Store(ptr, value) => {
+            if slots.contains(ptr) {
+                match known.values.get(value).copied() {
+                    Some(value) => known.memory.insert(*ptr, value),
+                    None => known.memory.remove(ptr),
+                };
+            }
+            None
+        }
+        Instruction::Load(ptr, id) => known.memory.get(ptr).map(|value| (*id, *value)),
+        Instruction::GlobalGet(id, name) => {
+            if let Some(value) = globals.get(name) {
+                known.memory.insert(*id, *value);
+            }
+            None
+        }
+        _ => None,
+    };
+
+    match (folded, instruction) {
+        (Some((id, value)), _) => {
+            known.values.insert(id, value);
+            match value {
+                QuillConstant::Int(bits, value) => Instruction::ConstInt(id, bits, value),
+                QuillConstant::Bool(value) => Instruction::ConstBool(id, value),
+            }
+        }
+        (None, Instruction::ConditionalBranch(cond, mut t_branch, mut f_branch)) => {
+            fold_nib(&mut t_branch, known.clone(), slots, globals);
+            f_branch
+                .iter_mut()
+                .for_each(|f_branch| fold_nib(f_branch, known.clone(), slots, globals));
+            // Either branch may have run, so anything they stored is unknown afterwards
+            stored_slots(&t_branch)
+                .into_iter()
+                .chain(f_branch.iter().flat_map(stored_slots))
+                .for_each(|slot| known.forget(slot));
+            Instruction::ConditionalBranch(cond, t_branch, f_branch)
+        }
+        (None, Instruction::UnconditionalBranch(mut branch)) => {
+            fold_nib(&mut branch, known.clone(), slots, globals);
+            stored_slots(&branch)
+                .iter()
+                .for_each(|slot| known.forget(*slot));
+            Instruction::UnconditionalBranch(branch)
+        }
+        (None, instruction) => instruction,
+    }
+}
+
+fn fold_int_op<F>(
+    known: &Known,
+    id: usize,
+    lhs: usize,
+    rhs: usize,
+    op: F,
+) -> Option<(usize, QuillConstant)>
+where
+    F: FnOnce(u64, u64) -> Option<u64>,
+{
+    let (bits, lhs) = known.get_int(lhs)?;
+    let (_, rhs) = known.get_int(rhs)?;
+    let result = op(lhs, rhs)?;
+    Some((id, QuillConstant::Int(bits, truncate(result, bits))))
+}
+
+///
+/// Removes every instruction that has no effect and whose result is never used, until none are left
+/// Stores to a slot that is never loaded from are removed too
+///
+fn remove_dead(nib: &mut ChildNib, slots: &HashSet<usize>) {
+    loop {
+        let mut used = HashSet::new();
+        let mut loaded = HashSet::new();
+        visit_instructions(nib, &mut |instruction| {
+            used.extend(get_used(instruction));
+            if let Instruction::Load(ptr, _) = instruction {
+                loaded.insert(*ptr);
+            }
+        });
+        if !remove_unused(nib, &used, &loaded, slots) {
+            break;
+        }
+    }
+}
+
+///
+/// Removes the instructions that have no effect and whose result is never used from a nib and its children
+///
+/// Returns:
+/// Whether any instruction was removed
+///
+fn remove_unused(
+    nib: &mut ChildNib,
+    used: &HashSet<usize>,
+    loaded: &HashSet<usize>,
+    slots: &HashSet<usize>,
+) -> bool {
+    let before = nib.instructions.len();
+    nib.instructions.retain(|instruction| match instruction {
+        Instruction::Store(ptr, _) => !slots.contains(ptr) || loaded.contains(ptr),
+        _ if has_effect(instruction) => true,
+        _ => get_defined(instruction).map_or(true, |id| used.contains(&id)),
+    });
+    let mut removed = nib.instructions.len() != before;
+    nib.instructions
+        .iter_mut()
+        .for_each(|instruction| match instruction {
+            Instruction::ConditionalBranch(_, t_branch, f_branch) => {
+                removed |= remove_unused(t_branch, used, loaded, slots);
+                if let Some(f_branch) = f_branch {
+                    removed |= remove_unused(f_branch, used, loaded, slots);
+                }
+            }
+            Instruction::UnconditionalBranch(branch) => {
+                removed |= remove_unused(branch, used, loaded, slots);
+            }
+            _ => {}
+        });
+    removed
+}
+
+///
+/// Finds the slots of a fn, which are the allocas that are only ever stored to and loaded from
+/// Nothing else can see the memory of a slot, so it always holds whatever was last stored to it
+///
+fn find_slots(nib: &ChildNib) -> HashSet<usize> {
+    let mut allocas = HashSet::new();
+    let mut escaped = HashSet::new();
+    visit_instructions(nib, &mut |instruction| match instruction {
+        Instruction::Alloca(id, _) => {
+            allocas.insert(*id);
+        }
+        Instruction::Store(_, value) => {
+            escaped.insert(*value);
+        }
+        Instruction::Load(_, _) => {}
+        _ => escaped.extend(get_used(instruction)),
+    });
+    // Ids are reused between nibs, so an id that is defined by anything else may not be an alloca at all
+    visit_instructions(nib, &mut |instruction| match instruction {
+        Instruction::Alloca(_, _) => {}
+        _ => escaped.extend(get_defined(instruction)),
+    });
+    allocas.difference(&escaped).copied().collect()
+}
+
+///
+/// Finds every slot that a nib, or any of its children, stores to
+///
+fn stored_slots(nib: &ChildNib) -> Vec<usize> {
+    let mut stored = vec![];
+    visit_instructions(nib, &mut |instruction| {
+        if let Instruction::Store(ptr, _) = instruction {
+            stored.push(*ptr);
+        }
+    });
+    stored
+}
+
+///
+/// Whether a nib is the body of a loop, which jumps back to its start when it reaches its end
+///
+fn is_loop(nib: &ChildNib) -> bool {
+    nib.instructions
+        .iter()
+        .any(|instruction| matches!(instruction, Instruction::ConditionalLoop(_)))
+}
+
+///
+/// Visits every instruction of a nib and its children, in order
+///
+fn visit_instructions<F: FnMut(&Instruction)>(nib: &ChildNib, visit: &mut F) {
+    nib.instructions.iter().for_each(|instruction| {
+        visit(instruction);
+        match instruction {
+            Instruction::ConditionalBranch(_, t_branch, f_branch) => {
+                visit_instructions(t_branch, visit);
+                if let Some(f_branch) = f_branch {
+                    visit_instructions(f_branch, visit);
+                }
+            }
+            Instruction::UnconditionalBranch(branch) => visit_instructions(branch, visit),
+            _ => {}
+        }
+    });
+}
+
+///
+/// Whether an instruction does anything besides defining a value
+///
+fn has_effect(instruction: &Instruction) -> bool {
+    !matches!(
+        instruction,
+        Instruction::ConstInt(_, _, _)
+            | Instruction::ConstBool(_, _)
+            | Instruction::ConstString(_, _)
+            | Instruction::Alloca(_, _)
+            | Instruction::Load(_, _)
+            | Instruction::StructGet(_, _, _)
+            | Instruction::IntAdd(_, _, _)
+            | Instruction::IntSub(_, _, _)
+            | Instruction::IntMul(_, _, _)
+            | Instruction::IntDiv(_, _, _)
+            | Instruction::IntResize(_, _, _)
+            | Instruction::IntCmp(_, _, _, _)
+            | Instruction::ListValueGet(_, _, _)
+            | Instruction::ListOffset(_, _, _)
+            | Instruction::MemCmp(_, _, _, _)
+            | Instruction::GlobalGet(_, _)
+            | Instruction::PointerCast(_, _, _)
+    )
+}
+
+///
+/// Gets the id of the value an instruction defines, if it defines one
+///
+fn get_defined(instruction: &Instruction) -> Option<usize> {
+    match instruction {
+        Instruction::StructGet(_, id, _)
+        | Instruction::ConstInt(id, _, _)
+        | Instruction::ConstBool(id, _)
+        | Instruction::ConstString(id, _)
+        | Instruction::Alloca(id, _)
+        | Instruction::Malloc(id, _)
+        | Instruction::Load(_, id)
+        | Instruction::FnCall(_, id, _)
+        | Instruction::FnParam(id, _)
+        | Instruction::IntAdd(id, _, _)
+        | Instruction::IntSub(id, _, _)
+        | Instruction::IntMul(id, _, _)
+        | Instruction::IntDiv(id, _, _)
+        | Instruction::IntResize(_, id, _)
+        | Instruction::ListValueGet(_, id, _)
+        | Instruction::ListOffset(_, id, _)
+        | Instruction::MemCmp(_, _, _, id)
+        | Instruction::IntCmp(_, _, id, _)
+        | Instruction::GlobalGet(id, _)
+        | Instruction::PointerCast(_, id, _)
+        | Instruction::FnPtrCall(_, id, _) => Some(*id),
+        _ => None,
+    }
+}
+
+///
+/// Gets the ids of every value an instruction uses, not including the instructions of any child nibs
+///
+fn get_used(instruction: &Instruction) -> Vec<usize> {
+    match instruction {
+        Instruction::Return(id) => id.iter().copied().collect(),
+        Instruction::ConditionalBranch(id, _, _) | Instruction::ConditionalLoop(id) => vec![*id],
+        Instruction::StructGet(id, _, _) | Instruction::Load(id, _) | Instruction::Free(id) => {
+            vec![*id]
+        }
+        Instruction::IntResize(id, _, _) => vec![*id],
+        Instruction::PointerCast(id, _, q_type) => {
+            let mut used = get_type_used(q_type);
+            used.push(*id);
+            used
+        }
+        Instruction::StructSet(lhs, rhs, _) | Instruction::Store(lhs, rhs) => vec![*lhs, *rhs],
+        Instruction::IntAdd(_, lhs, rhs)
+        | Instruction::IntSub(_, lhs, rhs)
+        | Instruction::IntMul(_, lhs, rhs)
+        | Instruction::IntDiv(_, lhs, rhs)
+        | Instruction::ListValueGet(lhs, _, rhs)
+        | Instruction::ListOffset(lhs, _, rhs)
+        | Instruction::IntCmp(lhs, rhs, _, _) => vec![*lhs, *rhs],
+        Instruction::ListValueSet(list, value, index) => vec![*list, *value, *index],
+        Instruction::ListCopy(old, new, len, index) => vec![*old, *new, *len, *index],
+        Instruction::MemCmp(lhs, rhs, len, _) => vec![*lhs, *rhs, *len],
+        Instruction::FnCall(_, _, args) => args.clone(),
+        Instruction::FnPtrCall(fn_ptr, _, args) => {
+            let mut used = args.clone();
+            used.push(*fn_ptr);
+            used
+        }
+        Instruction::Alloca(_, q_type) | Instruction::Malloc(_, q_type) => get_type_used(q_type),
+        Instruction::UnconditionalBranch(_)
+        | Instruction::Unreachable
+        | Instruction::ConstInt(_, _, _)
+        | Instruction::ConstBool(_, _)
+        | Instruction::ConstString(_, _)
+        | Instruction::FnParam(_, _)
+        | Instruction::GlobalGet(_, _) => vec![],
+    }
+}
+
+///
+/// Gets the ids of every value a type uses, which are the lengths of variable length lists
+///
+fn get_type_used(q_type: &PolyQuillType) -> Vec<usize> {
+    match q_type {
+        PolyQuillType::PointerType(pt) => get_type_used(&pt.get_inner_type()),
+        PolyQuillType::ListType(lt) => {
+            let mut used = get_type_used(lt.get_inner());
+            if let QuillListSize::Variable(size) = lt.get_size() {
+                used.push(size.id());
+            }
+            used
+        }
+        _ => vec![],
+    }
+}
+
+///
+/// Cuts an int down to the given number of bits, the same way llvm would
+///
+fn truncate(value: u64, bits: u32) -> u64 {
+    match bits {
+        0..=63 => value & ((1 << bits) - 1),
+        _ => value,
+    }
+}
+
+///
+/// Compares two ints the same way llvm would
+///
+fn compare(lhs: u64, rhs: u64, bits: u32, cmp_type: IntCmpType) -> bool {
+    let signed = |value: u64| match bits {
+        1..=63 => ((value << (64 - bits)) as i64) >> (64 - bits),
+        _ => value as i64,
+    };
+    match cmp_type {
+        IntCmpType::EQ => lhs == rhs,
+        IntCmpType::NE => lhs != rhs,
+        IntCmpType::UGT => lhs > rhs,
+        IntCmpType::UGE => lhs >= rhs,
+        IntCmpType::ULT => lhs < rhs,
+        IntCmpType::ULE => lhs <= rhs,
+        IntCmpType::SGT => signed(lhs) > signed(rhs),
+        IntCmpType::SGE => signed(lhs) >= signed(rhs),
+        IntCmpType::SLT => signed(lhs) < signed(rhs),
+        IntCmpType::SLE => signed(lhs) <= signed(rhs),
+    }
+}
+
+///
+/// Compares the first len bytes of two constant strings, the same way memcmp would
+/// Constant strings are built without their null byte, so reading past their end can't be folded
+///
+fn mem_cmp(lhs: &[u8], rhs: &[u8], len: u64) -> Option<i64> {
+    let len = len as usize;
+    if len > lhs.len() || len > rhs.len() {
+        return None;
+    }
+    Some(match lhs[..len].cmp(&rhs[..len]) {
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+        Ordering::Greater => 1,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quill::{Nib, QuillBoolType, QuillFnType, QuillIntType};
+
+    fn new_nib() -> FnNib {
+        FnNib::new(
+            String::from("test"),
+            QuillFnType::new(Some(QuillIntType::new(64)), vec![]),
+        )
+    }
+
+    fn fold(mut nib: FnNib) -> Vec<Instruction> {
+        fold_fn(&mut nib, &HashMap::new());
+        nib.inner.instructions
+    }
+
+    ///
+    /// Builds a fn that divides lhs by rhs and returns the result, then folds it
+    ///
+    fn fold_div(bits: u32, lhs: u64, rhs: u64) -> Vec<Instruction> {
+        let mut nib = new_nib();
+        let lhs = nib.const_int(bits, lhs);
+        let rhs = nib.const_int(bits, rhs);
+        let quotient = nib.int_div(&lhs, &rhs).unwrap();
+        nib.add_return(Some(&quotient));
+        fold(nib)
+    }
+
+    #[test]
+    fn folds_int_ops() {
+        let mut nib = new_nib();
+        let forty = nib.const_int(64, 40);
+        let two = nib.const_int(64, 2);
+        let sum = nib.int_add(&forty, &two).unwrap();
+        let product = nib.int_mul(&sum, &two).unwrap();
+        let difference = nib.int_sub(&two, &product).unwrap();
+        nib.add_return(Some(&difference));
+
+        let instructions = fold(nib);
+        assert!(
+            matches!(
+                instructions.as_slice(),
+                [Instruction::ConstInt(id, 64, value), Instruction::Return(Some(ret))]
+                    if id == ret && *value == 2u64.wrapping_sub(84)
+            ),
+            "{:#?}",
+            instructions
+        );
+    }
+
+    #[test]
+    fn folds_int_cmp() {
+        let mut nib = new_nib();
+        let lhs = nib.const_int(64, 3);
+        let rhs = nib.const_int(64, 4);
+        let less = nib.int_cmp(&lhs, &rhs, IntCmpType::ULT).unwrap();
+        let flag = nib.get_fn_param(String::from("flag"), QuillBoolType::new());
+        nib.add_cond_branch(&less, nib.create_child(), None);
+        nib.add_cond_branch(&flag, nib.create_child(), None);
+
+        let instructions = fold(nib);
+        assert!(
+            matches!(
+                instructions.as_slice(),
+                [
+                    Instruction::ConstBool(id, true),
+                    Instruction::FnParam(_, _),
+                    Instruction::ConditionalBranch(cond, _, None),
+                    Instruction::ConditionalBranch(_, _, None),
+                ] if id == cond
+            ),
+            "{:#?}",
+            instructions
+        );
+    }
+
+    #[test]
+    fn folds_mem_cmp() {
+        let mut nib = new_nib();
+        let lhs = nib.const_string(String::from("crab"));
+        let rhs = nib.const_string(String::from("crib"));
+        let len = nib.const_int(64, 3);
+        let cmp = nib.mem_cmp(&lhs, &rhs, &len);
+        nib.add_return(Some(&cmp));
+
+        let instructions = fold(nib);
+        assert!(
+            matches!(
+                instructions.as_slice(),
+                [Instruction::ConstInt(id, 64, value), Instruction::Return(Some(ret))]
+                    if id == ret && *value == (-1i64) as u64
+            ),
+            "{:#?}",
+            instructions
+        );
+    }
+
+    #[test]
+    fn folds_unsigned_div() {
+        // Ints are unsigned, like llvm's udiv, so a negative looking lhs is a very large one
+        let instructions = fold_div(64, (-7i64) as u64, 2);
+        assert!(
+            matches!(
+                instructions.as_slice(),
+                [Instruction::ConstInt(_, 64, value), Instruction::Return(Some(_))]
+                    if *value == u64::MAX / 2 - 3
+            ),
+            "{:#?}",
+            instructions
+        );
+
+        let instructions = fold_div(8, 0xf9, 2);
+        assert!(
+            matches!(
+                instructions.as_slice(),
+                [
+                    Instruction::ConstInt(_, 8, 0x7c),
+                    Instruction::Return(Some(_))
+                ]
+            ),
+            "{:#?}",
+            instructions
+        );
+    }
+
+    #[test]
+    fn leaves_div_by_zero_for_runtime() {
+        let instructions = fold_div(64, 1, 0);
+        assert!(
+            instructions
+                .iter()
+                .any(|instruction| matches!(instruction, Instruction::IntDiv(_, _, _))),
+            "{:#?}",
+            instructions
+        );
+    }
+
+    #[test]
+    fn carries_stores_to_loads() {
+        let mut nib = new_nib();
+        let slot = nib.add_alloca(QuillIntType::new(64));
+        let five = nib.const_int(64, 5);
+        nib.add_store(&slot, &five).unwrap();
+        let loaded = nib.add_load(&slot, QuillIntType::new(64)).unwrap();
+        let doubled = nib.int_add(&loaded, &loaded).unwrap();
+        nib.add_return(Some(&doubled));
+
+        // The slot is never loaded from once the load is folded, so it disappears entirely
+        let instructions = fold(nib);
+        assert!(
+            matches!(
+                instructions.as_slice(),
+                [Instruction::ConstInt(id, 64, 10), Instruction::Return(Some(ret))] if id == ret
+            ),
+            "{:#?}",
+            instructions
+        );
+    }
+
+    #[test]
+    fn forgets_slots_stored_in_loops() {
+        let mut nib = new_nib();
+        let slot = nib.add_alloca(QuillIntType::new(64));
+        let zero = nib.const_int(64, 0);
+        nib.add_store(&slot, &zero).unwrap();
+
+        let mut body = nib.create_child();
+        let count = body.add_load(&slot, QuillIntType::new(64)).unwrap();
+        let one = body.const_int(64, 1);
+        let next = body.int_add(&count, &one).unwrap();
+        body.add_store(&slot, &next).unwrap();
+        let limit = body.const_int(64, 3);
+        let more = body.int_cmp(&next, &limit, IntCmpType::ULT).unwrap();
+        body.add_cond_loop(&more);
+        nib.add_branch(body);
+
+        let result = nib.add_load(&slot, QuillIntType::new(64)).unwrap();
+        nib.add_return(Some(&result));
+
+        let instructions = fold(nib);
+        let body = instructions
+            .iter()
+            .find_map(|instruction| match instruction {
+                Instruction::UnconditionalBranch(body) => Some(body),
+                _ => None,
+            })
+            .expect("The loop should not be removed");
+        // The first run of the loop would load zero, but every later run loads what the last one stored
+        assert!(
+            body.instructions.iter().any(
+                |instruction| matches!(instruction, Instruction::Load(ptr, _) if *ptr == slot.id())
+            ),
+            "{:#?}",
+            body
+        );
+        assert!(
+            body.instructions
+                .iter()
+                .any(|instruction| matches!(instruction, Instruction::IntAdd(_, _, _))),
+            "{:#?}",
+            body
+        );
+        assert!(
+            matches!(
+                instructions.as_slice(),
+                [.., Instruction::Load(ptr, _), Instruction::Return(Some(_))] if *ptr == slot.id()
+            ),
+            "{:#?}",
+            instructions
+        );
+    }
+
+    #[test]
+    fn forgets_slots_stored_in_branches() {
+        let mut nib = new_nib();
+        let flag = nib.get_fn_param(String::from("flag"), QuillBoolType::new());
+        let changed = nib.add_alloca(QuillIntType::new(64));
+        let unchanged = nib.add_alloca(QuillIntType::new(64));
+        let one = nib.const_int(64, 1);
+        nib.add_store(&changed, &one).unwrap();
+        nib.add_store(&unchanged, &one).unwrap();
+
+        let mut t_branch = nib.create_child();
+        let two = t_branch.const_int(64, 2);
+        t_branch.add_store(&changed, &two).unwrap();
+        nib.add_cond_branch(&flag, t_branch, None);
+
+        let changed_value = nib.add_load(&changed, QuillIntType::new(64)).unwrap();
+        let unchanged_value = nib.add_load(&unchanged, QuillIntType::new(64)).unwrap();
+        let sum = nib.int_add(&changed_value, &unchanged_value).unwrap();
+        nib.add_return(Some(&sum));
+
+        let instructions = fold(nib);
+        // Only the slot that the branch may have stored to still needs to be loaded
+        let loads = instructions
+            .iter()
+            .filter_map(|instruction| match instruction {
+                Instruction::Load(ptr, _) => Some(*ptr),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(loads, vec![changed.id()], "{:#?}", instructions);
+        assert!(
+            instructions
+                .iter()
+                .any(|instruction| matches!(instruction, Instruction::IntAdd(_, _, _))),
+            "{:#?}",
+            instructions
+        );
+        assert!(
+            !instructions
+                .iter()
+                .any(|instruction| matches!(instruction, Instruction::Store(ptr, _) if *ptr == unchanged.id())),
+            "{:#?}",
+            instructions
+        );
+    }
+
+    #[test]
+    fn removes_dead_instructions() {
+        let mut nib = new_nib();
+        let value = nib.get_fn_param(String::from("value"), QuillIntType::new(64));
+        nib.int_mul(&value, &value).unwrap();
+        nib.add_fn_call(String::from("effect"), vec![], QuillIntType::new(64));
+        nib.add_return(Some(&value));
+
+        // Calls may have effects, so they are kept even though their result is never used
+        let instructions = fold(nib);
+        assert!(
+            matches!(
+                instructions.as_slice(),
+                [
+                    Instruction::FnParam(_, _),
+                    Instruction::FnCall(_, _, _),
+                    Instruction::Return(Some(_)),
+                ]
+            ),
+            "{:#?}",
+            instructions
+        );
+    }
+}
@@ -18,3 +18,5 @@ pub use quill_value::*;
 
 mod except;
 pub use except::*;
+
+mod fold;
@@ -3,16 +3,19 @@ use crate::quill::{
     PolyQuillType, Quill, QuillBoolType, QuillError, QuillFnType, QuillIntType, QuillListType,
     QuillPointerType, QuillStructType, QuillType, QuillValue, Result,
 };
-use crate::util::{ListFunctional, ListReplace};
+use crate::util::{live_objects_global_name, refcount_field_name, ListFunctional, ListReplace};
 use inkwell::basic_block::BasicBlock;
+use inkwell::builder::Builder;
 use inkwell::context::Context;
-use inkwell::module::Module;
+use inkwell::module::{Linkage, Module};
 use inkwell::types::AnyTypeEnum;
 use inkwell::values::{
-    BasicMetadataValueEnum, BasicValue, BasicValueEnum, FunctionValue, IntValue, PointerValue,
+    BasicMetadataValueEnum, BasicValue, BasicValueEnum, CallableValue, FunctionValue, IntValue,
+    PointerValue,
 };
 use inkwell::{AddressSpace, IntPredicate};
 use log::trace;
+use std::cmp::Ordering;
 use std::convert::TryFrom;
 use std::fmt::Debug;
 
@@ -22,7 +25,7 @@ pub type IntCmpType = IntPredicate;
 /// Enum of all the possible instructions that can be stored in a nib
 ///
 #[derive(Debug, Clone)]
-enum Instruction {
+pub(super) enum Instruction {
     Return(Option<usize>),                                // Return value
     ConditionalBranch(usize, ChildNib, Option<ChildNib>), // Condition id, t_branch, f_branch
     UnconditionalBranch(ChildNib),                        // Child to branch to
@@ -40,11 +43,20 @@ enum Instruction {
     FnCall(String, usize, Vec<usize>), // Fn name, return id, positional params
     FnParam(usize, String),          // Param id, param name
     IntAdd(usize, usize, usize),     // Result id, lhs id, rhs id
+    IntSub(usize, usize, usize),     // Result id, lhs id, rhs id
+    IntMul(usize, usize, usize),     // Result id, lhs id, rhs id
+    IntDiv(usize, usize, usize),     // Result id, lhs id, rhs id
+    IntResize(usize, usize, u32),    // Source id, destination id, bit width
     ListValueSet(usize, usize, usize), // List id, value id, index id
     ListValueGet(usize, usize, usize), // List id, value id, index id
     ListCopy(usize, usize, usize, usize), // Old list id, new list id, list len, dest index id
+    ListOffset(usize, usize, usize), // List id, destination id, index id
+    MemCmp(usize, usize, usize, usize), // Lhs id, rhs id, len id, result id
     Free(usize),                     // Value id
     IntCmp(usize, usize, usize, IntCmpType), // Lhs id, rhs id, result id, comparison type
+    GlobalGet(usize, String),        // Ptr id, name of the global
+    PointerCast(usize, usize, PolyQuillType), // Source id, destination id, type to cast to
+    FnPtrCall(usize, usize, Vec<usize>), // Fn ptr id, return id, positional params
 }
 
 ///
@@ -185,10 +197,10 @@ pub trait Nib: Debug {
 
     ///
     /// Adds an alloca instruction to the Nib
-    /// This allocates stack memory
+    /// This allocates stack memory, which lives until the fn returns
     ///
     /// Params:
-    /// * `t` - The type to malloc
+    /// * `t` - The type to alloca
     ///
     /// Returns:
     /// A pointer to the created value
@@ -271,6 +283,74 @@ pub trait Nib: Debug {
         rhs: &QuillValue<QuillIntType>,
     ) -> Result<QuillValue<QuillIntType>>;
 
+    ///
+    /// Creates an integer subtraction instruction
+    /// Both params must have the same bit width, and the result will have the same bit width as the params
+    ///
+    /// Params:
+    /// * `lhs` - The int to subtract from
+    /// * `rhs` - The int to subtract
+    ///
+    /// Returns:
+    /// A value representing rhs subtracted from lhs
+    ///
+    fn int_sub(
+        &mut self,
+        lhs: &QuillValue<QuillIntType>,
+        rhs: &QuillValue<QuillIntType>,
+    ) -> Result<QuillValue<QuillIntType>>;
+
+    ///
+    /// Creates an integer multiplication instruction
+    /// Both params must have the same bit width, and the result will have the same bit width as the params
+    ///
+    /// Params:
+    /// * `lhs` - One of the ints to multiply
+    /// * `rhs` - The other of the ints to multiply
+    ///
+    /// Returns:
+    /// A value representing the two ints multiplied together
+    ///
+    fn int_mul(
+        &mut self,
+        lhs: &QuillValue<QuillIntType>,
+        rhs: &QuillValue<QuillIntType>,
+    ) -> Result<QuillValue<QuillIntType>>;
+
+    ///
+    /// Creates an unsigned integer division instruction
+    /// Both params must have the same bit width, and the result will have the same bit width as the params
+    ///
+    /// Params:
+    /// * `lhs` - The dividend
+    /// * `rhs` - The divisor
+    ///
+    /// Returns:
+    /// A value representing lhs divided by rhs, rounded down
+    ///
+    fn int_div(
+        &mut self,
+        lhs: &QuillValue<QuillIntType>,
+        rhs: &QuillValue<QuillIntType>,
+    ) -> Result<QuillValue<QuillIntType>>;
+
+    ///
+    /// Changes the bit width of an int
+    /// Wider ints are zero extended, and narrower ints are truncated
+    ///
+    /// Params:
+    /// * `value` - The int to resize
+    /// * `bits` - The bit width to resize it to
+    ///
+    /// Returns:
+    /// The resized int
+    ///
+    fn int_resize(
+        &mut self,
+        value: &QuillValue<QuillIntType>,
+        bits: u32,
+    ) -> QuillValue<QuillIntType>;
+
     ///
     /// Returns a reference to the fntype this nib is built from
     ///
@@ -330,6 +410,41 @@ pub trait Nib: Debug {
         dest_index: &QuillValue<QuillIntType>,
     ) -> Result<()>;
 
+    ///
+    /// Gets a pointer to an element of a list, so that the rest of the list can be used on its own
+    ///
+    /// Params:
+    /// * `lv` - A pointer to the list
+    /// * `index` - The index of the element to point to
+    ///
+    /// Returns:
+    /// A pointer to the element, with the same type as the list pointer
+    ///
+    fn list_offset(
+        &mut self,
+        lv: &QuillValue<QuillPointerType>,
+        index: &QuillValue<QuillIntType>,
+    ) -> QuillValue<QuillPointerType>;
+
+    ///
+    /// Compares the bytes of two buffers, like C's memcmp
+    ///
+    /// Params:
+    /// * `lhs` - A pointer to the first buffer
+    /// * `rhs` - A pointer to the second buffer
+    /// * `len` - The number of bytes to compare
+    ///
+    /// Returns:
+    /// A signed 64 bit int that is negative if lhs is less than rhs, zero if they are equal,
+    /// and positive if lhs is greater than rhs
+    ///
+    fn mem_cmp(
+        &mut self,
+        lhs: &QuillValue<QuillPointerType>,
+        rhs: &QuillValue<QuillPointerType>,
+        len: &QuillValue<QuillIntType>,
+    ) -> QuillValue<QuillIntType>;
+
     ///
     /// Free a value
     ///
@@ -355,6 +470,53 @@ pub trait Nib: Debug {
         rhs: &QuillValue<QuillIntType>,
         cmp_type: IntCmpType,
     ) -> Result<QuillValue<QuillBoolType>>;
+
+    ///
+    /// Gets a pointer to a global value that was registered with the quill, such as a vtable
+    ///
+    /// Params:
+    /// * `name` - The name of the global to get
+    /// * `t` - The type of the global
+    ///
+    /// Returns:
+    /// A pointer to the global
+    ///
+    fn get_global<T: QuillType>(&mut self, name: String, t: T) -> QuillValue<QuillPointerType>;
+
+    ///
+    /// Casts a pointer to a different pointer type
+    /// The value that is pointed to is not changed in any way
+    ///
+    /// Params:
+    /// * `ptr` - The pointer to cast
+    /// * `t` - The pointer type to cast to
+    ///
+    /// Returns:
+    /// The cast pointer
+    ///
+    fn add_pointer_cast(
+        &mut self,
+        ptr: &QuillValue<QuillPointerType>,
+        t: QuillPointerType,
+    ) -> QuillValue<QuillPointerType>;
+
+    ///
+    /// Add a call through a function pointer to the Nib
+    ///
+    /// Params:
+    /// * `fn_ptr` - A pointer to the function to call
+    /// * `args` - The arguments to the function
+    /// * `expected_type` - The expected return type of the function
+    ///
+    /// Returns:
+    /// A value of the expected type
+    ///
+    fn add_fn_ptr_call<T: QuillType>(
+        &mut self,
+        fn_ptr: &QuillValue<QuillPointerType>,
+        args: Vec<QuillValue<PolyQuillType>>,
+        expected_type: T,
+    ) -> Result<QuillValue<T>>;
 }
 
 ///
@@ -362,7 +524,7 @@ pub trait Nib: Debug {
 ///
 #[derive(Debug, Clone)]
 pub struct FnNib {
-    inner: ChildNib,
+    pub(super) inner: ChildNib,
     fn_name: String,
 }
 
@@ -505,6 +667,34 @@ impl Nib for FnNib {
     ) -> Result<QuillValue<QuillIntType>> {
         self.inner.int_add(lhs, rhs)
     }
+    fn int_sub(
+        &mut self,
+        lhs: &QuillValue<QuillIntType>,
+        rhs: &QuillValue<QuillIntType>,
+    ) -> Result<QuillValue<QuillIntType>> {
+        self.inner.int_sub(lhs, rhs)
+    }
+    fn int_mul(
+        &mut self,
+        lhs: &QuillValue<QuillIntType>,
+        rhs: &QuillValue<QuillIntType>,
+    ) -> Result<QuillValue<QuillIntType>> {
+        self.inner.int_mul(lhs, rhs)
+    }
+    fn int_div(
+        &mut self,
+        lhs: &QuillValue<QuillIntType>,
+        rhs: &QuillValue<QuillIntType>,
+    ) -> Result<QuillValue<QuillIntType>> {
+        self.inner.int_div(lhs, rhs)
+    }
+    fn int_resize(
+        &mut self,
+        value: &QuillValue<QuillIntType>,
+        bits: u32,
+    ) -> QuillValue<QuillIntType> {
+        self.inner.int_resize(value, bits)
+    }
     fn get_fn_t(&self) -> &QuillFnType {
         self.inner.get_fn_t()
     }
@@ -533,6 +723,21 @@ impl Nib for FnNib {
     ) -> Result<()> {
         self.inner.list_copy(ol, nl, len, dest_index)
     }
+    fn list_offset(
+        &mut self,
+        lv: &QuillValue<QuillPointerType>,
+        index: &QuillValue<QuillIntType>,
+    ) -> QuillValue<QuillPointerType> {
+        self.inner.list_offset(lv, index)
+    }
+    fn mem_cmp(
+        &mut self,
+        lhs: &QuillValue<QuillPointerType>,
+        rhs: &QuillValue<QuillPointerType>,
+        len: &QuillValue<QuillIntType>,
+    ) -> QuillValue<QuillIntType> {
+        self.inner.mem_cmp(lhs, rhs, len)
+    }
     fn free(&mut self, val: QuillValue<QuillPointerType>) {
         self.inner.free(val)
     }
@@ -544,6 +749,24 @@ impl Nib for FnNib {
     ) -> Result<QuillValue<QuillBoolType>> {
         self.inner.int_cmp(lhs, rhs, cmp_type)
     }
+    fn get_global<T: QuillType>(&mut self, name: String, t: T) -> QuillValue<QuillPointerType> {
+        self.inner.get_global(name, t)
+    }
+    fn add_pointer_cast(
+        &mut self,
+        ptr: &QuillValue<QuillPointerType>,
+        t: QuillPointerType,
+    ) -> QuillValue<QuillPointerType> {
+        self.inner.add_pointer_cast(ptr, t)
+    }
+    fn add_fn_ptr_call<T: QuillType>(
+        &mut self,
+        fn_ptr: &QuillValue<QuillPointerType>,
+        args: Vec<QuillValue<PolyQuillType>>,
+        expected_type: T,
+    ) -> Result<QuillValue<T>> {
+        self.inner.add_fn_ptr_call(fn_ptr, args, expected_type)
+    }
 }
 
 ///
@@ -551,7 +774,7 @@ impl Nib for FnNib {
 ///
 #[derive(Debug, Clone)]
 pub struct ChildNib {
-    instructions: Vec<Instruction>,
+    pub(super) instructions: Vec<Instruction>,
     parent_fn: QuillFnType,
     id_generator: usize,
     instruction_pointer: usize,
@@ -575,6 +798,31 @@ impl ChildNib {
         self.id_generator
     }
 
+    ///
+    /// Adds an instruction that combines two ints of the same bit width into a new int
+    ///
+    fn int_op(
+        &mut self,
+        lhs: &QuillValue<QuillIntType>,
+        rhs: &QuillValue<QuillIntType>,
+        instruction: fn(usize, usize, usize) -> Instruction,
+    ) -> Result<QuillValue<QuillIntType>> {
+        if lhs.get_type().bit_width() != rhs.get_type().bit_width() {
+            return Err(QuillError::IntSize(
+                lhs.get_type().bit_width(),
+                rhs.get_type().bit_width(),
+            ));
+        }
+        self.instructions
+            .push(instruction(self.id_generator, lhs.id(), rhs.id()));
+        let v = QuillValue::new(
+            self.id_generator,
+            QuillIntType::new(lhs.get_type().bit_width()),
+        );
+        self.id_generator += 1;
+        Ok(v)
+    }
+
     fn commit<'ctx>(
         mut self,
         peter: &Quill,
@@ -699,9 +947,10 @@ impl ChildNib {
                             String::from("Nib::commit::ConditionalLoop"),
                         ));
                     }
+                    // Branches inside the loop move on to new blocks, so go back to the very first one
                     builder.build_conditional_branch(
                         cond,
-                        curr_basic_block,
+                        first_basic_block,
                         after.ok_or(QuillError::NoAfter)?,
                     );
                 }
@@ -822,9 +1071,13 @@ impl ChildNib {
                 }
 
                 Instruction::ConstString(id, value) => {
-                    let const_string = builder
-                        .build_global_string_ptr(&value, "str_ptr")
-                        .as_pointer_value();
+                    // Strings may contain null bytes, so they can't be passed to LLVM as C strings
+                    let bytes = context.const_string(value.as_bytes(), false);
+                    let global = module.add_global(bytes.get_type(), None, "str_ptr");
+                    global.set_initializer(&bytes);
+                    global.set_constant(true);
+                    global.set_linkage(Linkage::Private);
+                    let const_string = global.as_pointer_value();
                     // Using value.len() means we don't copy the null byte
                     let string_len = context.i64_type().const_int(value.len() as u64, false);
                     let string_array = builder
@@ -836,20 +1089,44 @@ impl ChildNib {
                     values.replace(id, Some(string_array.as_basic_value_enum()));
                 }
 
-                Instruction::Alloca(dest_id, q_type) => match q_type {
-                    PolyQuillType::PointerType(pt) => match pt.get_inner_type() {
+                Instruction::Alloca(dest_id, q_type) => {
+                    // Every alloca goes at the start of the fn, so that loops reuse the same stack memory
+                    // The fn's first nib always appends its entry block before building anything
+                    let entry = fn_val.get_first_basic_block().unwrap();
+                    let entry_builder = context.create_builder();
+                    match entry.get_first_instruction() {
+                        Some(first) => entry_builder.position_before(&first),
+                        None => entry_builder.position_at_end(entry),
+                    }
+                    let ptr = match q_type {
+                        PolyQuillType::PointerType(pt) => match pt.get_inner_type() {
+                            PolyQuillType::StructType(qst) => {
+                                let l_t = module
+                                    .get_struct_type(&qst.get_name())
+                                    .ok_or(QuillError::NoStruct(qst.get_name()))?
+                                    .ptr_type(AddressSpace::Generic);
+                                entry_builder.build_alloca(l_t, "struct_alloca")
+                            }
+                            _ => todo!(),
+                        },
                         PolyQuillType::StructType(qst) => {
                             let l_t = module
                                 .get_struct_type(&qst.get_name())
-                                .ok_or(QuillError::NoStruct(qst.get_name()))?
-                                .ptr_type(AddressSpace::Generic);
-                            let ptr = builder.build_alloca(l_t, "struct_alloca");
-                            values.replace(dest_id, Some(ptr.as_basic_value_enum()));
+                                .ok_or(QuillError::NoStruct(qst.get_name()))?;
+                            entry_builder.build_alloca(l_t, "stack_struct")
+                        }
+                        PolyQuillType::IntType(it) => {
+                            let l_t = it.as_llvm_type(&context, &module)?;
+                            entry_builder.build_alloca(l_t, "int_alloca")
+                        }
+                        PolyQuillType::BoolType(bt) => {
+                            let l_t = bt.as_llvm_type(&context, &module)?;
+                            entry_builder.build_alloca(l_t, "bool_alloca")
                         }
                         _ => todo!(),
-                    },
-                    _ => todo!(),
-                },
+                    };
+                    values.replace(dest_id, Some(ptr.as_basic_value_enum()));
+                }
 
                 Instruction::Malloc(dest_id, q_type) => match q_type {
                     PolyQuillType::StructType(t) => {
@@ -859,6 +1136,20 @@ impl ChildNib {
                         let ptr = builder
                             .build_malloc(l_t, "struct_malloc")
                             .or(Err(QuillError::MallocErr))?;
+                        // Reference counted structs start with one reference, owned by whoever malloc'd them
+                        let q_strct = peter
+                            .get_struct_defintion(&t.get_name())
+                            .ok_or(QuillError::NoStruct(t.get_name()))?;
+                        if let Ok(index) = q_strct.get_index(&refcount_field_name()) {
+                            let refcount_ptr = builder
+                                .build_struct_gep(ptr, index, "refcount")
+                                .or(Err(QuillError::Gep))?;
+                            let one = context.i64_type().const_int(1, false);
+                            builder.build_store(refcount_ptr, one);
+                            if peter.counts_live_objects() {
+                                count_live_objects(&builder, module, one)?;
+                            }
+                        }
                         values.replace(dest_id, Some(ptr.as_basic_value_enum()));
                     }
                     PolyQuillType::ListType(t) => {
@@ -965,6 +1256,50 @@ impl ChildNib {
                     );
                 }
 
+                Instruction::IntSub(dest_id, lhs_id, rhs_id) => {
+                    let (lhs, rhs) = get_int_operands(&values, lhs_id, rhs_id, "IntSub")?;
+                    values.replace(
+                        dest_id,
+                        Some(builder.build_int_sub(lhs, rhs, "sub").as_basic_value_enum()),
+                    );
+                }
+
+                Instruction::IntMul(dest_id, lhs_id, rhs_id) => {
+                    let (lhs, rhs) = get_int_operands(&values, lhs_id, rhs_id, "IntMul")?;
+                    values.replace(
+                        dest_id,
+                        Some(builder.build_int_mul(lhs, rhs, "mul").as_basic_value_enum()),
+                    );
+                }
+
+                Instruction::IntDiv(dest_id, lhs_id, rhs_id) => {
+                    let (lhs, rhs) = get_int_operands(&values, lhs_id, rhs_id, "IntDiv")?;
+                    values.replace(
+                        dest_id,
+                        Some(
+                            builder
+                                .build_int_unsigned_div(lhs, rhs, "div")
+                                .as_basic_value_enum(),
+                        ),
+                    );
+                }
+
+                Instruction::IntResize(source_id, dest_id, bits) => {
+                    let source = values
+                        .get(source_id)
+                        .unwrap()
+                        .ok_or(QuillError::BadValueAccess)?;
+                    let source_int = IntValue::try_from(source).or(Err(QuillError::Convert))?;
+                    let source_bits = source_int.get_type().get_bit_width();
+                    let l_t = context.custom_width_int_type(bits);
+                    let resized = match source_bits.cmp(&bits) {
+                        Ordering::Less => builder.build_int_z_extend(source_int, l_t, "zext"),
+                        Ordering::Greater => builder.build_int_truncate(source_int, l_t, "trunc"),
+                        Ordering::Equal => source_int,
+                    };
+                    values.replace(dest_id, Some(resized.as_basic_value_enum()));
+                }
+
                 Instruction::ListValueSet(list_id, value_id, index_id) => unsafe {
                     let list = values
                         .get(list_id)
@@ -1038,12 +1373,85 @@ impl ChildNib {
                         .or(Err(QuillError::Memcpy))?;
                 },
 
+                Instruction::ListOffset(list_id, dest_id, index_id) => unsafe {
+                    let list = values
+                        .get(list_id)
+                        .unwrap()
+                        .ok_or(QuillError::BadValueAccess)?;
+                    let index = values
+                        .get(index_id)
+                        .unwrap()
+                        .ok_or(QuillError::BadValueAccess)?;
+                    let element_ptr = builder.build_gep(
+                        PointerValue::try_from(list).or(Err(QuillError::Convert))?,
+                        &[IntValue::try_from(index).or(Err(QuillError::Convert))?],
+                        "list_offset_gep",
+                    );
+                    values.replace(dest_id, Some(element_ptr.as_basic_value_enum()));
+                },
+
+                Instruction::MemCmp(lhs_id, rhs_id, len_id, dest_id) => {
+                    let byte_ptr_t = context.i8_type().ptr_type(AddressSpace::Generic);
+                    let memcmp = module.get_function("memcmp").unwrap_or_else(|| {
+                        module.add_function(
+                            "memcmp",
+                            context.i32_type().fn_type(
+                                &[
+                                    byte_ptr_t.into(),
+                                    byte_ptr_t.into(),
+                                    context.i64_type().into(),
+                                ],
+                                false,
+                            ),
+                            None,
+                        )
+                    });
+                    let args = [lhs_id, rhs_id]
+                        .iter()
+                        .try_fold(vec![], |args, id| {
+                            let ptr = values.get(*id).unwrap().ok_or(QuillError::BadValueAccess)?;
+                            let ptr = PointerValue::try_from(ptr).or(Err(QuillError::Convert))?;
+                            let byte_ptr = builder.build_pointer_cast(ptr, byte_ptr_t, "byte_ptr");
+                            Result::Ok(args.fpush(BasicMetadataValueEnum::from(byte_ptr)))
+                        })?
+                        .fpush(BasicMetadataValueEnum::from(
+                            values
+                                .get(len_id)
+                                .unwrap()
+                                .ok_or(QuillError::BadValueAccess)?,
+                        ));
+                    let result = builder
+                        .build_call(memcmp, &args, "memcmp")
+                        .try_as_basic_value()
+                        .left()
+                        .ok_or(QuillError::Convert)?;
+                    let result = builder.build_int_s_extend(
+                        IntValue::try_from(result).or(Err(QuillError::Convert))?,
+                        context.i64_type(),
+                        "memcmp_sext",
+                    );
+                    values.replace(dest_id, Some(result.as_basic_value_enum()));
+                }
+
                 Instruction::Free(val_id) => {
                     let val = values
                         .get(val_id)
                         .unwrap()
                         .ok_or(QuillError::BadValueAccess)?;
                     let ptr = PointerValue::try_from(val).or(Err(QuillError::Convert))?;
+                    if let AnyTypeEnum::StructType(strct_type) = ptr.get_type().get_element_type() {
+                        let name = strct_type.get_name().unwrap().to_str()?;
+                        let counted = peter.get_struct_defintion(name).map_or(false, |q_strct| {
+                            q_strct.get_index(&refcount_field_name()).is_ok()
+                        });
+                        if counted && peter.counts_live_objects() {
+                            count_live_objects(
+                                &builder,
+                                module,
+                                context.i64_type().const_all_ones(),
+                            )?;
+                        }
+                    }
                     builder.build_free(ptr);
                 }
 
@@ -1062,6 +1470,45 @@ impl ChildNib {
                         builder.build_int_compare(cmp_type, lhs_int, rhs_int, "int_cmp");
                     values.replace(val_id, Some(cmp_result.into()))
                 }
+
+                Instruction::GlobalGet(dest_id, name) => {
+                    let global = module.get_global(&name).ok_or(QuillError::NoGlobal(name))?;
+                    values.replace(
+                        dest_id,
+                        Some(global.as_pointer_value().as_basic_value_enum()),
+                    );
+                }
+
+                Instruction::PointerCast(source_id, dest_id, q_type) => {
+                    let source = values
+                        .get(source_id)
+                        .unwrap()
+                        .ok_or(QuillError::BadValueAccess)?;
+                    let source_ptr = PointerValue::try_from(source).or(Err(QuillError::Convert))?;
+                    let l_t = q_type.as_llvm_type(&context, &module)?.into_pointer_type();
+                    let cast = builder.build_pointer_cast(source_ptr, l_t, "ptr_cast");
+                    values.replace(dest_id, Some(cast.as_basic_value_enum()));
+                }
+
+                Instruction::FnPtrCall(fn_ptr_id, ret_id, pos_args) => {
+                    let fn_ptr = values
+                        .get(fn_ptr_id)
+                        .unwrap()
+                        .ok_or(QuillError::BadValueAccess)?;
+                    let fn_ptr = PointerValue::try_from(fn_ptr).or(Err(QuillError::Convert))?;
+                    let callable = CallableValue::try_from(fn_ptr).or(Err(QuillError::Convert))?;
+
+                    let args = pos_args.into_iter().try_fold(vec![], |args, id| {
+                        Result::Ok(args.fpush(BasicMetadataValueEnum::from(
+                            values.get(id).unwrap().ok_or(QuillError::BadValueAccess)?,
+                        )))
+                    })?;
+
+                    let ret_val = builder.build_call(callable, &args, "fn_ptr_call");
+                    if let Some(bv) = ret_val.try_as_basic_value().left() {
+                        values.replace(ret_id, Some(bv));
+                    }
+                }
             }
         }
 
@@ -1250,20 +1697,43 @@ impl Nib for ChildNib {
         lhs: &QuillValue<QuillIntType>,
         rhs: &QuillValue<QuillIntType>,
     ) -> Result<QuillValue<QuillIntType>> {
-        if lhs.get_type().bit_width() != rhs.get_type().bit_width() {
-            return Err(QuillError::IntSize(
-                lhs.get_type().bit_width(),
-                rhs.get_type().bit_width(),
-            ));
-        }
+        self.int_op(lhs, rhs, Instruction::IntAdd)
+    }
+
+    fn int_sub(
+        &mut self,
+        lhs: &QuillValue<QuillIntType>,
+        rhs: &QuillValue<QuillIntType>,
+    ) -> Result<QuillValue<QuillIntType>> {
+        self.int_op(lhs, rhs, Instruction::IntSub)
+    }
+
+    fn int_mul(
+        &mut self,
+        lhs: &QuillValue<QuillIntType>,
+        rhs: &QuillValue<QuillIntType>,
+    ) -> Result<QuillValue<QuillIntType>> {
+        self.int_op(lhs, rhs, Instruction::IntMul)
+    }
+
+    fn int_div(
+        &mut self,
+        lhs: &QuillValue<QuillIntType>,
+        rhs: &QuillValue<QuillIntType>,
+    ) -> Result<QuillValue<QuillIntType>> {
+        self.int_op(lhs, rhs, Instruction::IntDiv)
+    }
+
+    fn int_resize(
+        &mut self,
+        value: &QuillValue<QuillIntType>,
+        bits: u32,
+    ) -> QuillValue<QuillIntType> {
         self.instructions
-            .push(Instruction::IntAdd(self.id_generator, lhs.id(), rhs.id()));
-        let v = QuillValue::new(
-            self.id_generator,
-            QuillIntType::new(lhs.get_type().bit_width()),
-        );
+            .push(Instruction::IntResize(value.id(), self.id_generator, bits));
+        let v = QuillValue::new(self.id_generator, QuillIntType::new(bits));
         self.id_generator += 1;
-        Ok(v)
+        v
     }
 
     fn get_fn_t(&self) -> &QuillFnType {
@@ -1343,6 +1813,38 @@ impl Nib for ChildNib {
         }
     }
 
+    fn list_offset(
+        &mut self,
+        lv: &QuillValue<QuillPointerType>,
+        index: &QuillValue<QuillIntType>,
+    ) -> QuillValue<QuillPointerType> {
+        self.instructions.push(Instruction::ListOffset(
+            lv.id(),
+            self.id_generator,
+            index.id(),
+        ));
+        let v = QuillValue::new(self.id_generator, lv.get_type().clone());
+        self.id_generator += 1;
+        v
+    }
+
+    fn mem_cmp(
+        &mut self,
+        lhs: &QuillValue<QuillPointerType>,
+        rhs: &QuillValue<QuillPointerType>,
+        len: &QuillValue<QuillIntType>,
+    ) -> QuillValue<QuillIntType> {
+        self.instructions.push(Instruction::MemCmp(
+            lhs.id(),
+            rhs.id(),
+            len.id(),
+            self.id_generator,
+        ));
+        let v = QuillValue::new(self.id_generator, QuillIntType::new(64));
+        self.id_generator += 1;
+        v
+    }
+
     fn free(&mut self, val: QuillValue<QuillPointerType>) {
         self.instructions.push(Instruction::Free(val.id()));
     }
@@ -1370,4 +1872,84 @@ impl Nib for ChildNib {
             Ok(result)
         }
     }
+
+    fn get_global<T: QuillType>(&mut self, name: String, t: T) -> QuillValue<QuillPointerType> {
+        self.instructions
+            .push(Instruction::GlobalGet(self.id_generator, name));
+        let v = QuillValue::new(self.id_generator, QuillPointerType::new(t));
+        self.id_generator += 1;
+        v
+    }
+
+    fn add_pointer_cast(
+        &mut self,
+        ptr: &QuillValue<QuillPointerType>,
+        t: QuillPointerType,
+    ) -> QuillValue<QuillPointerType> {
+        self.instructions.push(Instruction::PointerCast(
+            ptr.id(),
+            self.id_generator,
+            t.clone().into(),
+        ));
+        let v = QuillValue::new(self.id_generator, t);
+        self.id_generator += 1;
+        v
+    }
+
+    fn add_fn_ptr_call<T: QuillType>(
+        &mut self,
+        fn_ptr: &QuillValue<QuillPointerType>,
+        args: Vec<QuillValue<PolyQuillType>>,
+        expected_type: T,
+    ) -> Result<QuillValue<T>> {
+        // Ensure we got a fn pointer
+        QuillFnType::try_from(fn_ptr.get_type().get_inner_type())?;
+        self.instructions.push(Instruction::FnPtrCall(
+            fn_ptr.id(),
+            self.id_generator,
+            args.into_iter().map(|arg| arg.id()).collect(),
+        ));
+        let v = QuillValue::new(self.id_generator, expected_type);
+        self.id_generator += 1;
+        Ok(v)
+    }
+}
+
+///
+/// Gets the two int operands of an arithmetic instruction while the instruction is being committed
+///
+fn get_int_operands<'ctx>(
+    values: &[Option<BasicValueEnum<'ctx>>],
+    lhs_id: usize,
+    rhs_id: usize,
+    instruction: &str,
+) -> Result<(IntValue<'ctx>, IntValue<'ctx>)> {
+    let get_int = |id: usize| match values.get(id).unwrap().ok_or(QuillError::BadValueAccess)? {
+        BasicValueEnum::IntValue(iv) => Ok(iv),
+        t => Err(QuillError::WrongType(
+            format!("{:?}", t),
+            String::from("IntValue"),
+            format!("Nib::commit::{}", instruction),
+        )),
+    };
+    Ok((get_int(lhs_id)?, get_int(rhs_id)?))
+}
+
+///
+/// Adds delta to the number of reference counted structs that have been malloc'd, but not yet freed
+///
+fn count_live_objects<'ctx>(
+    builder: &Builder<'ctx>,
+    module: &Module<'ctx>,
+    delta: IntValue<'ctx>,
+) -> Result<()> {
+    let name = live_objects_global_name();
+    let global = module
+        .get_global(&name)
+        .ok_or(QuillError::NoGlobal(name))?
+        .as_pointer_value();
+    let count = builder.build_load(global, "live_objects").into_int_value();
+    let count = builder.build_int_add(count, delta, "live_objects");
+    builder.build_store(global, count);
+    Ok(())
 }
@@ -1,13 +1,20 @@
-use crate::quill::{FnNib, Nib, PolyQuillType, QuillError, QuillFnType, QuillType, Result};
-use crate::util::{ListFunctional, ListReplace, MapFunctional};
+use crate::quill::fold::fold_fn;
+use crate::quill::{
+    FnNib, Nib, PolyQuillType, QuillBoolType, QuillError, QuillFnType, QuillIntType,
+    QuillStructType, QuillType, Result,
+};
+use crate::util::{live_objects_global_name, ListFunctional, ListReplace, MapFunctional};
 use inkwell::context::Context;
-use inkwell::module::Linkage;
-use inkwell::types::{BasicMetadataTypeEnum, BasicType};
+use inkwell::module::{Linkage, Module};
+use inkwell::passes::{PassManager, PassManagerBuilder};
+use inkwell::values::{BasicValue, FunctionValue};
+use inkwell::OptimizationLevel;
 use log::{debug, error, trace};
 use std::collections::HashMap;
 use std::fs;
 use std::panic::catch_unwind;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 ///
 /// Essentially a combined inkwell context & module
@@ -17,6 +24,11 @@ pub struct Quill {
     functions: HashMap<String, (QuillFnType, FnNib)>,
     struct_types: HashMap<String, StructDefinition>,
     external_functions: HashMap<String, QuillFnType>,
+    vtables: HashMap<String, VTableDefinition>,
+    globals: HashMap<String, QuillConstant>,
+
+    /// Whether reference counted structs update the count of live objects when they are malloc'd and freed
+    count_live_objects: bool,
 }
 
 impl Quill {
@@ -24,14 +36,43 @@ impl Quill {
         Self::default()
     }
 
+    ///
+    /// Consumes self, returning a Quill that counts the reference counted structs that are alive
+    /// Counting adds a load, add and store to every malloc and free, so it should only be used for debugging
+    ///
+    /// Params:
+    /// * `count_live_objects` - Whether to count live objects
+    ///
+    pub fn with_live_object_count(self, count_live_objects: bool) -> Self {
+        Self {
+            count_live_objects,
+            ..self
+        }
+    }
+
+    ///
+    /// Returns whether mallocs and frees of reference counted structs update the count of live objects
+    ///
+    pub fn counts_live_objects(&self) -> bool {
+        self.count_live_objects
+    }
+
     ///
     /// Commits all of the instructions contained in this quill to an intermediate artifact llvm understands
     ///
     /// Params:
     /// * `out_path` - Where to place the generated artifact. If no file extension is provided, one that matches the artifact_type will be chosen
     /// * `artifact_type` - The type of artifact to build
+    /// * `opt_level` - How much llvm should optimize the artifact
+    /// * `verify` - Whether to verify the generated llvm IR before optimizing it
     ///
-    pub fn commit(self, out_path: &Path, artifact_type: &ArtifactType, verify: bool) -> Result<()> {
+    pub fn commit(
+        self,
+        out_path: &Path,
+        artifact_type: &ArtifactType,
+        opt_level: &OptLevel,
+        verify: bool,
+    ) -> Result<()> {
         trace!("Called Quill::commit with out_path {:?}", out_path);
 
         if let ArtifactType::QIR = artifact_type {
@@ -74,24 +115,35 @@ impl Quill {
                     Result::Ok(())
                 })?;
 
+            // Count the reference counted structs that are alive, so that programs can check for leaks
+            // The count is always defined, but it stays at zero unless counting is enabled
+            let live_objects =
+                module.add_global(context.i64_type(), None, &live_objects_global_name());
+            live_objects.set_initializer(&context.i64_type().const_zero());
+
+            // Global constants only hold scalars, so they don't depend on anything else
+            debug!("Registering globals");
+            self.globals.iter().for_each(|(name, value)| {
+                trace!("Registering global {}", name);
+                let l_value = match value {
+                    QuillConstant::Int(bits, value) => context
+                        .custom_width_int_type(*bits)
+                        .const_int(*value, false),
+                    QuillConstant::Bool(value) => {
+                        context.bool_type().const_int(*value as u64, false)
+                    }
+                };
+                let global = module.add_global(l_value.get_type(), None, name);
+                global.set_initializer(&l_value);
+                global.set_constant(true);
+            });
+
             // Register the external functions
             self.external_functions
                 .iter()
                 .try_for_each(|(name, header)| {
                     trace!("Registering external fn type {}", name);
-                    let params =
-                        header
-                            .get_params()
-                            .iter()
-                            .try_fold(vec![], |params, (_, param)| {
-                                Result::Ok(params.fpush(BasicMetadataTypeEnum::from(
-                                    param.as_llvm_type(&context, &module)?,
-                                )))
-                            })?;
-                    let fn_t = match header.get_ret_type() {
-                        None => context.void_type().fn_type(&params, false),
-                        Some(pqt) => pqt.as_llvm_type(&context, &module)?.fn_type(&params, false),
-                    };
+                    let fn_t = header.as_llvm_fn_type(&context, &module)?;
                     module.add_function(name, fn_t, Some(Linkage::External));
                     Result::Ok(())
                 })?;
@@ -100,23 +152,46 @@ impl Quill {
             debug!("Registering function types");
             self.functions.iter().try_for_each(|(name, (header, _))| {
                 trace!("Registering fn type {}", name);
-                let params =
-                    header
-                        .get_params()
-                        .iter()
-                        .try_fold(vec![], |params, (_, param)| {
-                            Result::Ok(params.fpush(BasicMetadataTypeEnum::from(
-                                param.as_llvm_type(&context, &module)?,
-                            )))
-                        })?;
-                let fn_t = match header.get_ret_type() {
-                    None => context.void_type().fn_type(&params, false),
-                    Some(pqt) => pqt.as_llvm_type(&context, &module)?.fn_type(&params, false),
-                };
+                let fn_t = header.as_llvm_fn_type(&context, &module)?;
                 module.add_function(name, fn_t, None);
                 Result::Ok(())
             })?;
 
+            // Now that every fn exists, we can fill the vtables with pointers to them
+            debug!("Registering vtables");
+            self.vtables.iter().try_for_each(|(name, vtable)| {
+                trace!("Registering vtable {}", name);
+                let struct_name = vtable.struct_t.get_name();
+                let l_st = module
+                    .get_struct_type(&struct_name)
+                    .ok_or(QuillError::NoStruct(struct_name.clone()))?;
+                let q_st = self
+                    .get_struct_defintion(&struct_name)
+                    .ok_or(QuillError::NoStruct(struct_name.clone()))?;
+                // The fns' self params are more specific than the vtable's, so each fn pointer must be cast
+                let fn_ptrs = q_st
+                    .get_names()
+                    .into_iter()
+                    .zip(q_st.get_types())
+                    .try_fold(vec![], |fn_ptrs, (field_name, field_t)| {
+                        let fn_name = vtable
+                            .fns
+                            .get(&field_name)
+                            .ok_or(QuillError::StructHasNoField(field_name.clone()))?;
+                        let fn_val = module
+                            .get_function(fn_name)
+                            .ok_or(QuillError::FnNotFound(fn_name.clone()))?;
+                        let fn_ptr = fn_val.as_global_value().as_pointer_value().const_cast(
+                            field_t.as_llvm_type(&context, &module)?.into_pointer_type(),
+                        );
+                        Result::Ok(fn_ptrs.fpush(fn_ptr.as_basic_value_enum()))
+                    })?;
+                let global = module.add_global(l_st, None, name);
+                global.set_initializer(&l_st.const_named_struct(&fn_ptrs));
+                global.set_constant(true);
+                Result::Ok(())
+            })?;
+
             // Finally, a pass to build all of the code inside the functions
             debug!("Registering function definitions");
             self.functions
@@ -153,6 +228,9 @@ impl Quill {
                     module.verify().unwrap();
                 }
 
+                // Optimize after verifying, so that any broken IR is caught before llvm touches it
+                optimize(&module, opt_level);
+
                 // Output the generated artifact to a file
                 let out_path = match out_path.extension() {
                     Some(_) => PathBuf::from(out_path),
@@ -170,6 +248,20 @@ impl Quill {
         }
     }
 
+    ///
+    /// Folds every instruction that can be computed at compile time into a constant,
+    /// and then removes every instruction whose result is never used
+    /// This only rewrites the quill's own instructions, so it should be run before commit
+    ///
+    pub fn fold_constants(&mut self) {
+        debug!("Folding constants");
+        let globals = &self.globals;
+        self.functions.iter_mut().for_each(|(name, (_, nib))| {
+            trace!("Folding constants in fn {}", name);
+            fold_fn(nib, globals)
+        });
+    }
+
     ///
     /// Registers a struct type with the quill
     /// Struct types have fields enumerated by index, not by name
@@ -226,6 +318,36 @@ impl Quill {
         }
     }
 
+    ///
+    /// Registers a vtable, which is a global struct containing a pointer to each of the given functions
+    /// Each field of the vtable's struct type must match the name of one of the functions
+    ///
+    /// Params:
+    /// * `name` - The name of the vtable's global
+    /// * `struct_t` - The struct type of the vtable
+    /// * `fns` - The name of each function in the vtable, indexed by field name
+    ///
+    pub fn register_vtable(
+        &mut self,
+        name: String,
+        struct_t: QuillStructType,
+        fns: HashMap<String, String>,
+    ) {
+        self.vtables
+            .insert(name, VTableDefinition { struct_t, fns });
+    }
+
+    ///
+    /// Registers a global constant, which fns can read through a pointer from Nib::get_global
+    ///
+    /// Params:
+    /// * `name` - The name of the global
+    /// * `value` - The value the global always holds
+    ///
+    pub fn register_global(&mut self, name: String, value: QuillConstant) {
+        self.globals.insert(name, value);
+    }
+
     pub fn has_fn(&self, name: &str) -> bool {
         match self.functions.get(name) {
             Some(_) => true,
@@ -250,6 +372,104 @@ impl ArtifactType {
     }
 }
 
+///
+/// How much llvm should optimize the code it builds, mirroring clang's -O flags
+///
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum OptLevel {
+    O0,
+    O1,
+    O2,
+    O3,
+    Os,
+}
+
+impl OptLevel {
+    ///
+    /// Gets the flag that tells clang to optimize at this level
+    ///
+    pub fn get_flag(&self) -> &str {
+        match self {
+            OptLevel::O0 => "-O0",
+            OptLevel::O1 => "-O1",
+            OptLevel::O2 => "-O2",
+            OptLevel::O3 => "-O3",
+            OptLevel::Os => "-Os",
+        }
+    }
+
+    fn get_llvm_level(&self) -> OptimizationLevel {
+        match self {
+            OptLevel::O0 => OptimizationLevel::None,
+            OptLevel::O1 => OptimizationLevel::Less,
+            OptLevel::O2 | OptLevel::Os => OptimizationLevel::Default,
+            OptLevel::O3 => OptimizationLevel::Aggressive,
+        }
+    }
+
+    ///
+    /// Gets how many instructions a fn can have and still be inlined, if fns should be inlined at all
+    /// These match the thresholds clang uses for each level
+    ///
+    fn get_inline_threshold(&self) -> Option<u32> {
+        match self {
+            OptLevel::O0 | OptLevel::O1 => None,
+            OptLevel::O2 => Some(225),
+            OptLevel::O3 => Some(250),
+            OptLevel::Os => Some(75),
+        }
+    }
+}
+
+impl FromStr for OptLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().trim_start_matches('o') {
+            "0" => Ok(Self::O0),
+            "1" => Ok(Self::O1),
+            "2" => Ok(Self::O2),
+            "3" => Ok(Self::O3),
+            "s" => Ok(Self::Os),
+            _ => Err(format!("Could not parse {} as an optimization level", s)),
+        }
+    }
+}
+
+///
+/// Runs llvm's optimization passes over a module, the same ones clang would run at the given level
+///
+/// Params:
+/// * `module` - The module to optimize
+/// * `opt_level` - How much to optimize the module
+///
+fn optimize(module: &Module, opt_level: &OptLevel) {
+    if let OptLevel::O0 = opt_level {
+        return;
+    }
+    debug!("Optimizing at {}", opt_level.get_flag());
+    let builder = PassManagerBuilder::create();
+    builder.set_optimization_level(opt_level.get_llvm_level());
+    if let OptLevel::Os = opt_level {
+        builder.set_size_level(1);
+    }
+    if let Some(threshold) = opt_level.get_inline_threshold() {
+        builder.set_inliner_with_threshold(threshold);
+    }
+
+    let fn_passes = PassManager::<FunctionValue>::create(module);
+    builder.populate_function_pass_manager(&fn_passes);
+    fn_passes.initialize();
+    module.get_functions().for_each(|fn_val| {
+        fn_passes.run_on(&fn_val);
+    });
+    fn_passes.finalize();
+
+    let module_passes = PassManager::<Module>::create(());
+    builder.populate_module_pass_manager(&module_passes);
+    module_passes.run_on(module);
+}
+
 // map of name -> (type, index)
 #[derive(Debug, Clone)]
 pub(super) struct StructDefinition(HashMap<String, (PolyQuillType, u32)>);
@@ -275,6 +495,21 @@ impl StructDefinition {
             .1)
     }
 
+    ///
+    /// Returns the names of every field, in index order
+    ///
+    fn get_names(&self) -> Vec<String> {
+        let mut names: Vec<Option<String>> =
+            (0..self.0.len()).fold(vec![], |names, _| names.fpush(None));
+        self.0
+            .iter()
+            .for_each(|(name, (_, i))| names.replace(*i as usize, Some(name.clone())));
+        names
+            .into_iter()
+            .map(|name_opt| name_opt.unwrap())
+            .collect()
+    }
+
     fn get_types(&self) -> Vec<PolyQuillType> {
         let mut pqts: Vec<Option<PolyQuillType>> =
             (0..self.0.len()).fold(vec![], |pqts, _| pqts.fpush(None));
@@ -284,3 +519,27 @@ impl StructDefinition {
         pqts.into_iter().map(|pqt_opt| pqt_opt.unwrap()).collect()
     }
 }
+
+///
+/// A value that is known at compile time, such as the value of a global constant
+///
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum QuillConstant {
+    /// An int with the given bit width and value
+    Int(u32, u64),
+    Bool(bool),
+}
+impl QuillConstant {
+    pub fn get_type(&self) -> PolyQuillType {
+        match self {
+            QuillConstant::Int(bits, _) => QuillIntType::new(*bits).into(),
+            QuillConstant::Bool(_) => QuillBoolType::new().into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct VTableDefinition {
+    struct_t: QuillStructType,
+    fns: HashMap<String, String>,
+}
@@ -1,7 +1,8 @@
 use crate::quill::{QuillError, QuillValue, Result};
+use crate::util::ListFunctional;
 use inkwell::context::Context;
 use inkwell::module::Module;
-use inkwell::types::{BasicType, BasicTypeEnum};
+use inkwell::types::{BasicMetadataTypeEnum, BasicType, BasicTypeEnum, FunctionType};
 use inkwell::AddressSpace;
 use std::convert::TryFrom;
 use std::fmt::Debug;
@@ -236,9 +237,29 @@ impl QuillFnType {
         &self.params
     }
 
-    pub(super) fn get_ret_type(&self) -> &Option<PolyQuillType> {
+    pub fn get_ret_type(&self) -> &Option<PolyQuillType> {
         &self.return_type
     }
+
+    ///
+    /// Get this type as an llvm function type
+    /// Unlike the other quill types, function types are not basic types, so they need their own conversion
+    ///
+    pub(super) fn as_llvm_fn_type<'ctx>(
+        &self,
+        context: &'ctx Context,
+        module: &Module<'ctx>,
+    ) -> Result<FunctionType<'ctx>> {
+        let params = self.params.iter().try_fold(vec![], |params, (_, param)| {
+            Result::Ok(params.fpush(BasicMetadataTypeEnum::from(
+                param.as_llvm_type(context, module)?,
+            )))
+        })?;
+        Ok(match self.get_ret_type() {
+            None => context.void_type().fn_type(&params, false),
+            Some(pqt) => pqt.as_llvm_type(context, module)?.fn_type(&params, false),
+        })
+    }
 }
 impl QuillType for QuillFnType {
     fn as_llvm_type<'ctx>(
@@ -262,6 +283,13 @@ impl QuillPointerType {
     pub fn get_inner_type(&self) -> PolyQuillType {
         *self.0.clone()
     }
+
+    ///
+    /// A pointer that may point to a value of any type, equivalent to a void pointer in C
+    ///
+    pub fn opaque() -> Self {
+        Self::new(QuillIntType::new(8))
+    }
 }
 impl QuillType for QuillPointerType {
     fn as_llvm_type<'ctx>(
@@ -269,11 +297,17 @@ impl QuillType for QuillPointerType {
         context: &'ctx Context,
         module: &Module<'ctx>,
     ) -> Result<BasicTypeEnum<'ctx>> {
-        Ok(self
-            .get_inner_type()
-            .as_llvm_type(context, module)?
-            .ptr_type(AddressSpace::Generic)
-            .as_basic_type_enum())
+        Ok(match self.get_inner_type() {
+            // Function types aren't basic types, but pointers to them are
+            PolyQuillType::FnType(ft) => ft
+                .as_llvm_fn_type(context, module)?
+                .ptr_type(AddressSpace::Generic)
+                .as_basic_type_enum(),
+            inner => inner
+                .as_llvm_type(context, module)?
+                .ptr_type(AddressSpace::Generic)
+                .as_basic_type_enum(),
+        })
     }
 }
 
@@ -9,6 +9,12 @@ pub fn string_struct_name() -> Ident {
 pub fn bool_struct_name() -> Ident {
     Ident::from("Bool")
 }
+pub fn char_struct_name() -> Ident {
+    Ident::from("Char")
+}
+pub fn byte_struct_name() -> Ident {
+    Ident::from("Byte")
+}
 pub fn list_struct_name() -> Ident {
     Ident::from("List")
 }
@@ -48,9 +54,45 @@ pub fn get_fn_name() -> Ident {
 pub fn length_fn_name() -> Ident {
     Ident::from("len")
 }
+pub fn push_fn_name() -> Ident {
+    Ident::from("push")
+}
+pub fn set_fn_name() -> Ident {
+    Ident::from("set")
+}
+pub fn truncate_fn_name() -> Ident {
+    Ident::from("__truncate__")
+}
 pub fn inner_add_fn_name() -> Ident {
     Ident::from("__inner_add__")
 }
+pub fn new_string_name() -> Ident {
+    Ident::from("__new_string__")
+}
+pub fn push_str_fn_name() -> Ident {
+    Ident::from("__push_str__")
+}
+pub fn concat_fn_name() -> Ident {
+    Ident::from("__concat__")
+}
+pub fn map_of_fn_name() -> Ident {
+    Ident::from("__map_of__")
+}
+pub fn byte_at_fn_name() -> Ident {
+    Ident::from("__byte_at__")
+}
+pub fn push_byte_fn_name() -> Ident {
+    Ident::from("__push_byte__")
+}
+pub fn slice_fn_name() -> Ident {
+    Ident::from("__slice__")
+}
+pub fn matches_at_fn_name() -> Ident {
+    Ident::from("__matches_at__")
+}
+pub fn bytes_lt_fn_name() -> Ident {
+    Ident::from("__bytes_lt__")
+}
 
 pub fn operator_add_name() -> Ident {
     Ident::from("operatorAdd")
@@ -89,3 +131,81 @@ pub fn operator_rsh_name() -> Ident {
 pub fn to_string_name() -> Ident {
     Ident::from("toString")
 }
+pub fn hash_fn_name() -> Ident {
+    Ident::from("hash")
+}
+pub fn drop_fn_name() -> Ident {
+    Ident::from("drop")
+}
+pub fn to_int_fn_name() -> Ident {
+    Ident::from("toInt")
+}
+pub fn to_byte_fn_name() -> Ident {
+    Ident::from("toByte")
+}
+pub fn as_char_fn_name() -> Ident {
+    Ident::from("__as_char__")
+}
+
+pub fn refcount_field_name() -> Ident {
+    Ident::from("__refcount__")
+}
+pub fn release_field_name() -> Ident {
+    Ident::from("__release__")
+}
+pub fn live_objects_fn_name() -> Ident {
+    Ident::from("__live_objects__")
+}
+pub fn live_objects_global_name() -> Ident {
+    Ident::from("_LIVE_OBJECTS")
+}
+
+pub fn interface_data_field_name() -> Ident {
+    Ident::from("data")
+}
+pub fn interface_vtable_field_name() -> Ident {
+    Ident::from("vtable")
+}
+pub fn self_type_name() -> Ident {
+    Ident::from("Self")
+}
+pub fn self_var_name() -> Ident {
+    Ident::from("self")
+}
+
+pub fn printable_interface_name() -> Ident {
+    Ident::from("Printable")
+}
+pub fn equable_interface_name() -> Ident {
+    Ident::from("Equable")
+}
+pub fn comparable_interface_name() -> Ident {
+    Ident::from("Comparable")
+}
+pub fn hashable_interface_name() -> Ident {
+    Ident::from("Hashable")
+}
+pub fn drop_interface_name() -> Ident {
+    Ident::from("Drop")
+}
+pub fn vtable_struct_name(interface_name: &str) -> Ident {
+    format!("_VTABLE_{}", interface_name)
+}
+pub fn vtable_name(interface_name: &str, mangled_struct_name: &str) -> Ident {
+    format!("_VTABLE_{}{}", interface_name, mangled_struct_name)
+}
+pub fn release_fn_name(mangled_struct_name: &str) -> Ident {
+    format!("_RELEASE{}", mangled_struct_name)
+}
+pub fn unbox_fn_name(mangled_fn_name: &str) -> Ident {
+    format!("_UNBOX{}", mangled_fn_name)
+}
+pub fn const_fn_name(const_name: &str) -> Ident {
+    format!("_CONST_{}", const_name)
+}
+pub fn const_global_name(const_name: &str) -> Ident {
+    format!("_GLOBAL_{}", const_name)
+}
+pub fn assoc_const_name(struct_name: &str, const_name: &str) -> Ident {
+    format!("{}::{}", struct_name, const_name)
+}